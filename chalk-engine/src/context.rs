@@ -5,14 +5,15 @@
 //! `DomainGoal` type, add arena lifetime parameters, and more. See
 //! [`Context`] trait for a list of types.
 
-use crate::{CompleteAnswer, ExClause};
+use crate::{CompleteAnswer, ExClause, FlounderedCause};
 use chalk_ir::interner::Interner;
 use chalk_ir::{
     AnswerSubst, Binders, Canonical, ConstrainedSubst, Constraint, DomainGoal, Environment,
     Fallible, Floundered, GenericArg, Goal, InEnvironment, ProgramClause, ProgramClauses,
-    Substitution, UCanonical, UniverseMap,
+    Substitution, Ty, UCanonical, UnificationDatabase, UniverseMap, Variance,
 };
 use std::fmt::Debug;
+use std::hash::Hash;
 
 /// The "context" in which the SLG solver operates. It defines all the
 /// types that the SLG solver may need to refer to, as well as a few
@@ -37,6 +38,12 @@ pub trait Context<I: Interner>: Clone + Debug {
     /// Represents an inference table.
     type InferenceTable: InferenceTable<I, Self> + Clone;
 
+    /// An item of the program that program clauses are derived from, such
+    /// as a trait or an impl. Each table remembers the items its answers
+    /// depend on, so that `Forest::invalidate` can tell which tables are
+    /// still valid after the program changes.
+    type Dependency: Clone + Debug + Eq + Hash;
+
     /// Selects the next appropriate subgoal index for evaluation.
     /// Used by: logic
     fn next_subgoal_index(ex_clause: &ExClause<I>) -> usize;
@@ -53,12 +60,16 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
     /// of program clauses cannot be enumerated because there are
     /// unresolved type variables that would have to be resolved
     /// first; the goal will be considered floundered.
+    ///
+    /// The items the clauses were derived from are pushed onto
+    /// `dependencies`.
     fn program_clauses(
         &self,
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
         infer: &mut C::InferenceTable,
-    ) -> Result<Vec<ProgramClause<I>>, Floundered>;
+        dependencies: &mut Vec<C::Dependency>,
+    ) -> Result<Vec<ProgramClause<I>>, Floundered<I>>;
 
     // Used by: simplify
     fn add_clauses(&self, env: &Environment<I>, clauses: ProgramClauses<I>) -> Environment<I>;
@@ -123,8 +134,20 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
         value: &Canonical<AnswerSubst<I>>,
     ) -> Canonical<AnswerSubst<I>>;
 
+    /// Records that the engine did some `work` for the current query, which
+    /// shows up in its statistics and counts against its budget, if it has
+    /// one.
+    fn charge(&self, work: Work);
+
+    /// True once the current query has used up its budget. The engine then
+    /// stops with `QuantumExceeded` before pursuing another strand.
+    fn budget_exhausted(&self) -> bool;
+
     fn interner(&self) -> &I;
 
+    /// The database used to look up variances when relating types.
+    fn unification_database(&self) -> &dyn UnificationDatabase<I>;
+
     /// Upcast this domain goal into a more general goal.
     fn into_goal(&self, domain_goal: DomainGoal<I>) -> Goal<I>;
 
@@ -176,6 +199,13 @@ pub trait UnificationOps<I: Interner, C: Context<I>> {
         value: &ExClause<I>,
     ) -> Canonical<ExClause<I>>;
 
+    // Used by: logic (when recording derivations)
+    fn canonicalize_clause(
+        &mut self,
+        interner: &I,
+        value: &InEnvironment<ProgramClause<I>>,
+    ) -> Canonical<InEnvironment<ProgramClause<I>>>;
+
     // Used by: logic
     fn canonicalize_constrained_subst(
         &mut self,
@@ -214,6 +244,23 @@ pub trait UnificationOps<I: Interner, C: Context<I>> {
         b: &GenericArg<I>,
         ex_clause: &mut ExClause<I>,
     ) -> Fallible<()>;
+
+    /// Relate the types `a` and `b` with the given variance, then add
+    /// the residual subgoals as new subgoals of the ex-clause.
+    /// Also add region constraints.
+    ///
+    /// If the types fail to relate, then `Error` is returned
+    // Used by: simplify
+    fn relate_tys_into_ex_clause(
+        &mut self,
+        interner: &I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+        ex_clause: &mut ExClause<I>,
+    ) -> Fallible<()>;
 }
 
 /// "Truncation" (called "abstraction" in the papers referenced below)
@@ -261,6 +308,22 @@ pub trait ResolventOps<I: Interner, C: Context<I>> {
     ) -> Fallible<()>;
 }
 
+/// A unit of work done by the engine; see `ContextOps::charge`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Work {
+    /// A strand was pursued.
+    Strand,
+
+    /// A table was created for a new goal.
+    Table,
+
+    /// A program clause was instantiated to create a strand.
+    ClauseInstantiation,
+
+    /// An answer was added to a table.
+    Answer,
+}
+
 pub enum AnswerResult<I: Interner> {
     /// The next available answer.
     Answer(CompleteAnswer<I>),
@@ -268,8 +331,9 @@ pub enum AnswerResult<I: Interner> {
     /// No answer could be returned because there are no more solutions.
     NoMoreSolutions,
 
-    /// No answer could be returned because the goal has floundered.
-    Floundered,
+    /// No answer could be returned because the goal has floundered. Carries
+    /// the cause, which may concern a subgoal of the goal itself.
+    Floundered(FlounderedCause<I>),
 
     // No answer could be returned *yet*, because we exceeded our
     // quantum (`should_continue` returned false).
@@ -310,7 +374,7 @@ impl<I: Interner> Debug for AnswerResult<I> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AnswerResult::Answer(answer) => write!(fmt, "{:?}", answer),
-            AnswerResult::Floundered => write!(fmt, "Floundered"),
+            AnswerResult::Floundered(_) => write!(fmt, "Floundered"),
             AnswerResult::NoMoreSolutions => write!(fmt, "None"),
             AnswerResult::QuantumExceeded => write!(fmt, "QuantumExceeded"),
         }
@@ -2,11 +2,12 @@ use crate::context::{AnswerResult, AnswerStream, Context, ContextOps};
 use crate::logic::RootSearchFail;
 use crate::table::AnswerIndex;
 use crate::tables::Tables;
-use crate::{TableIndex, TimeStamp};
+use crate::{Answer, TableIndex, TimeStamp};
 
-use chalk_ir::debug;
 use chalk_ir::interner::Interner;
 use chalk_ir::{Goal, InEnvironment, Substitution, UCanonical};
+use rustc_hash::FxHashSet;
+use tracing::debug;
 
 pub struct Forest<I: Interner, C: Context<I>> {
     pub(crate) tables: Tables<I>,
@@ -16,24 +17,131 @@ pub struct Forest<I: Interner, C: Context<I>> {
     /// This effectively gives us way to track what depth
     /// and loop a table or strand was last followed.
     pub(crate) clock: TimeStamp,
+
+    /// For each table, what its answers were derived from.
+    pub(crate) dependencies: Vec<TableDependencies<C::Dependency>>,
+
+    /// Whether answers record how they were derived; see
+    /// `record_derivations`.
+    pub(crate) record_derivations: bool,
     _context: std::marker::PhantomData<C>,
 }
 
+/// What the answers of a table were derived from; see `Forest::invalidate`.
+pub(crate) struct TableDependencies<D> {
+    /// The items that the program clauses of the table came from.
+    pub(crate) items: Vec<D>,
+
+    /// The tables whose answers the strands of the table used.
+    pub(crate) tables: FxHashSet<TableIndex>,
+}
+
 impl<I: Interner, C: Context<I>> Forest<I, C> {
     pub fn new() -> Self {
         Forest {
             tables: Tables::new(),
             clock: TimeStamp::default(),
+            dependencies: Vec::new(),
+            record_derivations: false,
             _context: std::marker::PhantomData,
         }
     }
 
+    /// Makes all answers found from now on record how they were derived,
+    /// see `Answer::derivation`. The answers found so far did not, so the
+    /// first call drops all tables.
+    pub fn record_derivations(&mut self) {
+        if !self.record_derivations {
+            *self = Forest::new();
+            self.record_derivations = true;
+        }
+    }
+
+    /// The table for `goal`, if there is one.
+    pub fn table_index(&self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> Option<TableIndex> {
+        self.tables.index_of(goal)
+    }
+
+    /// The goal of `table`.
+    pub fn table_goal(&self, table: TableIndex) -> &UCanonical<InEnvironment<Goal<I>>> {
+        &self.tables[table].table_goal
+    }
+
+    /// The answer of `table` with the given index, if it has been found.
+    pub fn table_answer(&self, table: TableIndex, answer: AnswerIndex) -> Option<&Answer<I>> {
+        self.tables[table].answer(answer)
+    }
+
     // Gets the next clock TimeStamp. This will never decrease.
     pub(crate) fn increment_clock(&mut self) -> TimeStamp {
         self.clock.increment();
         self.clock
     }
 
+    /// Records that the strands of `table` used the answers of `subgoal_table`.
+    pub(crate) fn record_dependency(&mut self, table: TableIndex, subgoal_table: TableIndex) {
+        self.dependencies[table.value].tables.insert(subgoal_table);
+    }
+
+    /// Prepares the forest for solving goals against a changed program, in
+    /// which the items for which `changed` returns true may have different
+    /// program clauses. Drops every table whose answers may have been
+    /// derived from those items, directly or through the answers of other
+    /// tables, as well as the tables that are not complete yet. The
+    /// remaining tables keep their answers, so later queries can reuse them.
+    pub fn invalidate(&mut self, changed: impl Fn(&C::Dependency) -> bool) {
+        let num_tables = self.dependencies.len();
+
+        // For each table, the tables that used its answers.
+        let mut users = vec![vec![]; num_tables];
+        for (value, dependencies) in self.dependencies.iter().enumerate() {
+            for table in &dependencies.tables {
+                users[table.value].push(value);
+            }
+        }
+
+        let mut invalid = vec![false; num_tables];
+        let mut worklist: Vec<usize> = (0..num_tables)
+            .filter(|&value| {
+                !self.tables[TableIndex { value }].is_complete()
+                    || self.dependencies[value].items.iter().any(&changed)
+            })
+            .collect();
+        while let Some(value) = worklist.pop() {
+            if !invalid[value] {
+                invalid[value] = true;
+                worklist.extend(&users[value]);
+            }
+        }
+        debug!(
+            "invalidated {} of {} tables",
+            invalid.iter().filter(|&&invalid| invalid).count(),
+            num_tables
+        );
+
+        // Since the users of an invalid table are invalid too, the tables
+        // that remain only depend on each other.
+        let new_indices = self.tables.retain(|table| !invalid[table.value]);
+        let new_index = |table: TableIndex| new_indices[table.value].unwrap();
+        for value in 0..self.tables.next_index().value {
+            self.tables[TableIndex { value }].map_derivation_tables(new_index);
+        }
+        let dependencies = std::mem::take(&mut self.dependencies);
+        self.dependencies = dependencies
+            .into_iter()
+            .zip(&new_indices)
+            .filter(|(_, new_index)| new_index.is_some())
+            .map(|(dependencies, _)| TableDependencies {
+                items: dependencies.items,
+                tables: dependencies
+                    .tables
+                    .iter()
+                    .map(|&table| new_index(table))
+                    .collect(),
+            })
+            .collect();
+    }
+
     /// Returns a "solver" for a given goal in the form of an
     /// iterator. Each time you invoke `next`, it will do the work to
     /// extract one more answer. These answers are cached in between
@@ -84,7 +192,11 @@ impl<'me, I: Interner, C: Context<I>, CO: ContextOps<I, C>> AnswerStream<I>
                     self.answer.increment();
                 }
                 Err(RootSearchFail::Floundered) => {
-                    return AnswerResult::Floundered;
+                    let cause = self.forest.tables[self.table]
+                        .floundered_cause()
+                        .expect("root search floundered without a floundered table")
+                        .clone();
+                    return AnswerResult::Floundered(cause);
                 }
 
                 Err(RootSearchFail::NoMoreSolutions) => {
@@ -92,7 +204,7 @@ impl<'me, I: Interner, C: Context<I>, CO: ContextOps<I, C>> AnswerStream<I>
                 }
 
                 Err(RootSearchFail::QuantumExceeded) => {
-                    if !should_continue() {
+                    if !should_continue() || self.context.budget_exhausted() {
                         return AnswerResult::QuantumExceeded;
                     }
                 }
@@ -60,8 +60,8 @@ use chalk_derive::{Fold, HasInterner, Visit};
 use chalk_ir::interner::{Interner, TargetInterner};
 use chalk_ir::visit::VisitResult;
 use chalk_ir::{
-    AnswerSubst, Canonical, ConstrainedSubst, Constraint, DebruijnIndex, Goal, InEnvironment,
-    Substitution,
+    AnswerSubst, Canonical, ConstrainedSubst, Constraint, DebruijnIndex, FlounderedGoal, Goal,
+    InEnvironment, ProgramClause, Substitution,
 };
 
 pub mod context;
@@ -74,6 +74,8 @@ mod strand;
 mod table;
 mod tables;
 
+pub use table::AnswerIndex;
+
 index_struct! {
     pub struct TableIndex { // FIXME: pub b/c Fold
         value: usize,
@@ -173,6 +175,22 @@ pub struct FlounderedSubgoal<I: Interner> {
     /// Current value of the strand's clock at the time of
     /// floundering.
     pub floundered_time: TimeStamp,
+
+    /// Why the literal floundered, if known. The cause is either the
+    /// literal itself or one of the goals it depends on.
+    pub floundered_cause: Option<FlounderedCause<I>>,
+}
+
+/// Why a table, an answer or a subgoal floundered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+pub enum FlounderedCause<I: Interner> {
+    /// A goal whose solutions cannot be enumerated.
+    Goal(FlounderedGoal<I>),
+
+    /// A goal or an answer got too large (see `TruncateOps`). Holds the
+    /// chain of goals that led to it, outermost first; the last goal is
+    /// the one that got too large or that had a too large answer.
+    Overflow(Vec<Canonical<InEnvironment<Goal<I>>>>),
 }
 
 /// An "answer" in the on-demand solver corresponds to a fully solved
@@ -190,6 +208,54 @@ pub struct Answer<I: Interner> {
     /// nor disproven. This could be the size of the answer exceeded
     /// `max_size` or because of a negative loop (e.g., `P :- not { P }`).
     pub ambiguous: bool,
+
+    /// If the answer is ambiguous because some subgoal floundered, why it
+    /// floundered.
+    pub floundered: Option<FlounderedCause<I>>,
+
+    /// How the answer was derived, if the forest records derivations (see
+    /// `Forest::record_derivations`).
+    pub derivation: Option<Derivation<I>>,
+}
+
+/// How an answer was derived: the program clause that was applied to the
+/// goal of its table, and the answers to the subgoals that this gave rise
+/// to, in the order they were found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Derivation<I: Interner> {
+    /// The clause, canonicalized together with the environment of the
+    /// table goal. `None` if the table goal is not a domain goal, and was
+    /// simplified into its subgoals instead.
+    pub clause: Option<Canonical<InEnvironment<ProgramClause<I>>>>,
+
+    pub subgoals: Vec<SubgoalDerivation>,
+}
+
+impl<I: Interner> Derivation<I> {
+    /// Records that a positive subgoal was proven by the given answer of
+    /// its table. If the subgoal was delayed before, this replaces it.
+    pub(crate) fn push_answer(&mut self, table: TableIndex, answer: AnswerIndex) {
+        let subgoal = SubgoalDerivation::Answer(table, answer);
+        let delayed = SubgoalDerivation::Delayed(table);
+        match self.subgoals.iter_mut().find(|s| **s == delayed) {
+            Some(s) => *s = subgoal,
+            None => self.subgoals.push(subgoal),
+        }
+    }
+}
+
+/// How a subgoal in a `Derivation` was resolved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SubgoalDerivation {
+    /// A positive subgoal, proven by an answer of its table.
+    Answer(TableIndex, AnswerIndex),
+
+    /// A negative subgoal, whose table has no answers.
+    Refuted(TableIndex),
+
+    /// A coinductive subgoal that was delayed, because its table was
+    /// being solved already.
+    Delayed(TableIndex),
 }
 
 #[derive(Clone, Debug)]
@@ -203,6 +269,10 @@ pub struct CompleteAnswer<I: Interner> {
     /// nor disproven. This could be the size of the answer exceeded
     /// `max_size` or because of a negative loop (e.g., `P :- not { P }`).
     pub ambiguous: bool,
+
+    /// If the answer is ambiguous because some subgoal floundered, why it
+    /// floundered.
+    pub floundered: Option<FlounderedCause<I>>,
 }
 
 /// Either `A` or `~A`, where `A` is a `Env |- Goal`.
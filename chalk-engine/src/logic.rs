@@ -1,21 +1,21 @@
 use crate::context::{
-    Context, ContextOps, InferenceTable, ResolventOps, TruncateOps, UnificationOps,
+    Context, ContextOps, InferenceTable, ResolventOps, TruncateOps, UnificationOps, Work,
 };
-use crate::forest::Forest;
+use crate::forest::{Forest, TableDependencies};
 use crate::stack::{Stack, StackIndex};
 use crate::strand::{CanonicalStrand, SelectedSubgoal, Strand};
 use crate::table::{AnswerIndex, Table};
 use crate::{
-    Answer, AnswerMode, CompleteAnswer, ExClause, FlounderedSubgoal, Literal, Minimums, TableIndex,
-    TimeStamp,
+    Answer, AnswerMode, CompleteAnswer, Derivation, ExClause, FlounderedCause, FlounderedSubgoal,
+    Literal, Minimums, SubgoalDerivation, TableIndex, TimeStamp,
 };
 
 use chalk_ir::interner::Interner;
-use chalk_ir::{debug, debug_heading, info, info_heading};
 use chalk_ir::{
-    Canonical, ConstrainedSubst, DomainGoal, Floundered, Goal, GoalData, InEnvironment, NoSolution,
-    Substitution, UCanonical, UniverseMap, WhereClause,
+    Canonical, ConstrainedSubst, DomainGoal, Floundered, FlounderedGoal, Goal, GoalData,
+    InEnvironment, NoSolution, Substitution, UCanonical, UniverseMap, WhereClause,
 };
+use tracing::{debug, debug_span, info, info_span};
 
 type RootSearchResult<T> = Result<T, RootSearchFail>;
 
@@ -106,6 +106,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                         },
                     },
                     ambiguous: answer.ambiguous,
+                    floundered: answer.floundered.clone(),
                 })
             }
             Err(err) => Err(err),
@@ -141,6 +142,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             ex_clause,
             selected_subgoal,
             last_pursued_time,
+            derivation,
         } = strand;
         Forest::canonicalize_strand_from(
             context,
@@ -148,6 +150,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             &ex_clause,
             selected_subgoal,
             last_pursued_time,
+            derivation,
         )
     }
 
@@ -157,12 +160,14 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         ex_clause: &ExClause<I>,
         selected_subgoal: Option<SelectedSubgoal>,
         last_pursued_time: TimeStamp,
+        derivation: Option<Derivation<I>>,
     ) -> CanonicalStrand<I> {
         let canonical_ex_clause = infer.canonicalize_ex_clause(context.interner(), &ex_clause);
         CanonicalStrand {
             canonical_ex_clause,
             selected_subgoal,
             last_pursued_time,
+            derivation,
         }
     }
 
@@ -185,7 +190,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         infer: &mut dyn InferenceTable<I, C>,
         subgoal: &Literal<I>,
     ) -> Option<(TableIndex, UniverseMap)> {
-        debug_heading!("get_or_create_table_for_subgoal(subgoal={:?})", subgoal);
+        let _span = debug_span!("get_or_create_table_for_subgoal", ?subgoal).entered();
 
         // Subgoal abstraction:
         let (ucanonical_subgoal, universe_map) = match subgoal {
@@ -217,19 +222,21 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         context: &impl ContextOps<I, C>,
         goal: UCanonical<InEnvironment<Goal<I>>>,
     ) -> TableIndex {
-        debug_heading!("get_or_create_table_for_ucanonical_goal({:?})", goal);
+        let _span = debug_span!("get_or_create_table_for_ucanonical_goal", ?goal).entered();
 
         if let Some(table) = self.tables.index_of(&goal) {
             debug!("found existing table {:?}", table);
             return table;
         }
 
-        info_heading!(
-            "creating new table {:?} and goal {:#?}",
-            self.tables.next_index(),
-            goal
-        );
-        let table = Self::build_table(context, self.tables.next_index(), goal);
+        let table = self.tables.next_index();
+        let _span = info_span!("build_table", ?table).entered();
+        info!(name: "goal_entered", ?table, ?goal, "goal entered");
+        let (table, items) = Self::build_table(context, table, goal, self.record_derivations);
+        self.dependencies.push(TableDependencies {
+            items,
+            tables: Default::default(),
+        });
         self.tables.insert(table)
     }
 
@@ -244,29 +251,41 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     /// In terms of the NFTD paper, this corresponds to the *Program
     /// Clause Resolution* step being applied eagerly, as many times
     /// as possible.
+    ///
+    /// Also returns the items that the program clauses came from. If
+    /// `record_derivations` is set, each strand starts a `Derivation`.
     fn build_table(
         context: &impl ContextOps<I, C>,
         table_idx: TableIndex,
         goal: UCanonical<InEnvironment<Goal<I>>>,
-    ) -> Table<I> {
+        record_derivations: bool,
+    ) -> (Table<I>, Vec<C::Dependency>) {
+        context.charge(Work::Table);
         let mut table = Table::new(goal.clone(), context.is_coinductive(&goal));
         let (mut infer, subst, environment, goal) = context.instantiate_ucanonical_goal(&goal);
         let goal_data = goal.data(context.interner());
+        let mut items = vec![];
 
         let is_outlives_goal = |dg: &DomainGoal<I>| {
-            if let DomainGoal::Holds(WhereClause::LifetimeOutlives(_)) = dg {
-                true
-            } else {
-                false
-            }
+            matches!(
+                dg,
+                DomainGoal::Holds(WhereClause::LifetimeOutlives(_))
+                    | DomainGoal::Holds(WhereClause::TypeOutlives(_))
+            )
         };
 
         match goal_data {
             GoalData::DomainGoal(domain_goal) if !is_outlives_goal(domain_goal) => {
-                match context.program_clauses(&environment, &domain_goal, &mut infer) {
+                match context.program_clauses(&environment, &domain_goal, &mut infer, &mut items) {
                     Ok(clauses) => {
                         for clause in clauses {
-                            info!("program clause = {:#?}", clause);
+                            info!(
+                                name: "clause_tried",
+                                table = ?table_idx,
+                                ?clause,
+                                "clause tried"
+                            );
+                            context.charge(Work::ClauseInstantiation);
                             let mut infer = infer.clone();
                             if let Ok(resolvent) = infer.resolvent_clause(
                                 context.interner(),
@@ -276,23 +295,43 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                                 &clause,
                             ) {
                                 info!("pushing initial strand with ex-clause: {:#?}", &resolvent,);
+                                let derivation = if record_derivations {
+                                    let clause = InEnvironment::new(&environment, clause);
+                                    Some(Derivation {
+                                        clause: Some(
+                                            infer.canonicalize_clause(context.interner(), &clause),
+                                        ),
+                                        subgoals: vec![],
+                                    })
+                                } else {
+                                    None
+                                };
                                 let strand = Strand {
                                     infer,
                                     ex_clause: resolvent,
                                     selected_subgoal: None,
                                     last_pursued_time: TimeStamp::default(),
+                                    derivation,
                                 };
                                 let canonical_strand = Self::canonicalize_strand(context, strand);
                                 table.enqueue_strand(canonical_strand);
                             }
                         }
                     }
-                    Err(Floundered) => {
+                    Err(Floundered { ty }) => {
                         debug!(
                             "Marking table {:?} as floundered! (failed to create program clauses)",
                             table_idx
                         );
-                        table.mark_floundered();
+                        let interner = context.interner();
+                        let variable = subst
+                            .iter(interner)
+                            .position(|arg| arg.ty(interner) == Some(&ty));
+                        let goal = table.table_goal.canonical.clone();
+                        table.mark_floundered(FlounderedCause::Goal(FlounderedGoal {
+                            goal,
+                            variable,
+                        }));
                     }
                 }
             }
@@ -313,11 +352,20 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                         "pushing initial strand with ex-clause: {:#?}",
                         infer.debug_ex_clause(context.interner(), &ex_clause),
                     );
+                    let derivation = if record_derivations {
+                        Some(Derivation {
+                            clause: None,
+                            subgoals: vec![],
+                        })
+                    } else {
+                        None
+                    };
                     let strand = Strand {
                         infer,
                         ex_clause,
                         selected_subgoal: None,
                         last_pursued_time: TimeStamp::default(),
+                        derivation,
                     };
                     let canonical_strand = Self::canonicalize_strand(context, strand);
                     table.enqueue_strand(canonical_strand);
@@ -325,7 +373,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             }
         }
 
-        table
+        (table, items)
     }
 
     /// Given a selected positive subgoal, applies the subgoal
@@ -441,11 +489,12 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         initial_table: TableIndex,
         initial_answer: AnswerIndex,
     ) -> RootSearchResult<()> {
-        info_heading!(
-            "ensure_answer(table={:?}, answer={:?})",
-            initial_table,
-            initial_answer
-        );
+        let _span = info_span!(
+            "ensure_root_answer",
+            table = ?initial_table,
+            answer = ?initial_answer
+        )
+        .entered();
         info!(
             "table goal = {:#?}",
             self.forest.tables[initial_table].table_goal
@@ -472,6 +521,12 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         loop {
             // FIXME: use depth for debug/info printing
 
+            // Dropping `self` puts the active strand back into its table, so
+            // the search can resume once the query gets more budget.
+            if self.context.budget_exhausted() {
+                return Err(RootSearchFail::QuantumExceeded);
+            }
+
             let clock = self.stack.top().clock;
             // If we had an active strand, continue to pursue it
             let table = self.stack.top().table;
@@ -503,6 +558,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             canonical_ex_clause,
                             selected_subgoal,
                             last_pursued_time,
+                            derivation,
                         } = canonical_strand;
                         let (infer, ex_clause) =
                             context.instantiate_ex_clause(num_universes, &canonical_ex_clause);
@@ -511,6 +567,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             ex_clause,
                             selected_subgoal: selected_subgoal.clone(),
                             last_pursued_time,
+                            derivation,
                         };
                         strand
                     })
@@ -518,6 +575,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             match next_strand {
                 Some(mut strand) => {
                     debug!("next strand: {:#?}", strand);
+                    self.context.charge(Work::Strand);
 
                     strand.last_pursued_time = clock;
                     match self.select_subgoal(&mut strand) {
@@ -559,13 +617,13 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
 
         // If this answer is ambiguous and we don't want ambiguous answers
         // yet, then we act like this is a floundered subgoal.
-        let ambiguous = {
+        let (ambiguous, floundered_cause) = {
             let selected_subgoal = strand.selected_subgoal.as_ref().unwrap();
             let answer = self.forest.answer(
                 selected_subgoal.subgoal_table,
                 selected_subgoal.answer_index,
             );
-            answer.ambiguous
+            (answer.ambiguous, answer.floundered.clone())
         };
         if let AnswerMode::Complete = self.forest.tables[self.stack.top().table].answer_mode {
             if ambiguous {
@@ -585,7 +643,11 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 // The selected subgoal returned an ambiguous answer, but we don't want that.
                 // So, we treat this subgoal as floundered.
                 let selected_subgoal = strand.selected_subgoal.take().unwrap();
-                self.flounder_subgoal(&mut strand.ex_clause, selected_subgoal.subgoal_index);
+                self.flounder_subgoal(
+                    &mut strand.ex_clause,
+                    selected_subgoal.subgoal_index,
+                    floundered_cause,
+                );
                 return Ok(());
             }
         }
@@ -613,6 +675,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                     ex_clause: strand.ex_clause.clone(),
                     selected_subgoal: Some(next_subgoal),
                     last_pursued_time: strand.last_pursued_time.clone(),
+                    derivation: strand.derivation.clone(),
                 };
                 let table = self.stack.top().table;
                 let canonical_next_strand = Forest::canonicalize_strand(self.context, next_strand);
@@ -655,8 +718,13 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             ex_clause,
                             selected_subgoal: _,
                             last_pursued_time: _,
+                            derivation,
                         } = strand;
 
+                        if let Some(derivation) = derivation {
+                            derivation.push_answer(subgoal_table, answer_index);
+                        }
+
                         // If the answer had was ambiguous, we have to
                         // ensure that `ex_clause` is also ambiguous. This is
                         // the SLG FACTOR operation, though NFTD just makes it
@@ -761,7 +829,14 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 // floundered list, along with the time that it
                 // floundered. We'll try to solve some other subgoals
                 // and maybe come back to it.
-                self.flounder_subgoal(&mut strand.ex_clause, selected_subgoal.subgoal_index);
+                let floundered_cause = self.forest.tables[selected_subgoal.subgoal_table]
+                    .floundered_cause()
+                    .cloned();
+                self.flounder_subgoal(
+                    &mut strand.ex_clause,
+                    selected_subgoal.subgoal_index,
+                    floundered_cause,
+                );
 
                 return false;
             }
@@ -815,6 +890,11 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 );
 
                 strand.ex_clause.delayed_subgoals.push(subgoal);
+                if let Some(derivation) = &mut strand.derivation {
+                    derivation
+                        .subgoals
+                        .push(SubgoalDerivation::Delayed(selected_subgoal.subgoal_table));
+                }
 
                 self.stack.top().active_strand = Some(strand);
                 return Ok(());
@@ -930,7 +1010,12 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         // Next, check if the table is already active. If so, then we
         // have a recursive attempt.
         if let Some(cyclic_depth) = self.stack.is_active(subgoal_table) {
-            info!("cycle detected at depth {:?}", cyclic_depth);
+            info!(
+                name: "cycle_detected",
+                table = ?subgoal_table,
+                depth = ?cyclic_depth,
+                "cycle detected"
+            );
             let minimums = Minimums {
                 positive: self.stack[cyclic_depth].clock,
                 negative: TimeStamp::MAX,
@@ -1074,6 +1159,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             },
             selected_subgoal: None,
             last_pursued_time: TimeStamp::default(),
+            derivation: answer.derivation.clone(),
         };
 
         Some(Forest::canonicalize_strand(self.context, strand))
@@ -1092,7 +1178,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             // have **failed**. Hence we can pop it off the stack and
             // check what this means for the table T' that was just
             // below T on the stack (if any).
-            debug!("no more strands available");
+            info!(name: "table_completed", ?table, "table completed");
             let caller_strand = match self.stack.pop_and_borrow_caller_strand() {
                 Some(s) => s,
                 None => {
@@ -1126,6 +1212,9 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                         .ex_clause
                         .subgoals
                         .remove(caller_selected_subgoal.subgoal_index);
+                    if let Some(derivation) = &mut caller_strand.derivation {
+                        derivation.subgoals.push(SubgoalDerivation::Refuted(table));
+                    }
 
                     // This strand is still active, so continue
                     Ok(())
@@ -1185,6 +1274,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             // we know that isn't part of a cycle.
             let cyclic_strands = self.forest.tables[table].drain_strands(strand_is_participating);
             self.clear_strands_after_cycle(cyclic_strands);
+            info!(name: "table_completed", ?table, "table completed");
 
             // Now we yield with `QuantumExceeded`
             self.unwind_stack();
@@ -1265,6 +1355,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 canonical_ex_clause,
                 selected_subgoal,
                 last_pursued_time: _,
+                derivation: _,
             } = strand;
             let selected_subgoal = selected_subgoal.unwrap_or_else(|| {
                 panic!(
@@ -1311,6 +1402,8 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                     &strand.ex_clause.subgoals[subgoal_index],
                 ) {
                     Some((subgoal_table, universe_map)) => {
+                        let table = self.stack.top().table;
+                        self.forest.record_dependency(table, subgoal_table);
                         strand.selected_subgoal = Some(SelectedSubgoal {
                             subgoal_index,
                             subgoal_table,
@@ -1322,8 +1415,16 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                     None => {
                         // If we failed to create a table for the subgoal,
                         // that is because we have a floundered negative
-                        // literal.
-                        self.flounder_subgoal(&mut strand.ex_clause, subgoal_index);
+                        // literal (or a literal that is too large).
+                        let floundered_cause = self.floundered_literal_cause(
+                            &mut strand.infer,
+                            &strand.ex_clause.subgoals[subgoal_index],
+                        );
+                        self.flounder_subgoal(
+                            &mut strand.ex_clause,
+                            subgoal_index,
+                            Some(floundered_cause),
+                        );
                     }
                 }
             }
@@ -1370,6 +1471,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 },
             selected_subgoal: _,
             last_pursued_time: _,
+            derivation,
         } = strand;
         // If there are subgoals left, they should be followed
         assert!(subgoals.is_empty());
@@ -1402,7 +1504,8 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         // down to "it works as we expect for the current tests". And, we likely don't
         // even *need* the added complexity just for potentially more answers.
         if infer.answer_needs_truncation(self.context.interner(), &subst) {
-            self.forest.tables[table].mark_floundered();
+            let cause = self.overflow_cause(None);
+            self.forest.tables[table].mark_floundered(cause);
             return None;
         }
 
@@ -1429,7 +1532,14 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             table, subst, floundered
         );
 
-        let answer = Answer { subst, ambiguous };
+        let answer = Answer {
+            subst,
+            ambiguous,
+            floundered: floundered_subgoals
+                .into_iter()
+                .find_map(|subgoal| subgoal.floundered_cause),
+            derivation,
+        };
 
         // A "trivial" answer is one that is 'just true for all cases'
         // -- in other words, it gives no information back to the
@@ -1496,6 +1606,9 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         };
 
         if let Some(answer_index) = self.forest.tables[table].push_answer(answer) {
+            info!(name: "answer_found", ?table, answer = ?answer_index, "answer found");
+            self.context.charge(Work::Answer);
+
             // See above, if we have a *complete* and trivial answer, we don't
             // want to follow any more strands
             if !ambiguous && is_trivial_answer {
@@ -1528,21 +1641,75 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
     /// Removes the subgoal at `subgoal_index` from the strand's
     /// subgoal list and adds it to the strand's floundered subgoal
     /// list.
-    fn flounder_subgoal(&self, ex_clause: &mut ExClause<I>, subgoal_index: usize) {
-        info_heading!(
-            "flounder_subgoal(answer_time={:?}, subgoal={:?})",
-            ex_clause.answer_time,
-            ex_clause.subgoals[subgoal_index],
-        );
+    fn flounder_subgoal(
+        &self,
+        ex_clause: &mut ExClause<I>,
+        subgoal_index: usize,
+        floundered_cause: Option<FlounderedCause<I>>,
+    ) {
+        let _span = info_span!(
+            "flounder_subgoal",
+            answer_time = ?ex_clause.answer_time,
+            subgoal = ?ex_clause.subgoals[subgoal_index]
+        )
+        .entered();
         let floundered_time = ex_clause.answer_time;
         let floundered_literal = ex_clause.subgoals.remove(subgoal_index);
         ex_clause.floundered_subgoals.push(FlounderedSubgoal {
             floundered_literal,
             floundered_time,
+            floundered_cause,
         });
         debug!("flounder_subgoal: ex_clause={:#?}", ex_clause);
     }
 
+    /// Explains why no table could be created for `subgoal`: either it is
+    /// too large, or it is a negative literal that still contains
+    /// unresolved variables.
+    fn floundered_literal_cause(
+        &self,
+        infer: &mut dyn InferenceTable<I, C>,
+        subgoal: &Literal<I>,
+    ) -> FlounderedCause<I> {
+        let interner = self.context.interner();
+        match subgoal {
+            Literal::Negative(goal) if !infer.goal_needs_truncation(interner, goal) => {
+                let not_goal = GoalData::Not(goal.goal.clone()).intern(interner);
+                let not_goal = InEnvironment::new(&goal.environment, not_goal);
+                let (goal, _) = infer.fully_canonicalize_goal(interner, &not_goal);
+                let goal = goal.canonical;
+                // Any of the unresolved variables could have made the
+                // literal flounder; report the first one.
+                let variable = if goal.binders.is_empty(interner) {
+                    None
+                } else {
+                    Some(0)
+                };
+                FlounderedCause::Goal(FlounderedGoal { goal, variable })
+            }
+            Literal::Positive(goal) | Literal::Negative(goal) => {
+                let (goal, _) = infer.fully_canonicalize_goal(interner, goal);
+                self.overflow_cause(Some(goal.canonical))
+            }
+        }
+    }
+
+    /// Records that the goal of the table on top of the stack, or its
+    /// `subgoal` if given, got too large, along with the chain of goals on
+    /// the stack that led to it.
+    fn overflow_cause(
+        &self,
+        subgoal: Option<Canonical<InEnvironment<Goal<I>>>>,
+    ) -> FlounderedCause<I> {
+        let goals = self
+            .stack
+            .tables()
+            .map(|table| self.forest.tables[table].table_goal.canonical.clone())
+            .chain(subgoal)
+            .collect();
+        FlounderedCause::Overflow(goals)
+    }
+
     /// True if all the tables on the stack starting from `depth` and
     /// continuing until the top of the stack are coinductive.
     ///
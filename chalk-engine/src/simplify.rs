@@ -2,12 +2,12 @@ use crate::context::{Context, ContextOps, InferenceTable};
 use crate::forest::Forest;
 use crate::{ExClause, Literal, TimeStamp};
 
-use chalk_ir::debug;
 use chalk_ir::interner::Interner;
 use chalk_ir::{
     Constraint, DomainGoal, Environment, Fallible, Goal, GoalData, InEnvironment, LifetimeOutlives,
-    QuantifierKind, Substitution, WhereClause,
+    QuantifierKind, Substitution, TypeOutlives, Variance, WhereClause,
 };
+use tracing::debug;
 
 impl<I: Interner, C: Context<I>> Forest<I, C> {
     /// Simplifies a goal into a series of positive domain goals
@@ -69,6 +69,15 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                     &goal.b,
                     &mut ex_clause,
                 )?,
+                GoalData::SubtypeGoal(goal) => infer.relate_tys_into_ex_clause(
+                    context.interner(),
+                    context.unification_database(),
+                    &environment,
+                    Variance::Covariant,
+                    &goal.a,
+                    &goal.b,
+                    &mut ex_clause,
+                )?,
                 GoalData::DomainGoal(domain_goal) => match domain_goal {
                     DomainGoal::Holds(WhereClause::LifetimeOutlives(LifetimeOutlives { a, b })) => {
                         ex_clause.constraints.push(InEnvironment::new(
@@ -76,6 +85,12 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                             Constraint::Outlives(a.clone(), b.clone()),
                         ));
                     }
+                    DomainGoal::Holds(WhereClause::TypeOutlives(TypeOutlives { ty, lifetime })) => {
+                        ex_clause.constraints.push(InEnvironment::new(
+                            &environment,
+                            Constraint::TypeOutlives(ty.clone(), lifetime.clone()),
+                        ));
+                    }
                     _ => {
                         ex_clause
                             .subgoals
@@ -66,6 +66,11 @@ impl<I: Interner, C: Context<I>> Stack<I, C> {
             .next()
     }
 
+    /// The tables on the stack, from the bottom of the stack to its top.
+    pub(super) fn tables(&self) -> impl Iterator<Item = TableIndex> + '_ {
+        self.stack.iter().map(|stack_entry| stack_entry.table)
+    }
+
     pub(super) fn top_of_stack_from(&self, depth: StackIndex) -> Range<StackIndex> {
         depth..StackIndex::from(self.stack.len())
     }
@@ -1,6 +1,6 @@
 use crate::context::Context;
 use crate::table::AnswerIndex;
-use crate::{ExClause, TableIndex, TimeStamp};
+use crate::{Derivation, ExClause, TableIndex, TimeStamp};
 use std::fmt::{Debug, Error, Formatter};
 
 use chalk_ir::interner::Interner;
@@ -14,6 +14,8 @@ pub(crate) struct CanonicalStrand<I: Interner> {
     pub(crate) selected_subgoal: Option<SelectedSubgoal>,
 
     pub(crate) last_pursued_time: TimeStamp,
+
+    pub(crate) derivation: Option<Derivation<I>>,
 }
 
 pub(crate) struct Strand<I: Interner, C: Context<I>> {
@@ -25,6 +27,9 @@ pub(crate) struct Strand<I: Interner, C: Context<I>> {
     pub(crate) selected_subgoal: Option<SelectedSubgoal>,
 
     pub(crate) last_pursued_time: TimeStamp,
+
+    /// How the strand got this far, if the forest records derivations.
+    pub(crate) derivation: Option<Derivation<I>>,
 }
 
 #[derive(Clone, Debug)]
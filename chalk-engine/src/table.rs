@@ -1,14 +1,14 @@
 use crate::index_struct;
 use crate::strand::CanonicalStrand;
-use crate::{Answer, AnswerMode};
+use crate::{Answer, AnswerMode, FlounderedCause, SubgoalDerivation, TableIndex};
 use rustc_hash::FxHashMap;
 use std::collections::hash_map::Entry;
 use std::collections::VecDeque;
 use std::mem;
 
 use chalk_ir::interner::Interner;
-use chalk_ir::{debug, debug_heading, info};
 use chalk_ir::{AnswerSubst, Canonical, Goal, InEnvironment, UCanonical};
+use tracing::{debug, debug_span, info};
 
 pub(crate) struct Table<I: Interner> {
     /// The goal this table is trying to solve (also the key to look
@@ -19,9 +19,10 @@ pub(crate) struct Table<I: Interner> {
     /// or less. This is true for auto traits.
     pub(crate) coinductive_goal: bool,
 
-    /// True if this table is floundered, meaning that it doesn't have
-    /// enough types specified for us to solve.
-    floundered: bool,
+    /// Set if this table is floundered, meaning that it doesn't have
+    /// enough types specified for us to solve (or that its answers got
+    /// too large). Records why it floundered.
+    floundered: Option<FlounderedCause<I>>,
 
     /// Stores the answers that we have found thus far. When we get a request
     /// for an answer N, we will first check this vector.
@@ -47,7 +48,7 @@ pub(crate) struct Table<I: Interner> {
 }
 
 index_struct! {
-    pub(crate) struct AnswerIndex {
+    pub struct AnswerIndex {
         value: usize,
     }
 }
@@ -61,7 +62,7 @@ impl<I: Interner> Table<I> {
             table_goal,
             coinductive_goal,
             answers: Vec::new(),
-            floundered: false,
+            floundered: None,
             answers_hash: FxHashMap::default(),
             strands: VecDeque::new(),
             answer_mode: AnswerMode::Complete,
@@ -110,17 +111,28 @@ impl<I: Interner> Table<I> {
         }
     }
 
-    /// Mark the table as floundered -- this also discards all pre-existing answers,
-    /// as they are no longer relevant.
-    pub(crate) fn mark_floundered(&mut self) {
-        self.floundered = true;
+    /// Mark the table as floundered because of `cause` -- this also
+    /// discards all pre-existing answers, as they are no longer relevant.
+    pub(crate) fn mark_floundered(&mut self, cause: FlounderedCause<I>) {
+        self.floundered = Some(cause);
         self.strands = Default::default();
         self.answers = Default::default();
     }
 
+    /// Returns true if the table has no strands left to pursue, i.e. all of
+    /// its answers have been found.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.strands.is_empty()
+    }
+
     /// Returns true if the table is floundered.
     pub(crate) fn is_floundered(&self) -> bool {
-        self.floundered
+        self.floundered.is_some()
+    }
+
+    /// Returns why this table floundered, if it has.
+    pub(crate) fn floundered_cause(&self) -> Option<&FlounderedCause<I>> {
+        self.floundered.as_ref()
     }
 
     /// Adds `answer` to our list of answers, unless it is already present.
@@ -134,9 +146,9 @@ impl<I: Interner> Table<I> {
     /// `true` is returned here, that a *new* answer was added (instead of an)
     /// existing answer replaced.
     pub(super) fn push_answer(&mut self, answer: Answer<I>) -> Option<AnswerIndex> {
-        assert!(!self.floundered);
+        assert!(!self.is_floundered());
 
-        debug_heading!("push_answer(answer={:?})", answer);
+        let _span = debug_span!("push_answer", ?answer).entered();
         debug!(
             "pre-existing entry: {:?}",
             self.answers_hash.get(&answer.subst)
@@ -177,6 +189,25 @@ impl<I: Interner> Table<I> {
     pub(super) fn next_answer_index(&self) -> AnswerIndex {
         AnswerIndex::from(self.answers.len())
     }
+
+    /// Renumbers the tables that the derivations of our answers refer to.
+    pub(super) fn map_derivation_tables(&mut self, map: impl Fn(TableIndex) -> TableIndex) {
+        let derivations = self
+            .answers
+            .iter_mut()
+            .filter_map(|answer| answer.derivation.as_mut());
+        for derivation in derivations {
+            for subgoal in &mut derivation.subgoals {
+                *subgoal = match *subgoal {
+                    SubgoalDerivation::Answer(table, answer) => {
+                        SubgoalDerivation::Answer(map(table), answer)
+                    }
+                    SubgoalDerivation::Refuted(table) => SubgoalDerivation::Refuted(map(table)),
+                    SubgoalDerivation::Delayed(table) => SubgoalDerivation::Delayed(map(table)),
+                };
+            }
+        }
+    }
 }
 
 impl AnswerIndex {
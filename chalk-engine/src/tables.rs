@@ -39,6 +39,25 @@ impl<I: Interner> Tables<I> {
         index
     }
 
+    /// Keeps only the tables for which `keep` returns true, renumbering
+    /// them in order. Returns the new index of each old table, if it was
+    /// kept.
+    pub(super) fn retain(&mut self, keep: impl Fn(TableIndex) -> bool) -> Vec<Option<TableIndex>> {
+        let tables = std::mem::take(&mut self.tables);
+        self.table_indices.clear();
+        tables
+            .into_iter()
+            .enumerate()
+            .map(|(value, table)| {
+                if keep(TableIndex { value }) {
+                    Some(self.insert(table))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub(super) fn index_of(
         &self,
         literal: &UCanonical<InEnvironment<Goal<I>>>,
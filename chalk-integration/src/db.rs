@@ -7,21 +7,53 @@ use crate::{
     tls,
 };
 use chalk_ir::{
-    AdtId, AssocTypeId, Canonical, ConstrainedSubst, Environment, FnDefId, GenericArg, Goal,
-    ImplId, InEnvironment, OpaqueTyId, ProgramClause, ProgramClauses, TraitId, Ty, UCanonical,
+    AdtId, AssocTypeId, Canonical, ClosureId, ConstrainedSubst, Environment, FnDefId, GeneratorId,
+    GenericArg, Goal, ImplId, InEnvironment, OpaqueTyId, ProgramClause, ProgramClauses, TraitId,
+    Ty, UCanonical, UnificationDatabase, Variances,
 };
 use chalk_solve::rust_ir::{
-    AdtDatum, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, FnDefDatum, ImplDatum,
-    OpaqueTyDatum, TraitDatum, WellKnownTrait,
+    AdtDatum, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ClosureDatum, FnDefDatum,
+    GeneratorDatum, GeneratorWitnessDatum, ImplDatum, OpaqueTyDatum, TraitDatum,
+    WellKnownAssocType, WellKnownTrait,
+};
+use chalk_solve::{
+    AmbiguityCandidates, BudgetExceeded, FailureTree, ProofTree, RustIrDatabase, Solution,
+    SolveBudget, Solver, SolverChoice, SolverComparison, SolverStats, SubstitutionResult,
 };
-use chalk_solve::{RustIrDatabase, Solution, SolverChoice, SubstitutionResult};
 use salsa::Database;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 #[salsa::database(Lowering)]
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ChalkDatabase {
     runtime: salsa::Runtime<ChalkDatabase>,
+
+    /// The solver used so far; see `ChalkDatabase::solver`.
+    solver: Mutex<Option<CachedSolver>>,
+
+    /// Whether solvers collect statistics; see `set_collect_stats`.
+    collect_stats: bool,
+}
+
+/// A solver, along with the program and solver choice it was used with.
+#[derive(Debug)]
+struct CachedSolver {
+    program: Option<Arc<Program>>,
+    solver_choice: SolverChoice,
+    solver: Arc<Mutex<Solver<ChalkIr>>>,
+}
+
+impl Default for ChalkDatabase {
+    fn default() -> Self {
+        let mut db = ChalkDatabase {
+            runtime: Default::default(),
+            solver: Default::default(),
+            collect_stats: false,
+        };
+        db.set_check_threads(1);
+        db.set_program_path(Arc::new("<input>".to_string()));
+        db
+    }
 }
 
 impl Database for ChalkDatabase {
@@ -38,6 +70,16 @@ impl ChalkDatabase {
         db
     }
 
+    /// Like `with`, but errors in the program are reported relative to
+    /// the file at `path`.
+    pub fn with_path(path: &str, program_text: &str, solver_choice: SolverChoice) -> Self {
+        let mut db = ChalkDatabase::default();
+        db.set_program_path(Arc::new(path.to_string()));
+        db.set_program_text(Arc::new(program_text.to_string()));
+        db.set_solver_choice(solver_choice);
+        db
+    }
+
     pub fn with_program<R>(&self, op: impl FnOnce(&Program) -> R) -> R {
         let program = &self.checked_program().unwrap();
         tls::set_current_program(&program, || op(&program))
@@ -48,6 +90,49 @@ impl ChalkDatabase {
         Ok(chalk_parse::parse_goal(text)?.lower(&*program)?)
     }
 
+    /// Returns the solver we can use to solve goals. This solver stores
+    /// intermediate, cached state, which is why it is behind a mutex.
+    /// When the program changes, that state may become invalid. If only
+    /// traits and impls changed, the solver is kept and forgets just the
+    /// state that depends on them; otherwise, a new solver is created.
+    fn solver(&self) -> Arc<Mutex<Solver<ChalkIr>>> {
+        let program = self.program_ir().ok();
+        let solver_choice = self.solver_choice();
+        let mut cached = self.solver.lock().unwrap();
+        if let Some(cached) = &mut *cached {
+            if cached.solver_choice == solver_choice {
+                match (&cached.program, &program) {
+                    (None, None) => return cached.solver.clone(),
+                    (Some(old), Some(new)) if Arc::ptr_eq(old, new) => {
+                        return cached.solver.clone()
+                    }
+                    (Some(old), Some(new)) => {
+                        if let Some(changed) = old.changed_dependencies(new) {
+                            cached
+                                .solver
+                                .lock()
+                                .unwrap()
+                                .invalidate(|dependency| changed.contains(dependency));
+                            cached.program = program;
+                            return cached.solver.clone();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut solver = solver_choice.into_solver();
+        solver.set_collect_stats(self.collect_stats);
+        let solver = Arc::new(Mutex::new(solver));
+        *cached = Some(CachedSolver {
+            program,
+            solver_choice,
+            solver: solver.clone(),
+        });
+        solver
+    }
+
     pub fn solve(
         &self,
         goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
@@ -57,6 +142,106 @@ impl ChalkDatabase {
         solution
     }
 
+    /// Solves a given goal like `solve`, but if the goal has a unique
+    /// solution, also returns a proof tree explaining why it holds.
+    pub fn solve_with_proof(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<(Solution<ChalkIr>, Option<ProofTree<ChalkIr>>)> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_with_proof(self, goal);
+        solution
+    }
+
+    /// Solves a given goal like `solve`, but if the goal is ambiguous,
+    /// also returns the distinct candidate substitutions behind the
+    /// ambiguity; see `Solver::solve_with_candidates`.
+    pub fn solve_with_candidates(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<(Solution<ChalkIr>, AmbiguityCandidates<ChalkIr>)> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_with_candidates(self, goal);
+        solution
+    }
+
+    /// Solves a given goal like `solve`, but gives up once `budget` is
+    /// used up; see `Solver::solve_with_budget`.
+    pub fn solve_with_budget(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        budget: SolveBudget,
+    ) -> Result<Option<Solution<ChalkIr>>, BudgetExceeded> {
+        let solver = self.solver();
+        let solution = solver.lock().unwrap().solve_with_budget(self, goal, budget);
+        solution
+    }
+
+    /// Solves a given goal like `solve`, but stops once `should_continue`
+    /// returns `false`; see `Solver::solve_limited`.
+    pub fn solve_limited(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        should_continue: impl Fn() -> bool,
+    ) -> Option<Solution<ChalkIr>> {
+        let solver = self.solver();
+        let solution = solver
+            .lock()
+            .unwrap()
+            .solve_limited(self, goal, should_continue);
+        solution
+    }
+
+    /// If the goal has no solution, explains why; see
+    /// `Solver::explain_failure`.
+    pub fn explain_failure(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<FailureTree<ChalkIr>> {
+        let solver = self.solver();
+        let failure = solver.lock().unwrap().explain_failure(self, goal);
+        failure
+    }
+
+    /// Solves a given goal with a fresh solver for each of
+    /// `solver_choices`, reporting whether they agree; see
+    /// `chalk_solve::compare_solvers`.
+    pub fn compare_solvers(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        solver_choices: &[SolverChoice],
+    ) -> SolverComparison<ChalkIr> {
+        chalk_solve::compare_solvers(self, goal, solver_choices)
+    }
+
+    /// Sets whether solvers collect statistics; see
+    /// `Solver::set_collect_stats`.
+    pub fn set_collect_stats(&mut self, collect_stats: bool) {
+        self.collect_stats = collect_stats;
+        if let Some(cached) = &*self.solver.lock().unwrap() {
+            cached
+                .solver
+                .lock()
+                .unwrap()
+                .set_collect_stats(collect_stats);
+        }
+    }
+
+    /// The statistics of all solves so far; see `Solver::stats`.
+    pub fn solver_stats(&self) -> SolverStats {
+        let solver = self.solver();
+        let stats = solver.lock().unwrap().stats().clone();
+        stats
+    }
+
+    /// The statistics of the most recent solve; see
+    /// `Solver::last_solve_stats`.
+    pub fn last_solve_stats(&self) -> SolverStats {
+        let solver = self.solver();
+        let stats = solver.lock().unwrap().last_solve_stats().clone();
+        stats
+    }
+
     /// Solves a given goal, producing the solution. This will do only
     /// as much work towards `goal` as it has to (and that works is
     /// cached for future attempts). Calls provided function `f` to
@@ -112,6 +297,21 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
         self.program_ir().unwrap().fn_def_datum(id)
     }
 
+    fn closure_datum(&self, id: ClosureId<ChalkIr>) -> Arc<ClosureDatum<ChalkIr>> {
+        self.program_ir().unwrap().closure_datum(id)
+    }
+
+    fn generator_datum(&self, id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        self.program_ir().unwrap().generator_datum(id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        self.program_ir().unwrap().generator_witness_datum(id)
+    }
+
     fn impls_for_trait(
         &self,
         trait_id: TraitId<ChalkIr>,
@@ -140,6 +340,15 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
             .well_known_trait_id(well_known_trait)
     }
 
+    fn well_known_assoc_type_id(
+        &self,
+        well_known_assoc_type: WellKnownAssocType,
+    ) -> Option<AssocTypeId<ChalkIr>> {
+        self.program_ir()
+            .unwrap()
+            .well_known_assoc_type_id(well_known_assoc_type)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &Environment<ChalkIr>,
@@ -151,7 +360,21 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
         &ChalkIr
     }
 
+    fn unification_database(&self) -> &dyn UnificationDatabase<ChalkIr> {
+        self
+    }
+
     fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
         self.program_ir().unwrap().is_object_safe(trait_id)
     }
 }
+
+impl UnificationDatabase<ChalkIr> for ChalkDatabase {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
+        self.program_ir().unwrap().fn_def_variance(fn_def_id)
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
+        self.program_ir().unwrap().adt_variance(adt_id)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::interner::ChalkIr;
-use chalk_parse::ast::{Identifier, Kind};
+use chalk_parse::ast::{Identifier, Kind, Span};
 use chalk_solve::coherence::CoherenceError;
 use chalk_solve::wf::WfError;
 
@@ -12,6 +12,60 @@ pub struct ChalkError {
     error_text: String,
 }
 
+impl ChalkError {
+    /// Appends the `file:line:col` location of each of `spans`, along with
+    /// a snippet of the source text, to the error message.
+    pub(crate) fn with_spans(
+        mut self,
+        path: &str,
+        text: &str,
+        spans: impl IntoIterator<Item = Span>,
+    ) -> Self {
+        for span in spans {
+            self.error_text.push('\n');
+            self.error_text.push_str(&source_snippet(path, text, span));
+        }
+        self
+    }
+}
+
+/// Renders `span` as a `file:line:col` location followed by the first line
+/// of source text it covers, with the spanned part underlined by carets:
+///
+/// ```text
+///  --> foo.chalk:3:1
+///   |
+/// 3 | impl Foo for Bar { }
+///   | ^^^^^^^^^^^^^^^^^^^^
+/// ```
+pub fn source_snippet(path: &str, text: &str, span: Span) -> String {
+    let (line, col) = span.line_col(text);
+    let lo = span.lo.min(text.len());
+    let line_start = text[..lo].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[lo..].find('\n').map_or(text.len(), |i| lo + i);
+    let source_line = text[line_start..line_end].trim_end_matches('\r');
+    let underlined = text[lo..span.hi.max(lo).min(line_end)]
+        .trim_end_matches('\r')
+        .chars()
+        .count()
+        .max(1);
+
+    let gutter = " ".repeat(line.to_string().len());
+    format!(
+        "{gutter}--> {path}:{line}:{col}\n\
+         {gutter} |\n\
+         {line} | {source_line}\n\
+         {gutter} | {indent}{carets}",
+        gutter = gutter,
+        path = path,
+        line = line,
+        col = col,
+        source_line = source_line,
+        indent = " ".repeat(col - 1),
+        carets = "^".repeat(underlined),
+    )
+}
+
 impl From<Box<dyn std::error::Error>> for ChalkError {
     fn from(value: Box<dyn std::error::Error>) -> Self {
         ChalkError {
@@ -65,6 +119,10 @@ pub enum RustIrError {
     InvalidFundamentalTypesParameters(Identifier),
     NegativeImplAssociatedValues(Identifier),
     MissingAssociatedType(Identifier),
+    MissingWellKnownAssociatedType {
+        identifier: Identifier,
+        name: &'static str,
+    },
     IncorrectNumberOfTypeParameters {
         identifier: Identifier,
         expected: usize,
@@ -91,6 +149,52 @@ pub enum RustIrError {
         actual: Kind,
     },
     CannotApplyTypeParameter(Identifier),
+    IncorrectNumberOfVariances {
+        identifier: Identifier,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl RustIrError {
+    /// The source span of the identifier the error refers to, if any.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RustIrError::InvalidParameterName(name)
+            | RustIrError::InvalidTraitName(name)
+            | RustIrError::NotTrait(name)
+            | RustIrError::NotStruct(name)
+            | RustIrError::AutoTraitAssociatedTypes(name)
+            | RustIrError::AutoTraitParameters(name)
+            | RustIrError::AutoTraitWhereClauses(name)
+            | RustIrError::InvalidFundamentalTypesParameters(name)
+            | RustIrError::NegativeImplAssociatedValues(name)
+            | RustIrError::MissingAssociatedType(name)
+            | RustIrError::CannotApplyTypeParameter(name)
+            | RustIrError::MissingWellKnownAssociatedType {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectNumberOfTypeParameters {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectParameterKind {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectTraitParameterKind {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectAssociatedTypeParameterKind {
+                identifier: name, ..
+            }
+            | RustIrError::IncorrectNumberOfVariances {
+                identifier: name, ..
+            } => Some(name.span),
+            RustIrError::DuplicateOrShadowedParameters => None,
+        }
+    }
 }
 
 impl std::fmt::Display for RustIrError {
@@ -135,6 +239,11 @@ impl std::fmt::Display for RustIrError {
             RustIrError::MissingAssociatedType(name) => {
                 write!(f, "no associated type `{}` defined in trait", name)
             }
+            RustIrError::MissingWellKnownAssociatedType { identifier, name } => write!(
+                f,
+                "lang item trait `{}` must define the associated type `{}`",
+                identifier, name
+            ),
             RustIrError::IncorrectNumberOfTypeParameters {
                 identifier,
                 expected,
@@ -183,6 +292,15 @@ impl std::fmt::Display for RustIrError {
             RustIrError::CannotApplyTypeParameter(name) => {
                 write!(f, "cannot apply type parameter `{}`", name)
             }
+            RustIrError::IncorrectNumberOfVariances {
+                identifier,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "`{}` has {} generic parameters, but {} variances were given",
+                identifier, expected, actual
+            ),
         }
     }
 }
@@ -1,10 +1,10 @@
 use crate::tls;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::{
-    AdtId, AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKind, CanonicalVarKinds, ConstData,
-    Goals, Lifetime, OpaqueTy, OpaqueTyId, ProgramClauseImplication, ProgramClauses, ProjectionTy,
-    QuantifiedWhereClauses, SeparatorTraitRef, Substitution, TraitId, Ty, VariableKind,
-    VariableKinds,
+    AdtId, AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKind, CanonicalVarKinds, ClosureId,
+    ConstData, ForeignDefId, GeneratorId, Goals, ImplId, Lifetime, OpaqueTy, OpaqueTyId,
+    ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses,
+    SeparatorTraitRef, Substitution, TraitId, Ty, VariableKind, VariableKinds, Variance,
 };
 use chalk_ir::{
     GenericArg, GenericArgData, Goal, GoalData, LifetimeData, ProgramClause, ProgramClauseData,
@@ -47,6 +47,7 @@ impl Interner for ChalkIr {
     type InternedQuantifiedWhereClauses = Vec<QuantifiedWhereClause<ChalkIr>>;
     type InternedVariableKinds = Vec<VariableKind<ChalkIr>>;
     type InternedCanonicalVarKinds = Vec<CanonicalVarKind<ChalkIr>>;
+    type InternedVariances = Vec<Variance>;
     type DefId = RawId;
     type InternedAdtId = RawId;
     type Identifier = Identifier;
@@ -65,6 +66,10 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_trait_id(type_kind_id, fmt)))
     }
 
+    fn display_impl_id(id: ImplId<ChalkIr>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.display_impl_id(id, fmt)))
+    }
+
     fn debug_assoc_type_id(
         id: AssocTypeId<ChalkIr>,
         fmt: &mut fmt::Formatter<'_>,
@@ -79,6 +84,27 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_opaque_ty_id(id, fmt)))
     }
 
+    fn debug_closure_id(
+        id: ClosureId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_closure_id(id, fmt)))
+    }
+
+    fn debug_generator_id(
+        id: GeneratorId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_generator_id(id, fmt)))
+    }
+
+    fn debug_foreign_def_id(
+        id: ForeignDefId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_foreign_def_id(id, fmt)))
+    }
+
     fn debug_alias(alias: &AliasTy<ChalkIr>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
         tls::with_current_program(|prog| Some(prog?.debug_alias(alias, fmt)))
     }
@@ -337,6 +363,17 @@ impl Interner for ChalkIr {
     ) -> &'a [CanonicalVarKind<ChalkIr>] {
         canonical_var_kinds
     }
+
+    fn intern_variances<E>(
+        &self,
+        data: impl IntoIterator<Item = Result<Variance, E>>,
+    ) -> Result<Self::InternedVariances, E> {
+        data.into_iter().collect()
+    }
+
+    fn variances_data<'a>(&self, variances: &'a Self::InternedVariances) -> &'a [Variance] {
+        variances
+    }
 }
 
 impl HasInterner for ChalkIr {
@@ -8,6 +8,7 @@ pub mod lowering;
 pub mod program;
 pub mod program_environment;
 pub mod query;
+pub mod test_file;
 pub mod tls;
 
 use chalk_ir::interner::HasInterner;
@@ -18,8 +19,10 @@ pub use interner::{Identifier, RawId};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TypeSort {
-    Struct,
+    Adt,
     FnDef,
+    Closure,
+    Generator,
     Trait,
     Opaque,
 }
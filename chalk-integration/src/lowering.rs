@@ -2,17 +2,18 @@ use crate::interner::ChalkIr;
 use chalk_ir::cast::{Cast, Caster};
 use chalk_ir::interner::HasInterner;
 use chalk_ir::{
-    self, AdtId, AssocTypeId, BoundVar, ClausePriority, DebruijnIndex, FnDefId, ImplId, OpaqueTyId,
-    QuantifiedWhereClauses, Substitution, ToGenericArg, TraitId, TyKind,
+    self, AdtId, AssocTypeId, BoundVar, ClausePriority, ClosureId, DebruijnIndex, FnDefId,
+    ForeignDefId, GeneratorId, ImplId, OpaqueTyId, QuantifiedWhereClauses, Substitution,
+    ToGenericArg, TraitId, TyKind,
 };
-use chalk_ir::{debug, debug_heading};
 use chalk_parse::ast::*;
 use chalk_solve::rust_ir::{
     self, Anonymize, AssociatedTyValueId, IntoWhereClauses, OpaqueTyDatum, OpaqueTyDatumBound,
 };
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use string_cache::DefaultAtom as Atom;
+use tracing::{debug, debug_span};
 
 use crate::error::RustIrError;
 use crate::program::Program as LoweredProgram;
@@ -20,10 +21,15 @@ use crate::{Identifier as Ident, RawId, TypeKind, TypeSort};
 
 type AdtIds = BTreeMap<Ident, chalk_ir::AdtId<ChalkIr>>;
 type FnDefIds = BTreeMap<Ident, chalk_ir::FnDefId<ChalkIr>>;
+type ClosureIds = BTreeMap<Ident, chalk_ir::ClosureId<ChalkIr>>;
+type GeneratorIds = BTreeMap<Ident, chalk_ir::GeneratorId<ChalkIr>>;
+type ForeignIds = BTreeMap<Ident, chalk_ir::ForeignDefId<ChalkIr>>;
 type TraitIds = BTreeMap<Ident, chalk_ir::TraitId<ChalkIr>>;
 type OpaqueTyIds = BTreeMap<Ident, chalk_ir::OpaqueTyId<ChalkIr>>;
 type AdtKinds = BTreeMap<chalk_ir::AdtId<ChalkIr>, TypeKind>;
 type FnDefKinds = BTreeMap<chalk_ir::FnDefId<ChalkIr>, TypeKind>;
+type ClosureKinds = BTreeMap<chalk_ir::ClosureId<ChalkIr>, TypeKind>;
+type GeneratorKinds = BTreeMap<chalk_ir::GeneratorId<ChalkIr>, TypeKind>;
 type TraitKinds = BTreeMap<chalk_ir::TraitId<ChalkIr>, TypeKind>;
 type OpaqueTyKinds = BTreeMap<chalk_ir::OpaqueTyId<ChalkIr>, TypeKind>;
 type AssociatedTyLookups = BTreeMap<(chalk_ir::TraitId<ChalkIr>, Ident), AssociatedTyLookup>;
@@ -40,6 +46,11 @@ struct Env<'k> {
     adt_kinds: &'k AdtKinds,
     fn_def_ids: &'k FnDefIds,
     fn_def_kinds: &'k FnDefKinds,
+    closure_ids: &'k ClosureIds,
+    closure_kinds: &'k ClosureKinds,
+    generator_ids: &'k GeneratorIds,
+    generator_kinds: &'k GeneratorKinds,
+    foreign_ty_ids: &'k ForeignIds,
     trait_ids: &'k TraitIds,
     trait_kinds: &'k TraitKinds,
     opaque_ty_ids: &'k OpaqueTyIds,
@@ -78,6 +89,8 @@ struct AssociatedTyLookup {
 enum ApplyTypeLookup {
     Adt(AdtId<ChalkIr>),
     FnDef(FnDefId<ChalkIr>),
+    Closure(ClosureId<ChalkIr>),
+    Generator(GeneratorId<ChalkIr>),
     Opaque(OpaqueTyId<ChalkIr>),
 }
 
@@ -139,6 +152,51 @@ impl<'k> Env<'k> {
             }
         }
 
+        if let Some(id) = self.closure_ids.get(&name.str) {
+            let k = self.closure_kind(*id);
+            if k.binders.len(interner) > 0 {
+                return Err(RustIrError::IncorrectNumberOfTypeParameters {
+                    identifier: name.clone(),
+                    expected: k.binders.len(interner),
+                    actual: 0,
+                });
+            } else {
+                return Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                    name: chalk_ir::TypeName::Closure(*id),
+                    substitution: chalk_ir::Substitution::empty(interner),
+                })
+                .intern(interner)
+                .cast(interner));
+            }
+        }
+
+        if let Some(id) = self.generator_ids.get(&name.str) {
+            let k = self.generator_kind(*id);
+            if k.binders.len(interner) > 0 {
+                return Err(RustIrError::IncorrectNumberOfTypeParameters {
+                    identifier: name.clone(),
+                    expected: k.binders.len(interner),
+                    actual: 0,
+                });
+            } else {
+                return Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                    name: chalk_ir::TypeName::Generator(*id),
+                    substitution: chalk_ir::Substitution::empty(interner),
+                })
+                .intern(interner)
+                .cast(interner));
+            }
+        }
+
+        if let Some(id) = self.foreign_ty_ids.get(&name.str) {
+            return Ok(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                name: chalk_ir::TypeName::Foreign(*id),
+                substitution: chalk_ir::Substitution::empty(interner),
+            })
+            .intern(interner)
+            .cast(interner));
+        }
+
         if let Some(id) = self.opaque_ty_ids.get(&name.str) {
             return Ok(
                 chalk_ir::TyData::Alias(chalk_ir::AliasTy::Opaque(chalk_ir::OpaqueTy {
@@ -169,6 +227,14 @@ impl<'k> Env<'k> {
             return Ok(ApplyTypeLookup::FnDef(*id));
         }
 
+        if let Some(id) = self.closure_ids.get(&name.str) {
+            return Ok(ApplyTypeLookup::Closure(*id));
+        }
+
+        if let Some(id) = self.generator_ids.get(&name.str) {
+            return Ok(ApplyTypeLookup::Generator(*id));
+        }
+
         if let Some(id) = self.opaque_ty_ids.get(&name.str) {
             return Ok(ApplyTypeLookup::Opaque(*id));
         }
@@ -204,6 +270,14 @@ impl<'k> Env<'k> {
         &self.fn_def_kinds[&id]
     }
 
+    fn closure_kind(&self, id: chalk_ir::ClosureId<ChalkIr>) -> &TypeKind {
+        &self.closure_kinds[&id]
+    }
+
+    fn generator_kind(&self, id: chalk_ir::GeneratorId<ChalkIr>) -> &TypeKind {
+        &self.generator_kinds[&id]
+    }
+
     fn opaque_kind(&self, id: chalk_ir::OpaqueTyId<ChalkIr>) -> &TypeKind {
         &self.opaque_ty_kinds[&id]
     }
@@ -285,7 +359,9 @@ impl LowerProgram for Program {
         // Create ids for associated type declarations and values
         let mut associated_ty_lookups = BTreeMap::new();
         let mut associated_ty_value_ids = BTreeMap::new();
+        let mut item_spans = BTreeMap::new();
         for (item, &raw_id) in self.items.iter().zip(&raw_ids) {
+            item_spans.insert(raw_id, item.span());
             match item {
                 Item::TraitDefn(d) => {
                     if d.flags.auto && !d.assoc_ty_defns.is_empty() {
@@ -297,6 +373,7 @@ impl LowerProgram for Program {
                             id: AssocTypeId(next_item_id()),
                             addl_variable_kinds: addl_variable_kinds.anonymize(),
                         };
+                        item_spans.insert(lookup.id.0, defn.span);
                         associated_ty_lookups
                             .insert((TraitId(raw_id), defn.name.str.clone()), lookup);
                     }
@@ -305,6 +382,7 @@ impl LowerProgram for Program {
                 Item::Impl(d) => {
                     for atv in &d.assoc_ty_values {
                         let atv_id = AssociatedTyValueId(next_item_id());
+                        item_spans.insert(atv_id.0, atv.span);
                         associated_ty_value_ids
                             .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
                     }
@@ -316,16 +394,20 @@ impl LowerProgram for Program {
 
         let mut adt_ids = BTreeMap::new();
         let mut fn_def_ids = BTreeMap::new();
+        let mut closure_ids = BTreeMap::new();
+        let mut generator_ids = BTreeMap::new();
+        let mut foreign_ty_ids = BTreeMap::new();
         let mut trait_ids = BTreeMap::new();
         let mut opaque_ty_ids = BTreeMap::new();
         let mut adt_kinds = BTreeMap::new();
         let mut fn_def_kinds = BTreeMap::new();
+        let mut closure_kinds = BTreeMap::new();
+        let mut generator_kinds = BTreeMap::new();
         let mut trait_kinds = BTreeMap::new();
         let mut opaque_ty_kinds = BTreeMap::new();
-        let mut object_safe_traits = HashSet::new();
         for (item, &raw_id) in self.items.iter().zip(&raw_ids) {
             match item {
-                Item::StructDefn(defn) => {
+                Item::AdtDefn(defn) => {
                     let type_kind = defn.lower_type_kind()?;
                     let id = AdtId(raw_id);
                     adt_ids.insert(type_kind.name.clone(), id);
@@ -337,15 +419,26 @@ impl LowerProgram for Program {
                     fn_def_ids.insert(type_kind.name.clone(), id);
                     fn_def_kinds.insert(id, type_kind);
                 }
+                Item::ClosureDefn(defn) => {
+                    let type_kind = defn.lower_type_kind()?;
+                    let id = ClosureId(raw_id);
+                    closure_ids.insert(defn.name.str.clone(), id);
+                    closure_kinds.insert(id, type_kind);
+                }
+                Item::GeneratorDefn(defn) => {
+                    let type_kind = defn.lower_type_kind()?;
+                    let id = GeneratorId(raw_id);
+                    generator_ids.insert(defn.name.str.clone(), id);
+                    generator_kinds.insert(id, type_kind);
+                }
+                Item::Foreign(ForeignDefn { ref name, .. }) => {
+                    foreign_ty_ids.insert(name.str.clone(), ForeignDefId(raw_id));
+                }
                 Item::TraitDefn(defn) => {
                     let type_kind = defn.lower_type_kind()?;
                     let id = TraitId(raw_id);
                     trait_ids.insert(type_kind.name.clone(), id);
                     trait_kinds.insert(id, type_kind);
-
-                    if defn.flags.object_safe {
-                        object_safe_traits.insert(id);
-                    }
                 }
                 Item::OpaqueTyDefn(defn) => {
                     let type_kind = defn.lower_type_kind()?;
@@ -359,9 +452,15 @@ impl LowerProgram for Program {
         }
 
         let mut adt_data = BTreeMap::new();
+        let mut adt_variances = BTreeMap::new();
         let mut fn_def_data = BTreeMap::new();
+        let mut fn_def_variances = BTreeMap::new();
+        let mut closure_data = BTreeMap::new();
+        let mut generator_data = BTreeMap::new();
+        let mut generator_witness_data = BTreeMap::new();
         let mut trait_data = BTreeMap::new();
         let mut well_known_traits = BTreeMap::new();
+        let mut well_known_assoc_types = BTreeMap::new();
         let mut impl_data = BTreeMap::new();
         let mut associated_ty_data = BTreeMap::new();
         let mut associated_ty_values = BTreeMap::new();
@@ -374,6 +473,11 @@ impl LowerProgram for Program {
                 adt_kinds: &adt_kinds,
                 fn_def_ids: &fn_def_ids,
                 fn_def_kinds: &fn_def_kinds,
+                closure_ids: &closure_ids,
+                closure_kinds: &closure_kinds,
+                generator_ids: &generator_ids,
+                generator_kinds: &generator_kinds,
+                foreign_ty_ids: &foreign_ty_ids,
                 trait_ids: &trait_ids,
                 trait_kinds: &trait_kinds,
                 opaque_ty_ids: &opaque_ty_ids,
@@ -383,9 +487,13 @@ impl LowerProgram for Program {
             };
 
             match *item {
-                Item::StructDefn(ref d) => {
+                Item::AdtDefn(ref d) => {
                     let adt_id = AdtId(raw_id);
                     adt_data.insert(adt_id, Arc::new(d.lower_adt(adt_id, &empty_env)?));
+                    adt_variances.insert(
+                        adt_id,
+                        lower_variances(&d.name, d.variable_kinds.len(), &d.variances)?,
+                    );
                 }
                 Item::FnDefn(ref defn) => {
                     let fn_def_id = FnDefId(raw_id);
@@ -393,6 +501,24 @@ impl LowerProgram for Program {
                         fn_def_id,
                         Arc::new(defn.lower_fn_def(fn_def_id, &empty_env)?),
                     );
+                    fn_def_variances.insert(
+                        fn_def_id,
+                        lower_variances(&defn.name, defn.variable_kinds.len(), &defn.variances)?,
+                    );
+                }
+                Item::ClosureDefn(ref defn) => {
+                    let closure_id = ClosureId(raw_id);
+                    closure_data.insert(
+                        closure_id,
+                        Arc::new(defn.lower_closure(closure_id, &empty_env)?),
+                    );
+                }
+                Item::GeneratorDefn(ref defn) => {
+                    let generator_id = GeneratorId(raw_id);
+                    let (generator_datum, witness_datum) =
+                        defn.lower_generator(generator_id, &empty_env)?;
+                    generator_data.insert(generator_id, Arc::new(generator_datum));
+                    generator_witness_data.insert(generator_id, Arc::new(witness_datum));
                 }
                 Item::TraitDefn(ref trait_defn) => {
                     let trait_id = TraitId(raw_id);
@@ -402,6 +528,22 @@ impl LowerProgram for Program {
                         well_known_traits.insert(well_known, trait_id);
                     }
 
+                    if trait_datum.well_known == Some(rust_ir::WellKnownTrait::Generator) {
+                        for &(well_known, name) in &[
+                            (rust_ir::WellKnownAssocType::GeneratorYield, "Yield"),
+                            (rust_ir::WellKnownAssocType::GeneratorReturn, "Return"),
+                        ] {
+                            let lookup =
+                                associated_ty_lookups
+                                    .get(&(trait_id, Atom::from(name)))
+                                    .ok_or_else(|| RustIrError::MissingWellKnownAssociatedType {
+                                        identifier: trait_defn.name.clone(),
+                                        name,
+                                    })?;
+                            well_known_assoc_types.insert(well_known, lookup.id);
+                        }
+                    }
+
                     trait_data.insert(trait_id, Arc::new(trait_datum));
 
                     for assoc_ty_defn in &trait_defn.assoc_ty_defns {
@@ -545,6 +687,7 @@ impl LowerProgram for Program {
                         );
                     }
                 }
+                Item::Foreign(_) => {}
             }
         }
 
@@ -554,11 +697,22 @@ impl LowerProgram for Program {
             trait_ids,
             adt_kinds,
             fn_def_kinds,
+            closure_ids,
+            closure_kinds,
+            generator_ids,
+            generator_kinds,
+            foreign_ty_ids,
             trait_kinds,
             adt_data,
+            adt_variances,
             fn_def_data,
+            fn_def_variances,
+            closure_data,
+            generator_data,
+            generator_witness_data,
             trait_data,
             well_known_traits,
+            well_known_assoc_types,
             impl_data,
             associated_ty_values,
             associated_ty_data,
@@ -567,7 +721,7 @@ impl LowerProgram for Program {
             opaque_ty_data,
             hidden_opaque_types,
             custom_clauses,
-            object_safe_traits,
+            item_spans,
         };
 
         Ok(program)
@@ -632,7 +786,27 @@ trait LowerParameterMap {
     }
 }
 
-impl LowerParameterMap for StructDefn {
+impl LowerParameterMap for AdtDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::WithKind<ChalkIr, Ident>> {
+        None
+    }
+
+    fn declared_parameters(&self) -> &[VariableKind] {
+        &self.variable_kinds
+    }
+}
+
+impl LowerParameterMap for ClosureDefn {
+    fn synthetic_parameters(&self) -> Option<chalk_ir::WithKind<ChalkIr, Ident>> {
+        None
+    }
+
+    fn declared_parameters(&self) -> &[VariableKind] {
+        &self.variable_kinds
+    }
+}
+
+impl LowerParameterMap for GeneratorDefn {
     fn synthetic_parameters(&self) -> Option<chalk_ir::WithKind<ChalkIr, Ident>> {
         None
     }
@@ -755,11 +929,11 @@ trait LowerWhereClauses {
     }
 }
 
-impl LowerTypeKind for StructDefn {
+impl LowerTypeKind for AdtDefn {
     fn lower_type_kind(&self) -> LowerResult<TypeKind> {
         let interner = &ChalkIr;
         Ok(TypeKind {
-            sort: TypeSort::Struct,
+            sort: TypeSort::Adt,
             name: self.name.str.clone(),
             binders: chalk_ir::Binders::new(
                 chalk_ir::VariableKinds::from(interner, self.all_parameters().anonymize()),
@@ -783,13 +957,41 @@ impl LowerTypeKind for FnDefn {
     }
 }
 
+impl LowerTypeKind for ClosureDefn {
+    fn lower_type_kind(&self) -> LowerResult<TypeKind> {
+        let interner = &ChalkIr;
+        Ok(TypeKind {
+            sort: TypeSort::Closure,
+            name: self.name.str.clone(),
+            binders: chalk_ir::Binders::new(
+                chalk_ir::VariableKinds::from(interner, self.all_parameters().anonymize()),
+                crate::Unit,
+            ),
+        })
+    }
+}
+
+impl LowerTypeKind for GeneratorDefn {
+    fn lower_type_kind(&self) -> LowerResult<TypeKind> {
+        let interner = &ChalkIr;
+        Ok(TypeKind {
+            sort: TypeSort::Generator,
+            name: self.name.str.clone(),
+            binders: chalk_ir::Binders::new(
+                chalk_ir::VariableKinds::from(interner, self.all_parameters().anonymize()),
+                crate::Unit,
+            ),
+        })
+    }
+}
+
 impl LowerWhereClauses for FnDefn {
     fn where_clauses(&self) -> &[QuantifiedWhereClause] {
         &self.where_clauses
     }
 }
 
-impl LowerWhereClauses for StructDefn {
+impl LowerWhereClauses for AdtDefn {
     fn where_clauses(&self) -> &[QuantifiedWhereClause] {
         &self.where_clauses
     }
@@ -882,6 +1084,14 @@ impl LowerWhereClause<chalk_ir::WhereClause<ChalkIr>> for WhereClause {
                     },
                 )]
             }
+            WhereClause::TypeOutlives { ty, lifetime } => {
+                vec![chalk_ir::WhereClause::TypeOutlives(
+                    chalk_ir::TypeOutlives {
+                        ty: ty.lower(env)?,
+                        lifetime: lifetime.lower(env)?,
+                    },
+                )]
+            }
         };
         Ok(where_clauses)
     }
@@ -964,6 +1174,11 @@ impl LowerLeafGoal for LeafGoal {
                 b: b.lower(env)?.cast(interner),
             }
             .cast::<chalk_ir::Goal<ChalkIr>>(interner),
+            LeafGoal::SubtypeTys { a, b } => chalk_ir::SubtypeGoal {
+                a: a.lower(env)?,
+                b: b.lower(env)?,
+            }
+            .cast::<chalk_ir::Goal<ChalkIr>>(interner),
         })
     }
 }
@@ -976,7 +1191,7 @@ trait LowerAdtDefn {
     ) -> LowerResult<rust_ir::AdtDatum<ChalkIr>>;
 }
 
-impl LowerAdtDefn for StructDefn {
+impl LowerAdtDefn for AdtDefn {
     fn lower_adt(
         &self,
         adt_id: chalk_ir::AdtId<ChalkIr>,
@@ -989,11 +1204,18 @@ impl LowerAdtDefn for StructDefn {
         }
 
         let binders = env.in_binders(self.all_parameters(), |env| {
-            let fields: LowerResult<_> = self.fields.iter().map(|f| f.ty.lower(env)).collect();
+            let variants: LowerResult<_> = self
+                .variants
+                .iter()
+                .map(|v| {
+                    let fields: LowerResult<_> = v.fields.iter().map(|f| f.ty.lower(env)).collect();
+                    Ok(rust_ir::AdtVariantDatum { fields: fields? })
+                })
+                .collect();
             let where_clauses = self.lower_where_clauses(env)?;
 
             Ok(rust_ir::AdtDatumBound {
-                fields: fields?,
+                variants: variants?,
                 where_clauses,
             })
         })?;
@@ -1004,10 +1226,17 @@ impl LowerAdtDefn for StructDefn {
             phantom_data: self.flags.phantom_data,
         };
 
+        let kind = match self.kind {
+            AdtKind::Struct => rust_ir::AdtKind::Struct,
+            AdtKind::Enum => rust_ir::AdtKind::Enum,
+            AdtKind::Union => rust_ir::AdtKind::Union,
+        };
+
         Ok(rust_ir::AdtDatum {
             id: adt_id,
             binders,
             flags,
+            kind,
         })
     }
 }
@@ -1050,6 +1279,112 @@ impl LowerFnDefn for FnDefn {
     }
 }
 
+trait LowerClosureDefn {
+    fn lower_closure(
+        &self,
+        closure_id: chalk_ir::ClosureId<ChalkIr>,
+        env: &Env,
+    ) -> LowerResult<rust_ir::ClosureDatum<ChalkIr>>;
+}
+
+impl LowerClosureDefn for ClosureDefn {
+    fn lower_closure(
+        &self,
+        closure_id: chalk_ir::ClosureId<ChalkIr>,
+        env: &Env,
+    ) -> LowerResult<rust_ir::ClosureDatum<ChalkIr>> {
+        let interner = env.interner();
+        let binders = env.in_binders(self.all_parameters(), |env| {
+            let inputs_and_output = env.in_binders(vec![], |env| {
+                let args: LowerResult<_> =
+                    self.argument_types.iter().map(|t| t.lower(env)).collect();
+                Ok(rust_ir::FnDefInputsAndOutputDatum {
+                    argument_types: args?,
+                    return_type: self.return_type.lower(env)?,
+                })
+            })?;
+
+            // The upvars are represented as a tuple of the captured types
+            let upvars = chalk_ir::Substitution::from_fallible(
+                interner,
+                self.upvars.iter().map(|t| Ok(t.lower(env)?)),
+            )?;
+            let upvars = chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                name: chalk_ir::TypeName::Tuple(upvars.len(interner)),
+                substitution: upvars,
+            })
+            .intern(interner);
+
+            Ok(rust_ir::ClosureDatumBound {
+                inputs_and_output,
+                upvars,
+            })
+        })?;
+
+        let kind = match self.kind {
+            ClosureKind::Fn => rust_ir::ClosureKind::Fn,
+            ClosureKind::FnMut => rust_ir::ClosureKind::FnMut,
+            ClosureKind::FnOnce => rust_ir::ClosureKind::FnOnce,
+        };
+
+        Ok(rust_ir::ClosureDatum {
+            id: closure_id,
+            kind,
+            binders,
+        })
+    }
+}
+
+trait LowerGeneratorDefn {
+    fn lower_generator(
+        &self,
+        generator_id: chalk_ir::GeneratorId<ChalkIr>,
+        env: &Env,
+    ) -> LowerResult<(
+        rust_ir::GeneratorDatum<ChalkIr>,
+        rust_ir::GeneratorWitnessDatum<ChalkIr>,
+    )>;
+}
+
+impl LowerGeneratorDefn for GeneratorDefn {
+    fn lower_generator(
+        &self,
+        generator_id: chalk_ir::GeneratorId<ChalkIr>,
+        env: &Env,
+    ) -> LowerResult<(
+        rust_ir::GeneratorDatum<ChalkIr>,
+        rust_ir::GeneratorWitnessDatum<ChalkIr>,
+    )> {
+        let input_output = env.in_binders(self.all_parameters(), |env| {
+            let upvars: LowerResult<_> = self.upvars.iter().map(|t| t.lower(env)).collect();
+            Ok(rust_ir::GeneratorInputOutputDatum {
+                resume_type: self.resume_ty.lower(env)?,
+                yield_type: self.yield_ty.lower(env)?,
+                return_type: self.return_ty.lower(env)?,
+                upvars: upvars?,
+            })
+        })?;
+
+        let inner_types = env.in_binders(self.all_parameters(), |env| {
+            let witness_lifetimes = self.witness_lifetimes.iter().map(|id| {
+                chalk_ir::WithKind::new(chalk_ir::VariableKind::Lifetime, id.str.clone())
+            });
+            let types = env.in_binders(witness_lifetimes, |env| {
+                self.witness_types.iter().map(|t| t.lower(env)).collect()
+            })?;
+            Ok(rust_ir::GeneratorWitnessExistential { types })
+        })?;
+
+        let generator_datum = rust_ir::GeneratorDatum {
+            id: generator_id,
+            input_output,
+        };
+        let witness_datum = rust_ir::GeneratorWitnessDatum { inner_types };
+
+        Ok((generator_datum, witness_datum))
+    }
+}
+
 trait LowerTraitRef {
     fn lower(&self, env: &Env) -> LowerResult<chalk_ir::TraitRef<ChalkIr>>;
 }
@@ -1349,6 +1684,12 @@ impl LowerTy for Ty {
                     ApplyTypeLookup::FnDef(id) => {
                         (chalk_ir::TypeName::FnDef(id), env.fn_def_kind(id))
                     }
+                    ApplyTypeLookup::Closure(id) => {
+                        (chalk_ir::TypeName::Closure(id), env.closure_kind(id))
+                    }
+                    ApplyTypeLookup::Generator(id) => {
+                        (chalk_ir::TypeName::Generator(id), env.generator_kind(id))
+                    }
                     ApplyTypeLookup::Opaque(id) => {
                         (chalk_ir::TypeName::OpaqueType(id), env.opaque_kind(id))
                     }
@@ -1577,7 +1918,7 @@ impl LowerImpl for Impl {
         impl_id: ImplId<ChalkIr>,
         associated_ty_value_ids: &AssociatedTyValueIds,
     ) -> LowerResult<rust_ir::ImplDatum<ChalkIr>> {
-        debug_heading!("LowerImpl::lower_impl(impl_id={:?})", impl_id);
+        let _span = debug_span!("LowerImpl::lower_impl", ?impl_id).entered();
 
         let polarity = self.polarity.lower();
         let binders = empty_env.in_binders(self.all_parameters(), |env| {
@@ -1741,6 +2082,11 @@ impl LowerGoal<LoweredProgram> for Goal {
             opaque_ty_ids: &program.opaque_ty_ids,
             adt_kinds: &program.adt_kinds,
             fn_def_kinds: &program.fn_def_kinds,
+            closure_ids: &program.closure_ids,
+            closure_kinds: &program.closure_kinds,
+            generator_ids: &program.generator_ids,
+            generator_kinds: &program.generator_kinds,
+            foreign_ty_ids: &program.foreign_ty_ids,
             trait_kinds: &program.trait_kinds,
             opaque_ty_kinds: &program.opaque_ty_kinds,
             associated_ty_lookups: &associated_ty_lookups,
@@ -1829,6 +2175,7 @@ impl LowerWellKnownTrait for WellKnownTrait {
             Self::FnMut => rust_ir::WellKnownTrait::FnMut,
             Self::Fn => rust_ir::WellKnownTrait::Fn,
             Self::Unsize => rust_ir::WellKnownTrait::Unsize,
+            Self::Generator => rust_ir::WellKnownTrait::Generator,
         }
     }
 }
@@ -1919,6 +2266,37 @@ fn ast_scalar_to_chalk_scalar(scalar: ScalarType) -> chalk_ir::Scalar {
     }
 }
 
+/// Lowers the `#[variance(..)]` attribute of an item with `num_parameters`
+/// generic parameters. Items without the attribute are invariant in all of
+/// their parameters.
+fn lower_variances(
+    name: &Identifier,
+    num_parameters: usize,
+    variances: &Option<Vec<Variance>>,
+) -> LowerResult<Vec<chalk_ir::Variance>> {
+    let variances = match variances {
+        Some(variances) => variances,
+        None => return Ok(vec![chalk_ir::Variance::Invariant; num_parameters]),
+    };
+
+    if variances.len() != num_parameters {
+        return Err(RustIrError::IncorrectNumberOfVariances {
+            identifier: name.clone(),
+            expected: num_parameters,
+            actual: variances.len(),
+        });
+    }
+
+    Ok(variances
+        .iter()
+        .map(|variance| match variance {
+            Variance::Invariant => chalk_ir::Variance::Invariant,
+            Variance::Covariant => chalk_ir::Variance::Covariant,
+            Variance::Contravariant => chalk_ir::Variance::Contravariant,
+        })
+        .collect())
+}
+
 fn ast_mutability_to_chalk_mutability(mutability: Mutability) -> chalk_ir::Mutability {
     match mutability {
         Mutability::Mut => chalk_ir::Mutability::Mut,
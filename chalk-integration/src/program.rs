@@ -1,19 +1,22 @@
 use crate::interner::ChalkIr;
-use crate::{tls, Identifier, TypeKind};
+use crate::{tls, Identifier, RawId, TypeKind};
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::debug::Angle;
 use chalk_ir::{
-    debug::SeparatorTraitRef, AdtId, AliasTy, ApplicationTy, AssocTypeId, FnDefId, GenericArg,
-    Goal, Goals, ImplId, Lifetime, OpaqueTy, OpaqueTyId, ProgramClause, ProgramClauseImplication,
-    ProgramClauses, ProjectionTy, Substitution, TraitId, Ty,
+    debug::SeparatorTraitRef, AdtId, AliasTy, ApplicationTy, AssocTypeId, Binders, ClosureId,
+    FnDefId, ForeignDefId, GeneratorId, GenericArg, Goal, Goals, ImplId, Lifetime, OpaqueTy,
+    OpaqueTyId, ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy,
+    Substitution, TraitId, TraitRef, Ty, UnificationDatabase, Variance, Variances,
 };
+use chalk_parse::ast::Span;
 use chalk_solve::rust_ir::{
-    AdtDatum, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, FnDefDatum, ImplDatum,
-    ImplType, OpaqueTyDatum, TraitDatum, WellKnownTrait,
+    AdtDatum, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ClosureDatum, FnDefDatum,
+    GeneratorDatum, GeneratorWitnessDatum, ImplDatum, ImplType, OpaqueTyDatum, Polarity,
+    TraitDatum, WellKnownAssocType, WellKnownTrait,
 };
 use chalk_solve::split::Split;
-use chalk_solve::RustIrDatabase;
-use std::collections::{BTreeMap, HashSet};
+use chalk_solve::{Dependency, RustIrDatabase};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 use std::sync::Arc;
 
@@ -29,6 +32,21 @@ pub struct Program {
 
     pub fn_def_kinds: BTreeMap<FnDefId<ChalkIr>, TypeKind>,
 
+    /// From closure name to item-id. Used during lowering only.
+    pub closure_ids: BTreeMap<Identifier, ClosureId<ChalkIr>>,
+
+    /// For each closure:
+    pub closure_kinds: BTreeMap<ClosureId<ChalkIr>, TypeKind>,
+
+    /// From generator name to item-id. Used during lowering only.
+    pub generator_ids: BTreeMap<Identifier, GeneratorId<ChalkIr>>,
+
+    /// For each generator:
+    pub generator_kinds: BTreeMap<GeneratorId<ChalkIr>, TypeKind>,
+
+    /// From foreign type name to item-id. Used during lowering only.
+    pub foreign_ty_ids: BTreeMap<Identifier, ForeignDefId<ChalkIr>>,
+
     /// From trait name to item-id. Used during lowering only.
     pub trait_ids: BTreeMap<Identifier, TraitId<ChalkIr>>,
 
@@ -40,6 +58,21 @@ pub struct Program {
 
     pub fn_def_data: BTreeMap<FnDefId<ChalkIr>, Arc<FnDefDatum<ChalkIr>>>,
 
+    /// For each ADT, the variances of its generic parameters:
+    pub adt_variances: BTreeMap<AdtId<ChalkIr>, Vec<Variance>>,
+
+    /// For each fn definition, the variances of its generic parameters:
+    pub fn_def_variances: BTreeMap<FnDefId<ChalkIr>, Vec<Variance>>,
+
+    /// For each closure:
+    pub closure_data: BTreeMap<ClosureId<ChalkIr>, Arc<ClosureDatum<ChalkIr>>>,
+
+    /// For each generator:
+    pub generator_data: BTreeMap<GeneratorId<ChalkIr>, Arc<GeneratorDatum<ChalkIr>>>,
+
+    /// For each generator, its witness types:
+    pub generator_witness_data: BTreeMap<GeneratorId<ChalkIr>, Arc<GeneratorWitnessDatum<ChalkIr>>>,
+
     /// For each impl:
     pub impl_data: BTreeMap<ImplId<ChalkIr>, Arc<ImplDatum<ChalkIr>>>,
 
@@ -65,17 +98,138 @@ pub struct Program {
     /// For each trait lang item
     pub well_known_traits: BTreeMap<WellKnownTrait, TraitId<ChalkIr>>,
 
+    /// For each associated type of a trait lang item that chalk-solve refers to
+    pub well_known_assoc_types: BTreeMap<WellKnownAssocType, AssocTypeId<ChalkIr>>,
+
     /// For each associated ty declaration `type Foo` found in a trait:
     pub associated_ty_data: BTreeMap<AssocTypeId<ChalkIr>, Arc<AssociatedTyDatum<ChalkIr>>>,
 
     /// For each user-specified clause
     pub custom_clauses: Vec<ProgramClause<ChalkIr>>,
 
-    /// Store the traits marked with `#[object_safe]`
-    pub object_safe_traits: HashSet<TraitId<ChalkIr>>,
+    /// For each item (including associated type declarations and values),
+    /// the range of source text it was lowered from:
+    pub item_spans: BTreeMap<RawId, Span>,
 }
 
 impl Program {
+    /// Returns the source span of the item with the given id, if it was
+    /// lowered from source text.
+    pub fn item_span(&self, id: RawId) -> Option<Span> {
+        self.item_spans.get(&id).copied()
+    }
+
+    /// Compares this program to `new`, an edited version of it, to find out
+    /// which cached solver state is still valid for `new`. Returns the
+    /// traits and impls whose program clauses may differ, or `None` if
+    /// anything besides traits and impls changed.
+    pub(crate) fn changed_dependencies(
+        &self,
+        new: &Program,
+    ) -> Option<HashSet<Dependency<ChalkIr>>> {
+        let Program {
+            adt_ids,
+            adt_kinds,
+            fn_def_ids,
+            fn_def_kinds,
+            closure_ids,
+            closure_kinds,
+            generator_ids,
+            generator_kinds,
+            foreign_ty_ids,
+            trait_ids,
+            trait_kinds,
+            adt_data,
+            fn_def_data,
+            adt_variances,
+            fn_def_variances,
+            closure_data,
+            generator_data,
+            generator_witness_data,
+            impl_data: _,
+            associated_ty_values: _,
+            opaque_ty_ids,
+            opaque_ty_kinds,
+            opaque_ty_data,
+            hidden_opaque_types,
+            trait_data: _,
+            well_known_traits,
+            well_known_assoc_types,
+            associated_ty_data: _,
+            custom_clauses,
+            item_spans: _,
+        } = self;
+        let unchanged = *adt_ids == new.adt_ids
+            && *adt_kinds == new.adt_kinds
+            && *fn_def_ids == new.fn_def_ids
+            && *fn_def_kinds == new.fn_def_kinds
+            && *closure_ids == new.closure_ids
+            && *closure_kinds == new.closure_kinds
+            && *generator_ids == new.generator_ids
+            && *generator_kinds == new.generator_kinds
+            && *foreign_ty_ids == new.foreign_ty_ids
+            && *trait_ids == new.trait_ids
+            && *trait_kinds == new.trait_kinds
+            && *adt_data == new.adt_data
+            && *fn_def_data == new.fn_def_data
+            && *adt_variances == new.adt_variances
+            && *fn_def_variances == new.fn_def_variances
+            && *closure_data == new.closure_data
+            && *generator_data == new.generator_data
+            && *generator_witness_data == new.generator_witness_data
+            && *opaque_ty_ids == new.opaque_ty_ids
+            && *opaque_ty_kinds == new.opaque_ty_kinds
+            && *opaque_ty_data == new.opaque_ty_data
+            && *hidden_opaque_types == new.hidden_opaque_types
+            && *well_known_traits == new.well_known_traits
+            && *well_known_assoc_types == new.well_known_assoc_types
+            && *custom_clauses == new.custom_clauses;
+        if !unchanged {
+            return None;
+        }
+
+        let mut changed = HashSet::new();
+        for trait_id in changed_keys(&self.trait_data, &new.trait_data) {
+            changed.insert(Dependency::Trait(trait_id));
+        }
+        for id in changed_keys(&self.associated_ty_data, &new.associated_ty_data) {
+            for datum in self
+                .associated_ty_data
+                .get(&id)
+                .into_iter()
+                .chain(new.associated_ty_data.get(&id))
+            {
+                changed.insert(Dependency::Trait(datum.trait_id));
+            }
+        }
+        for impl_id in changed_keys(&self.impl_data, &new.impl_data) {
+            changed.insert(Dependency::Impl(impl_id));
+
+            // Adding, removing or changing the header of an impl changes
+            // which impls its trait has.
+            let old_datum = self.impl_data.get(&impl_id);
+            let new_datum = new.impl_data.get(&impl_id);
+            if old_datum.map(|datum| impl_header(datum))
+                != new_datum.map(|datum| impl_header(datum))
+            {
+                for datum in old_datum.into_iter().chain(new_datum) {
+                    changed.insert(Dependency::Trait(datum.trait_id()));
+                }
+            }
+        }
+        for id in changed_keys(&self.associated_ty_values, &new.associated_ty_values) {
+            for value in self
+                .associated_ty_values
+                .get(&id)
+                .into_iter()
+                .chain(new.associated_ty_values.get(&id))
+            {
+                changed.insert(Dependency::Impl(value.impl_id));
+            }
+        }
+        Some(changed)
+    }
+
     /// Returns the ids for all impls declared in this crate.
     pub(crate) fn local_impl_ids(&self) -> Vec<ImplId<ChalkIr>> {
         self.impl_data
@@ -86,6 +240,28 @@ impl Program {
     }
 }
 
+/// The parts of an impl that decide which impls its trait has.
+fn impl_header(datum: &ImplDatum<ChalkIr>) -> (Polarity, ImplType, Binders<&TraitRef<ChalkIr>>) {
+    (
+        datum.polarity,
+        datum.impl_type,
+        datum.binders.map_ref(|bound| &bound.trait_ref),
+    )
+}
+
+/// Returns the keys that are only in one of `old` and `new`, or whose values
+/// differ between them.
+fn changed_keys<K: Copy + Ord, V: PartialEq>(
+    old: &BTreeMap<K, V>,
+    new: &BTreeMap<K, V>,
+) -> BTreeSet<K> {
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| old.get(key) != new.get(key))
+        .copied()
+        .collect()
+}
+
 impl tls::DebugContext for Program {
     fn debug_adt_id(
         &self,
@@ -115,6 +291,35 @@ impl tls::DebugContext for Program {
         }
     }
 
+    fn display_impl_id(
+        &self,
+        impl_id: ImplId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some(impl_datum) = self.impl_data.get(&impl_id) {
+            let interner = self.interner();
+            let binders = &impl_datum.binders.binders;
+            let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
+            let parameters = trait_ref.substitution.parameters(interner);
+            write!(fmt, "impl")?;
+            if !binders.is_empty(interner) {
+                write!(fmt, "{:?}", binders.inner_debug(interner))?;
+            }
+            write!(
+                fmt,
+                " {}{:?}{:?} for {:?}",
+                if impl_datum.is_positive() { "" } else { "!" },
+                trait_ref.trait_id,
+                Angle(&parameters[1..]),
+                parameters[0]
+            )
+        } else {
+            fmt.debug_struct("InvalidImplId")
+                .field("index", &impl_id.0)
+                .finish()
+        }
+    }
+
     fn debug_assoc_type_id(
         &self,
         assoc_type_id: AssocTypeId<ChalkIr>,
@@ -143,6 +348,52 @@ impl tls::DebugContext for Program {
         }
     }
 
+    fn debug_closure_id(
+        &self,
+        closure_id: ClosureId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some(k) = self.closure_kinds.get(&closure_id) {
+            write!(fmt, "{}", k.name)
+        } else {
+            fmt.debug_struct("InvalidItemId")
+                .field("index", &closure_id.0)
+                .finish()
+        }
+    }
+
+    fn debug_generator_id(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some(k) = self.generator_kinds.get(&generator_id) {
+            write!(fmt, "{}", k.name)
+        } else {
+            fmt.debug_struct("InvalidItemId")
+                .field("index", &generator_id.0)
+                .finish()
+        }
+    }
+
+    fn debug_foreign_def_id(
+        &self,
+        foreign_def_id: ForeignDefId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        if let Some((name, _)) = self
+            .foreign_ty_ids
+            .iter()
+            .find(|(_, id)| **id == foreign_def_id)
+        {
+            write!(fmt, "{}", name)
+        } else {
+            fmt.debug_struct("InvalidItemId")
+                .field("index", &foreign_def_id.0)
+                .finish()
+        }
+    }
+
     fn debug_alias(
         &self,
         alias_ty: &AliasTy<ChalkIr>,
@@ -351,6 +602,21 @@ impl RustIrDatabase<ChalkIr> for Program {
         self.fn_def_data[&id].clone()
     }
 
+    fn closure_datum(&self, id: ClosureId<ChalkIr>) -> Arc<ClosureDatum<ChalkIr>> {
+        self.closure_data[&id].clone()
+    }
+
+    fn generator_datum(&self, id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        self.generator_data[&id].clone()
+    }
+
+    fn generator_witness_datum(
+        &self,
+        id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        self.generator_witness_data[&id].clone()
+    }
+
     fn impls_for_trait(
         &self,
         trait_id: TraitId<ChalkIr>,
@@ -398,6 +664,15 @@ impl RustIrDatabase<ChalkIr> for Program {
         self.well_known_traits.get(&well_known_trait).map(|x| *x)
     }
 
+    fn well_known_assoc_type_id(
+        &self,
+        well_known_assoc_type: WellKnownAssocType,
+    ) -> Option<AssocTypeId<ChalkIr>> {
+        self.well_known_assoc_types
+            .get(&well_known_assoc_type)
+            .copied()
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<ChalkIr>,
@@ -409,7 +684,26 @@ impl RustIrDatabase<ChalkIr> for Program {
         &ChalkIr
     }
 
-    fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
-        self.object_safe_traits.contains(&trait_id)
+    fn unification_database(&self) -> &dyn UnificationDatabase<ChalkIr> {
+        self
+    }
+
+    fn is_object_safe(&self, _trait_id: TraitId<ChalkIr>) -> bool {
+        // Traits have no methods in the Rust IR, so there is nothing that
+        // `object_safety` doesn't already check.
+        true
+    }
+}
+
+impl UnificationDatabase<ChalkIr> for Program {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
+        Variances::from(
+            self.interner(),
+            self.fn_def_variances[&fn_def_id].iter().copied(),
+        )
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
+        Variances::from(self.interner(), self.adt_variances[&adt_id].iter().copied())
     }
 }
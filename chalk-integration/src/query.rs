@@ -7,27 +7,39 @@ use crate::lowering::LowerProgram;
 use crate::program::Program;
 use crate::program_environment::ProgramEnvironment;
 use crate::tls;
+use crate::RawId;
 use chalk_ir::TraitId;
 use chalk_solve::clauses::builder::ClauseBuilder;
 use chalk_solve::clauses::program_clauses::ToProgramClauses;
 use chalk_solve::coherence::orphan;
-use chalk_solve::coherence::{CoherenceSolver, SpecializationPriorities};
-use chalk_solve::wf;
+use chalk_solve::coherence::{CoherenceError, CoherenceSolver, SpecializationPriorities};
+use chalk_solve::wf::{self, WfError};
 use chalk_solve::RustIrDatabase;
-use chalk_solve::Solver;
 use chalk_solve::SolverChoice;
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::thread;
 
 #[salsa::query_group(Lowering)]
 pub trait LoweringDatabase: RustIrDatabase<ChalkIr> {
     #[salsa::input]
     fn program_text(&self) -> Arc<String>;
 
+    /// The path of the file the program text was read from, used when
+    /// reporting the location of errors.
+    #[salsa::input]
+    fn program_path(&self) -> Arc<String>;
+
     #[salsa::input]
     fn solver_choice(&self) -> SolverChoice;
 
+    /// How many threads the coherence and well-formedness checks are
+    /// spread over. Each trait, ADT and impl is checked on its own, so
+    /// they can be checked concurrently.
+    #[salsa::input]
+    fn check_threads(&self) -> usize;
+
     fn program_ir(&self) -> Result<Arc<Program>, ChalkError>;
 
     /// Performs coherence check and computes which impls specialize
@@ -43,20 +55,51 @@ pub trait LoweringDatabase: RustIrDatabase<ChalkIr> {
 
     /// The program as logic.
     fn environment(&self) -> Result<Arc<ProgramEnvironment>, ChalkError>;
-
-    /// Creates the solver we can use to solve goals. This solver
-    /// stores intermediate, cached state, which is why it is behind a
-    /// mutex. Moreover, if the set of program clauses change, that
-    /// cached state becomes invalid, so the query is marked as
-    /// volatile, thus ensuring that the solver is recreated in every
-    /// revision (i.e., each time source program changes).
-    #[salsa::volatile]
-    fn solver(&self) -> Arc<Mutex<Solver<ChalkIr>>>;
 }
 
 fn program_ir(db: &impl LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
     let text = db.program_text();
-    Ok(Arc::new(chalk_parse::parse_program(&text)?.lower()?))
+    let program = chalk_parse::parse_program(&text)?.lower().map_err(|e| {
+        let span = e.span();
+        ChalkError::from(e).with_spans(&db.program_path(), &text, span)
+    })?;
+    Ok(Arc::new(program))
+}
+
+/// Converts an error about some items of `program` into a `ChalkError`
+/// that points at the source of those items.
+fn located_error(
+    db: &impl LoweringDatabase,
+    program: &Program,
+    error: impl Into<ChalkError>,
+    ids: impl IntoIterator<Item = RawId>,
+) -> ChalkError {
+    let spans = ids.into_iter().filter_map(|id| program.item_span(id));
+    error
+        .into()
+        .with_spans(&db.program_path(), &db.program_text(), spans)
+}
+
+fn coherence_error(
+    db: &impl LoweringDatabase,
+    program: &Program,
+    error: CoherenceError<ChalkIr>,
+) -> ChalkError {
+    let ids = match error {
+        CoherenceError::OverlappingImpls {
+            impls: (lhs, rhs), ..
+        } => vec![lhs.0, rhs.0],
+        CoherenceError::FailedOrphanCheck { impl_id, .. } => vec![impl_id.0],
+    };
+    located_error(db, program, error, ids)
+}
+
+fn wf_error(db: &impl LoweringDatabase, program: &Program, error: WfError<ChalkIr>) -> ChalkError {
+    let id = match error {
+        WfError::IllFormedTypeDecl { adt_id, .. } => adt_id.0,
+        WfError::IllFormedTraitImpl { impl_id, .. } => impl_id.0,
+    };
+    located_error(db, program, error, Some(id))
 }
 
 fn orphan_check(db: &impl LoweringDatabase) -> Result<(), ChalkError> {
@@ -65,7 +108,8 @@ fn orphan_check(db: &impl LoweringDatabase) -> Result<(), ChalkError> {
     tls::set_current_program(&program, || -> Result<(), ChalkError> {
         let local_impls = program.local_impl_ids();
         for impl_id in local_impls {
-            orphan::perform_orphan_check(db, db.solver_choice(), impl_id)?;
+            orphan::perform_orphan_check(db, db.solver_choice(), impl_id)
+                .map_err(|e| coherence_error(db, &program, e))?;
         }
         Ok(())
     })
@@ -76,18 +120,17 @@ fn coherence(
 ) -> Result<BTreeMap<TraitId<ChalkIr>, Arc<SpecializationPriorities<ChalkIr>>>, ChalkError> {
     let program = db.program_ir()?;
 
-    let priorities_map = tls::set_current_program(&program, || -> Result<_, ChalkError> {
-        let priorities_map: Result<BTreeMap<_, _>, ChalkError> = program
-            .trait_data
-            .keys()
-            .map(|&trait_id| {
-                let solver = CoherenceSolver::new(db, db.solver_choice(), trait_id);
-                let priorities = solver.specialization_priorities()?;
-                Ok((trait_id, priorities))
-            })
-            .collect();
-        let priorities_map = priorities_map?;
-        Ok(priorities_map)
+    let solver_choice = db.solver_choice();
+    let trait_ids: Vec<_> = program.trait_data.keys().copied().collect();
+    let priorities = try_in_parallel(db.check_threads(), &trait_ids, |&trait_id| {
+        tls::set_current_program(&program, || {
+            CoherenceSolver::new(&*program, solver_choice, trait_id).specialization_priorities()
+        })
+    });
+    let priorities_map = tls::set_current_program(&program, || {
+        priorities
+            .map(|priorities| trait_ids.into_iter().zip(priorities).collect())
+            .map_err(|e| coherence_error(db, &program, e))
     });
     let () = db.orphan_check()?;
     priorities_map
@@ -98,18 +141,22 @@ fn checked_program(db: &impl LoweringDatabase) -> Result<Arc<Program>, ChalkErro
 
     db.coherence()?;
 
-    let () = tls::set_current_program(&program, || -> Result<(), ChalkError> {
-        let solver = wf::WfSolver::new(db, db.solver_choice());
-        for &id in program.adt_data.keys() {
-            solver.verify_adt_decl(id)?;
-        }
+    let solver_choice = db.solver_choice();
+    let solver = || wf::WfSolver::new(&*program, solver_choice);
 
-        for &impl_id in program.impl_data.keys() {
-            solver.verify_trait_impl(impl_id)?;
-        }
+    let adt_ids: Vec<_> = program.adt_data.keys().copied().collect();
+    try_in_parallel(db.check_threads(), &adt_ids, |&id| {
+        tls::set_current_program(&program, || solver().verify_adt_decl(id).map_err(Box::new))
+    })
+    .map_err(|e| tls::set_current_program(&program, || wf_error(db, &program, *e)))?;
 
-        Ok(())
-    })?;
+    let impl_ids: Vec<_> = program.impl_data.keys().copied().collect();
+    try_in_parallel(db.check_threads(), &impl_ids, |&impl_id| {
+        tls::set_current_program(&program, || {
+            solver().verify_trait_impl(impl_id).map_err(Box::new)
+        })
+    })
+    .map_err(|e| tls::set_current_program(&program, || wf_error(db, &program, *e)))?;
 
     Ok(program)
 }
@@ -166,7 +213,54 @@ fn environment(db: &impl LoweringDatabase) -> Result<Arc<ProgramEnvironment>, Ch
     Ok(Arc::new(ProgramEnvironment::new(program_clauses)))
 }
 
-fn solver(db: &impl LoweringDatabase) -> Arc<Mutex<Solver<ChalkIr>>> {
-    let choice = db.solver_choice();
-    Arc::new(Mutex::new(choice.into_solver()))
+/// Applies `check` to each of `items`, spreading the work over up to
+/// `threads` threads, and returns the results in the order of `items`.
+/// If any check fails, returns the error for the earliest item that failed,
+/// just like checking the items in order would.
+fn try_in_parallel<T, R, E>(
+    threads: usize,
+    items: &[T],
+    check: impl Fn(&T) -> Result<R, E> + Sync,
+) -> Result<Vec<R>, E>
+where
+    T: Sync,
+    R: Send,
+    E: Send,
+{
+    let threads = threads.min(items.len());
+    if threads <= 1 {
+        return items.iter().map(check).collect();
+    }
+
+    // The index of the next item to check, and of the earliest item that
+    // failed so far; items after that one need not be checked.
+    let next = AtomicUsize::new(0);
+    let first_error = AtomicUsize::new(items.len());
+    let mut results: Vec<(usize, Result<R, E>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= first_error.load(Ordering::Relaxed) {
+                            return results;
+                        }
+                        let result = check(&items[index]);
+                        if result.is_err() {
+                            first_error.fetch_min(index, Ordering::Relaxed);
+                        }
+                        results.push((index, result));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
 }
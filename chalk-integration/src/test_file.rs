@@ -0,0 +1,751 @@
+//! Tests written as `.chalk` files rather than as Rust code. A test file
+//! is a chalk program whose `//~` comments say what goals should yield,
+//! or what error the program should be rejected with:
+//!
+//! ```text
+//! trait Foo { }
+//! struct S { }
+//! impl Foo for S { }
+//!
+//! //~ goal { S: Foo } yields { "Unique" }
+//! //~ goal { exists<T> { T: Foo } } yields_all {
+//! //~     "substitution [?0 := S], lifetime constraints []"
+//! //~ }
+//! ```
+//!
+//! The annotations mirror the `test!` macro of chalk's own test suite:
+//!
+//! - `goal { G } yields { "Y" }` checks that both solvers yield `Y`;
+//! - `goal { G } yields_all { "Y1", ..., "YN" }` checks that the SLG
+//!   solver yields exactly these answers, in this order;
+//! - `goal { G } yields_first { "Y1", ..., "YN" }` checks that the SLG
+//!   solver yields at least these answers first;
+//! - `error_msg { "E" }` checks that the program is rejected with `E`.
+//!
+//! Any of the `yields` forms can be restricted to one solver with
+//! `yields[slg]` or `yields[recursive]`, and a goal can be followed by
+//! several of them. As in `test!`, expectations are compared ignoring
+//! whitespace, and only need to be a prefix of the actual output.
+//!
+//! An annotation can span several consecutive `//~` lines. Since `//~`
+//! lines are comments to the chalk parser, the whole file is the program.
+//!
+//! `TestRun::bless` rewrites the expectations that failed with the actual
+//! output; for `error_msg`, with the first line of the error, which is the
+//! message without the source snippet.
+
+use crate::db::ChalkDatabase;
+use crate::error::ChalkError;
+use crate::interner::ChalkIr;
+use crate::lowering::LowerGoal;
+use crate::query::LoweringDatabase;
+use crate::tls;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution, SolverChoice};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// What a goal is expected to yield; see `check_solutions`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestGoal<S> {
+    /// The solver should produce this aggregated single solution.
+    Aggregated(S),
+    /// The solver should produce exactly these solutions.
+    All(Vec<S>),
+    /// The solver should produce these solutions first.
+    First(Vec<S>),
+}
+
+impl<S: AsRef<str>> TestGoal<S> {
+    fn expected(&self) -> &[S] {
+        match self {
+            TestGoal::Aggregated(expected) => std::slice::from_ref(expected),
+            TestGoal::All(expected) | TestGoal::First(expected) => expected,
+        }
+    }
+}
+
+/// Why the solutions of a goal didn't match a `TestGoal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The solution at `index` didn't match the expected one.
+    Solution {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+    /// There were more solutions than expected.
+    TooMany,
+    /// There were fewer solutions than expected.
+    TooFew,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Solution {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "solution {} differs:\nexpected:\n{}\nactual:\n{}",
+                index, expected, actual
+            ),
+            Mismatch::TooMany => write!(f, "Unexpected next solution"),
+            Mismatch::TooFew => write!(f, "Not enough solutions found"),
+        }
+    }
+}
+
+/// Whether `actual` output matches `expected`, that is, whether it starts
+/// with `expected` once whitespace is removed from both. An empty
+/// expectation matches nothing.
+pub fn output_matches(actual: &str, expected: &str) -> bool {
+    let expected: String = expected.chars().filter(|c| !c.is_whitespace()).collect();
+    let actual: String = actual.chars().filter(|c| !c.is_whitespace()).collect();
+    !expected.is_empty() && actual.starts_with(&expected)
+}
+
+/// Solves `goal` with the current solver of `db`, returning the solutions
+/// that `expected` is to be checked against, as they are displayed. For
+/// `TestGoal::First`, at most as many solutions as expected are returned;
+/// for `TestGoal::All`, at most one more, which is enough to tell that
+/// there are too many.
+pub fn solve_test_goal<S: AsRef<str>>(
+    db: &ChalkDatabase,
+    goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    expected: &TestGoal<S>,
+) -> Vec<String> {
+    let program = db.program_ir().unwrap();
+    tls::set_current_program(&program, || match expected {
+        TestGoal::Aggregated(_) => {
+            // sort constraints, since the different solvers may output them in different order
+            let solution = db.solve(goal).map(|mut solution| {
+                solution.sort_constraints();
+                solution
+            });
+            vec![display_solution(solution)]
+        }
+        TestGoal::All(_) | TestGoal::First(_) => {
+            let limit = match expected {
+                TestGoal::First(expected) => expected.len(),
+                _ => expected.expected().len() + 1,
+            };
+            let mut solutions = vec![];
+            if limit > 0 {
+                db.solve_multiple(goal, |result, _| {
+                    solutions.push(format!("{}", result.as_ref().map(|v| v.display(&ChalkIr))));
+                    solutions.len() < limit
+                });
+            }
+            solutions
+        }
+    })
+}
+
+/// Displays an aggregated solution the way test expectations spell it.
+pub fn display_solution(solution: Option<Solution<ChalkIr>>) -> String {
+    match solution {
+        Some(v) => format!("{}", v.display(&ChalkIr)),
+        None => "No possible solution".to_string(),
+    }
+}
+
+/// Checks the `solutions` returned by `solve_test_goal` against
+/// `expected`.
+pub fn check_solutions<S: AsRef<str>>(
+    solutions: &[String],
+    expected: &TestGoal<S>,
+) -> Result<(), Mismatch> {
+    for (index, (actual, expected)) in solutions.iter().zip(expected.expected()).enumerate() {
+        if !output_matches(actual, expected.as_ref()) {
+            return Err(Mismatch::Solution {
+                index,
+                expected: expected.as_ref().to_string(),
+                actual: actual.clone(),
+            });
+        }
+    }
+    let expected = expected.expected().len();
+    if solutions.len() < expected {
+        Err(Mismatch::TooFew)
+    } else if solutions.len() > expected {
+        Err(Mismatch::TooMany)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the `.chalk` files in `dir` and its subdirectories, sorted.
+pub fn find_test_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension() == Some("chalk".as_ref()) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// A parsed `.chalk` test file; see the module docs.
+#[derive(Clone, Debug)]
+pub struct TestFile {
+    text: String,
+    error_msg: Option<Expectation>,
+    goals: Vec<GoalTest>,
+}
+
+#[derive(Clone, Debug)]
+struct GoalTest {
+    /// The line the goal is on, starting at 1.
+    line: usize,
+    text: String,
+    expectations: Vec<(Option<SolverChoice>, Expectation)>,
+}
+
+/// The expected strings of an annotation, along with where they are in the
+/// file so that they can be blessed.
+#[derive(Clone, Debug)]
+struct Expectation {
+    kind: ExpectationKind,
+    strings: Vec<String>,
+    /// The byte range of the braces around the strings.
+    span: Range<usize>,
+    /// What the line the braces start on starts with, up to and including
+    /// the `//~` and the space after it, used when blessing an expectation
+    /// across several lines.
+    line_prefix: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ExpectationKind {
+    Aggregated,
+    All,
+    First,
+    ErrorMsg,
+}
+
+impl Expectation {
+    fn test_goal(&self) -> TestGoal<&str> {
+        let strings = self.strings.iter().map(|s| &s[..]);
+        match self.kind {
+            ExpectationKind::Aggregated | ExpectationKind::ErrorMsg => {
+                TestGoal::Aggregated(self.strings.first().map_or("", |s| &s[..]))
+            }
+            ExpectationKind::All => TestGoal::All(strings.collect()),
+            ExpectationKind::First => TestGoal::First(strings.collect()),
+        }
+    }
+
+    /// The solver choices to check this expectation with, if it isn't
+    /// restricted to `solver_choice`.
+    fn solver_choices(&self, solver_choice: Option<SolverChoice>) -> Vec<SolverChoice> {
+        match (solver_choice, self.kind) {
+            (Some(solver_choice), _) => vec![solver_choice],
+            (None, ExpectationKind::Aggregated) => {
+                vec![SolverChoice::slg_default(), SolverChoice::recursive()]
+            }
+            (None, _) => vec![SolverChoice::slg_default()],
+        }
+    }
+
+    /// The text to replace `span` with so that the expectation is
+    /// `strings`.
+    fn blessed(&self, strings: &[String]) -> String {
+        match strings {
+            [] => "{ }".to_string(),
+            [string] => format!("{{ {} }}", quote(string)),
+            strings => {
+                let mut text = "{".to_string();
+                for (i, string) in strings.iter().enumerate() {
+                    let comma = if i + 1 < strings.len() { "," } else { "" };
+                    text += &format!("\n{}    {}{}", self.line_prefix, quote(string), comma);
+                }
+                text + &format!("\n{}}}", self.line_prefix)
+            }
+        }
+    }
+}
+
+/// An error in the `//~` annotations of a test file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl TestFile {
+    /// Parses the `//~` annotations of a test file. The program itself is
+    /// only parsed when the test is run.
+    pub fn parse(text: &str) -> Result<TestFile, ParseError> {
+        let mut file = TestFile {
+            text: text.to_string(),
+            error_msg: None,
+            goals: vec![],
+        };
+        for block in AnnotationBlock::all(text) {
+            AnnotationParser {
+                block: &block,
+                pos: 0,
+            }
+            .parse_into(&mut file)?;
+        }
+        if file.error_msg.is_some() && !file.goals.is_empty() {
+            return Err(ParseError {
+                line: file.goals[0].line,
+                message: "a program with an `error_msg` cannot have goals".to_string(),
+            });
+        }
+        Ok(file)
+    }
+
+    /// The text of the test file.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Runs the test, checking each goal with each of the solvers it is
+    /// expected to yield something with. Errors in the program are
+    /// reported relative to `path`.
+    pub fn run(&self, path: &str) -> TestRun {
+        let mut run = TestRun::default();
+        let mut db = ChalkDatabase::with_path(path, &self.text, SolverChoice::default());
+        let program = match (db.checked_program(), &self.error_msg) {
+            (Ok(program), None) => program,
+            (Ok(_), Some(_)) => {
+                run.fail(1, None, "the program has no errors".to_string());
+                run.cannot_bless = true;
+                return run;
+            }
+            (Err(error), None) => {
+                run.fail(1, None, format!("the program has errors: {}", error));
+                run.cannot_bless = true;
+                return run;
+            }
+            (Err(error), Some(expectation)) => {
+                let error = error.to_string();
+                if !output_matches(&error, &expectation.strings[0]) {
+                    run.fail(1, None, format!("the program has other errors: {}", error));
+                    // the first line is the message; the rest points into the program
+                    let message = error.lines().next().unwrap_or_default().to_string();
+                    run.replace(expectation, &[message]);
+                }
+                return run;
+            }
+        };
+
+        for goal_test in &self.goals {
+            // lowered against the program checked above: changing the
+            // solver choice may change the outcome of the checks
+            let goal = chalk_parse::parse_goal(&goal_test.text)
+                .map_err(ChalkError::from)
+                .and_then(|goal| Ok(goal.lower(&*program)?));
+            let goal = match goal {
+                Ok(goal) => goal.into_peeled_goal(db.interner()),
+                Err(error) => {
+                    run.fail(goal_test.line, None, format!("invalid goal: {}", error));
+                    run.cannot_bless = true;
+                    continue;
+                }
+            };
+            for (solver_choice, expectation) in &goal_test.expectations {
+                let test_goal = expectation.test_goal();
+                let mut all_solutions: Vec<Vec<String>> = vec![];
+                let mut failed = false;
+                for solver_choice in expectation.solver_choices(*solver_choice) {
+                    db.set_solver_choice(solver_choice);
+                    let solutions = solve_test_goal(&db, &goal, &test_goal);
+                    if let Err(mismatch) = check_solutions(&solutions, &test_goal) {
+                        run.fail(goal_test.line, Some(solver_choice), mismatch.to_string());
+                        failed = true;
+                        // We stopped one solution past the expected ones; if
+                        // there are more still, blessing cannot list them all.
+                        if let (Mismatch::TooMany, TestGoal::All(_)) = (&mismatch, &test_goal) {
+                            let more = TestGoal::All(solutions.clone());
+                            if solve_test_goal(&db, &goal, &more).len() > solutions.len() {
+                                run.cannot_bless = true;
+                            }
+                        }
+                    }
+                    all_solutions.push(solutions);
+                }
+                if failed {
+                    if all_solutions.windows(2).all(|w| w[0] == w[1]) {
+                        run.replace(expectation, &all_solutions[0]);
+                    } else {
+                        run.fail(
+                            goal_test.line,
+                            None,
+                            "the solvers disagree, so this cannot be blessed; \
+                             use `yields[slg]` and `yields[recursive]`"
+                                .to_string(),
+                        );
+                        run.cannot_bless = true;
+                    }
+                }
+            }
+        }
+        run
+    }
+}
+
+/// The outcome of `TestFile::run`.
+#[derive(Clone, Debug, Default)]
+pub struct TestRun {
+    pub failures: Vec<TestFailure>,
+    /// The expectations to rewrite when blessing, as byte ranges of the
+    /// file and their new text.
+    replacements: Vec<(Range<usize>, String)>,
+    /// Whether some failure cannot be fixed by blessing.
+    cannot_bless: bool,
+}
+
+/// A failed check of a `TestRun`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestFailure {
+    /// The line of the goal or error the check is for, starting at 1.
+    pub line: usize,
+    /// The solver the check was run with, if any.
+    pub solver_choice: Option<SolverChoice>,
+    pub message: String,
+}
+
+impl fmt::Display for TestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(solver_choice) = &self.solver_choice {
+            write!(f, " ({:?})", solver_choice)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl TestRun {
+    fn fail(&mut self, line: usize, solver_choice: Option<SolverChoice>, message: String) {
+        self.failures.push(TestFailure {
+            line,
+            solver_choice,
+            message,
+        });
+    }
+
+    fn replace(&mut self, expectation: &Expectation, strings: &[String]) {
+        self.replacements
+            .push((expectation.span.clone(), expectation.blessed(strings)));
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Whether blessing would fix every failure.
+    pub fn can_bless(&self) -> bool {
+        !self.cannot_bless
+    }
+
+    /// Returns the text of `file` with the expectations that failed
+    /// replaced by the actual output.
+    pub fn bless(&self, file: &TestFile) -> String {
+        let mut text = file.text.clone();
+        let mut replacements = self.replacements.clone();
+        replacements.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+        for (span, replacement) in replacements {
+            text.replace_range(span, &replacement);
+        }
+        text
+    }
+}
+
+/// Consecutive `//~` lines of a file, with the `//~` removed.
+struct AnnotationBlock {
+    text: String,
+    /// For each line, its offset in `text`, its offset in the file and
+    /// its prefix up to and including the `//~ `.
+    lines: Vec<(usize, usize, String)>,
+    /// The line of the file the block starts on, starting at 1.
+    first_line: usize,
+}
+
+impl AnnotationBlock {
+    fn all(file: &str) -> Vec<AnnotationBlock> {
+        let mut blocks: Vec<AnnotationBlock> = vec![];
+        let mut in_block = false;
+        let mut file_offset = 0;
+        for (index, line) in file.split('\n').enumerate() {
+            let line_offset = file_offset;
+            file_offset += line.len() + 1;
+            let marker = match line.find("//~") {
+                Some(marker) if line[..marker].trim().is_empty() => marker + "//~".len(),
+                _ => {
+                    in_block = false;
+                    continue;
+                }
+            };
+            if !in_block {
+                blocks.push(AnnotationBlock {
+                    text: String::new(),
+                    lines: vec![],
+                    first_line: index + 1,
+                });
+                in_block = true;
+            }
+            let block = blocks.last_mut().unwrap();
+            if !block.lines.is_empty() {
+                block.text.push('\n');
+            }
+            let prefix_end = if line[marker..].starts_with(' ') {
+                marker + 1
+            } else {
+                marker
+            };
+            block.lines.push((
+                block.text.len(),
+                line_offset + marker,
+                line[..prefix_end].to_string(),
+            ));
+            block.text += &line[marker..];
+        }
+        blocks
+    }
+
+    /// The index in `lines` of the line containing `offset`.
+    fn line_index(&self, offset: usize) -> usize {
+        self.lines
+            .iter()
+            .rposition(|&(start, _, _)| start <= offset)
+            .unwrap()
+    }
+
+    fn file_offset(&self, offset: usize) -> usize {
+        let (start, file_start, _) = &self.lines[self.line_index(offset)];
+        file_start + offset - start
+    }
+
+    fn file_line(&self, offset: usize) -> usize {
+        self.first_line + self.line_index(offset)
+    }
+}
+
+struct AnnotationParser<'a> {
+    block: &'a AnnotationBlock,
+    pos: usize,
+}
+
+impl<'a> AnnotationParser<'a> {
+    fn parse_into(&mut self, file: &mut TestFile) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.pos == self.block.text.len() {
+                return Ok(());
+            }
+            let line = self.block.file_line(self.pos);
+            match self.word()? {
+                "goal" => {
+                    let text = self.goal()?;
+                    let mut expectations = vec![];
+                    loop {
+                        self.skip_whitespace();
+                        if !self.rest().starts_with("yields") {
+                            break;
+                        }
+                        let kind = match self.word()? {
+                            "yields" => ExpectationKind::Aggregated,
+                            "yields_all" => ExpectationKind::All,
+                            "yields_first" => ExpectationKind::First,
+                            word => return Err(self.error(format!("unknown `{}`", word))),
+                        };
+                        let solver_choice = self.solver_choice()?;
+                        if solver_choice == Some(SolverChoice::recursive())
+                            && kind != ExpectationKind::Aggregated
+                        {
+                            return Err(self.error(
+                                "cannot test the recursive solver with yields_first or yields_all",
+                            ));
+                        }
+                        expectations.push((solver_choice, self.expectation(kind)?));
+                    }
+                    if expectations.is_empty() {
+                        return Err(self.error("expected `yields`, `yields_all` or `yields_first`"));
+                    }
+                    file.goals.push(GoalTest {
+                        line,
+                        text,
+                        expectations,
+                    });
+                }
+                "error_msg" => {
+                    if file.error_msg.is_some() {
+                        return Err(self.error("only one `error_msg` is allowed"));
+                    }
+                    let expectation = self.expectation(ExpectationKind::ErrorMsg)?;
+                    if expectation.strings.len() != 1 {
+                        return Err(self.error("`error_msg` expects one string"));
+                    }
+                    file.error_msg = Some(expectation);
+                }
+                word => {
+                    return Err(
+                        self.error(format!("expected `goal` or `error_msg`, found `{}`", word))
+                    )
+                }
+            }
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.block.text[self.pos..]
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.block.file_line(self.pos.min(self.block.text.len())),
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, ParseError> {
+        self.skip_whitespace();
+        let block = self.block;
+        let rest = &block.text[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected `goal` or `error_msg`"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Parses `{ G }`, returning `G`.
+    fn goal(&mut self) -> Result<String, ParseError> {
+        self.eat('{')?;
+        let start = self.pos;
+        let mut depth = 1;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => continue,
+            }
+            if depth == 0 {
+                self.pos += i + 1;
+                return Ok(self.block.text[start..self.pos - 1].trim().to_string());
+            }
+        }
+        Err(self.error("unclosed `{`"))
+    }
+
+    /// Parses an optional `[slg]` or `[recursive]`.
+    fn solver_choice(&mut self) -> Result<Option<SolverChoice>, ParseError> {
+        if !self.rest().starts_with('[') {
+            return Ok(None);
+        }
+        self.eat('[')?;
+        let solver_choice = match self.word()? {
+            "slg" => SolverChoice::slg_default(),
+            "recursive" => SolverChoice::recursive(),
+            word => return Err(self.error(format!("unknown solver `{}`", word))),
+        };
+        self.eat(']')?;
+        Ok(Some(solver_choice))
+    }
+
+    /// Parses `{ "S1", ..., "SN" }`.
+    fn expectation(&mut self, kind: ExpectationKind) -> Result<Expectation, ParseError> {
+        self.eat('{')?;
+        let start = self.pos - 1;
+        let mut strings = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with('}') {
+                break;
+            }
+            strings.push(self.string()?);
+            self.skip_whitespace();
+            if self.rest().starts_with(',') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.eat('}')?;
+        if kind == ExpectationKind::Aggregated && strings.len() != 1 {
+            return Err(self.error("`yields` expects one string"));
+        }
+        let span = self.block.file_offset(start)..self.block.file_offset(self.pos);
+        let line_prefix = self.block.lines[self.block.line_index(start)].2.clone();
+        Ok(Expectation {
+            kind,
+            strings,
+            span,
+            line_prefix,
+        })
+    }
+
+    /// Parses a string literal, with `\"`, `\\` and `\n` escapes.
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.eat('"')?;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, c @ '"')) | Some((_, c @ '\\')) => string.push(c),
+                    _ => break,
+                },
+                c => string.push(c),
+            }
+        }
+        Err(self.error("invalid string"))
+    }
+}
+
+/// Writes `string` as a string literal `AnnotationParser::string` reads.
+fn quote(string: &str) -> String {
+    let escaped = string
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
@@ -1,9 +1,9 @@
 use crate::interner::ChalkIr;
 use chalk_ir::{
     debug::SeparatorTraitRef, AdtId, AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKinds,
-    GenericArg, Goal, Goals, Lifetime, OpaqueTy, OpaqueTyId, ProgramClause,
-    ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses, Substitution,
-    TraitId, Ty, VariableKinds,
+    ClosureId, ForeignDefId, GeneratorId, GenericArg, Goal, Goals, ImplId, Lifetime, OpaqueTy,
+    OpaqueTyId, ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy,
+    QuantifiedWhereClauses, Substitution, TraitId, Ty, VariableKinds,
 };
 use std::cell::RefCell;
 use std::fmt;
@@ -26,6 +26,12 @@ pub trait DebugContext {
         fmt: &mut fmt::Formatter<'_>,
     ) -> Result<(), fmt::Error>;
 
+    fn display_impl_id(
+        &self,
+        id: ImplId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
     fn debug_assoc_type_id(
         &self,
         id: AssocTypeId<ChalkIr>,
@@ -38,6 +44,24 @@ pub trait DebugContext {
         fmt: &mut fmt::Formatter<'_>,
     ) -> Result<(), fmt::Error>;
 
+    fn debug_closure_id(
+        &self,
+        id: ClosureId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
+    fn debug_generator_id(
+        &self,
+        id: GeneratorId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
+    fn debug_foreign_def_id(
+        &self,
+        id: ForeignDefId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error>;
+
     fn debug_alias(
         &self,
         alias: &AliasTy<ChalkIr>,
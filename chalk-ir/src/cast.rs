@@ -104,6 +104,12 @@ impl<I: Interner> CastTo<WhereClause<I>> for LifetimeOutlives<I> {
     }
 }
 
+impl<I: Interner> CastTo<WhereClause<I>> for TypeOutlives<I> {
+    fn cast_to(self, _interner: &I) -> WhereClause<I> {
+        WhereClause::TypeOutlives(self)
+    }
+}
+
 impl<T, I> CastTo<DomainGoal<I>> for T
 where
     T: CastTo<WhereClause<I>>,
@@ -147,6 +153,12 @@ impl<I: Interner> CastTo<Goal<I>> for EqGoal<I> {
     }
 }
 
+impl<I: Interner> CastTo<Goal<I>> for SubtypeGoal<I> {
+    fn cast_to(self, interner: &I) -> Goal<I> {
+        GoalData::SubtypeGoal(self).intern(interner)
+    }
+}
+
 impl<I: Interner, T: HasInterner<Interner = I> + CastTo<Goal<I>>> CastTo<Goal<I>> for Binders<T> {
     fn cast_to(self, interner: &I) -> Goal<I> {
         GoalData::Quantified(
@@ -325,6 +337,15 @@ where
     }
 }
 
+impl<I> CastTo<TypeName<I>> for ClosureId<I>
+where
+    I: Interner,
+{
+    fn cast_to(self, _interner: &I) -> TypeName<I> {
+        TypeName::Closure(self)
+    }
+}
+
 impl<I> CastTo<TypeName<I>> for OpaqueTyId<I>
 where
     I: Interner,
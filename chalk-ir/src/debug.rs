@@ -14,6 +14,22 @@ impl<I: Interner> Debug for AdtId<I> {
     }
 }
 
+impl<I: Interner> ImplId<I> {
+    /// Displays the header of the impl (e.g., `impl<T> Foo for Vec<T>`),
+    /// for use in diagnostics.
+    pub fn display(self) -> ImplIdDisplay<I> {
+        ImplIdDisplay(self)
+    }
+}
+
+pub struct ImplIdDisplay<I: Interner>(ImplId<I>);
+
+impl<I: Interner> Display for ImplIdDisplay<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        I::display_impl_id(self.0, fmt).unwrap_or_else(|| write!(fmt, "{:?}", self.0))
+    }
+}
+
 impl<I: Interner> Debug for AssocTypeId<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         I::debug_assoc_type_id(*self, fmt)
@@ -27,6 +43,26 @@ impl<I: Interner> Debug for FnDefId<I> {
     }
 }
 
+impl<I: Interner> Debug for ClosureId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+        I::debug_closure_id(*self, fmt).unwrap_or_else(|| write!(fmt, "ClosureId({:?})", self.0))
+    }
+}
+
+impl<I: Interner> Debug for GeneratorId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+        I::debug_generator_id(*self, fmt)
+            .unwrap_or_else(|| write!(fmt, "GeneratorId({:?})", self.0))
+    }
+}
+
+impl<I: Interner> Debug for ForeignDefId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+        I::debug_foreign_def_id(*self, fmt)
+            .unwrap_or_else(|| write!(fmt, "ForeignDefId({:?})", self.0))
+    }
+}
+
 impl<I: Interner> Debug for Ty<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         I::debug_ty(self, fmt).unwrap_or_else(|| write!(fmt, "{:?}", self.interned))
@@ -165,6 +201,10 @@ impl<I: Interner> Debug for TypeName<I> {
             TypeName::OpaqueType(opaque_ty) => write!(fmt, "!{:?}", opaque_ty),
             TypeName::Slice => write!(fmt, "{{slice}}"),
             TypeName::FnDef(fn_def) => write!(fmt, "{:?}", fn_def),
+            TypeName::Closure(closure) => write!(fmt, "{:?}", closure),
+            TypeName::Generator(generator) => write!(fmt, "{:?}", generator),
+            TypeName::GeneratorWitness(generator) => write!(fmt, "{{witness}}{:?}", generator),
+            TypeName::Foreign(foreign_ty) => write!(fmt, "{:?}", foreign_ty),
             TypeName::Raw(mutability) => write!(fmt, "{:?}", mutability),
             TypeName::Ref(mutability) => write!(fmt, "{:?}", mutability),
             TypeName::Never => write!(fmt, "Never"),
@@ -314,6 +354,7 @@ impl<I: Interner> Debug for GoalData<I> {
             GoalData::All(ref goals) => write!(fmt, "all{:?}", goals),
             GoalData::Not(ref g) => write!(fmt, "not {{ {:?} }}", g),
             GoalData::EqGoal(ref wc) => write!(fmt, "{:?}", wc),
+            GoalData::SubtypeGoal(ref wc) => write!(fmt, "{:?}", wc),
             GoalData::DomainGoal(ref wc) => write!(fmt, "{:?}", wc),
             GoalData::CannotProve(()) => write!(fmt, r"¯\_(ツ)_/¯"),
         }
@@ -542,6 +583,12 @@ impl<I: Interner> Debug for LifetimeOutlives<I> {
     }
 }
 
+impl<I: Interner> Debug for TypeOutlives<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "{:?}: {:?}", self.ty, self.lifetime)
+    }
+}
+
 pub struct ProjectionTyDebug<'a, I: Interner> {
     projection_ty: &'a ProjectionTy<I>,
     interner: &'a I,
@@ -637,6 +684,7 @@ impl<I: Interner> Debug for WhereClause<I> {
             WhereClause::Implemented(tr) => write!(fmt, "Implemented({:?})", tr.with_colon()),
             WhereClause::AliasEq(a) => write!(fmt, "{:?}", a),
             WhereClause::LifetimeOutlives(l_o) => write!(fmt, "{:?}", l_o),
+            WhereClause::TypeOutlives(t_o) => write!(fmt, "{:?}", t_o),
         }
     }
 }
@@ -686,6 +734,18 @@ impl<I: Interner> Debug for EqGoal<I> {
     }
 }
 
+impl<I: Interner> Debug for SubtypeGoal<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "({:?} <: {:?})", self.a, self.b)
+    }
+}
+
+impl<I: Interner> Debug for Variances<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "{:?}", self.interned)
+    }
+}
+
 impl<T: HasInterner + Debug> Debug for Binders<T> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         let Binders {
@@ -802,7 +862,20 @@ impl<I: Interner> Debug for Constraint<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
             Constraint::Outlives(a, b) => write!(fmt, "{:?}: {:?}", a, b),
+            Constraint::TypeOutlives(ty, lifetime) => write!(fmt, "{:?}: {:?}", ty, lifetime),
+        }
+    }
+}
+
+impl<I: Interner> Display for FlounderedGoal<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let FlounderedGoal { goal, variable } = self;
+
+        write!(f, "`{:?}` floundered", goal.value.goal)?;
+        if let Some(variable) = variable {
+            write!(f, " on unresolved `^0.{}`", variable)?;
         }
+        Ok(())
     }
 }
 
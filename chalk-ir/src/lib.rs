@@ -28,7 +28,12 @@ pub struct NoSolution;
 /// Error type for the `UnificationOps::program_clauses` method --
 /// indicates that the complete set of program clauses for this goal
 /// cannot be enumerated.
-pub struct Floundered;
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Floundered<I: Interner> {
+    /// The type that is not known well enough to enumerate the clauses,
+    /// typically an unresolved inference variable.
+    pub ty: Ty<I>,
+}
 
 macro_rules! impl_debugs {
     ($($id:ident), *) => {
@@ -51,9 +56,6 @@ pub mod fold;
 #[macro_use]
 pub mod visit;
 
-#[macro_use]
-pub mod debug_macros;
-
 pub mod cast;
 
 pub mod interner;
@@ -191,6 +193,23 @@ pub enum TypeName<I: Interner> {
     /// a function definition
     FnDef(FnDefId<I>),
 
+    /// the type of a closure; the substitution contains the generic
+    /// parameters of the closure, which determine its signature and upvars
+    Closure(ClosureId<I>),
+
+    /// the type of a generator; the substitution contains the generic
+    /// parameters of the generator, which determine its signature and upvars
+    Generator(GeneratorId<I>),
+
+    /// the witness of a generator, which represents the types that are
+    /// live across its yield points; the substitution is the same as for
+    /// the corresponding `Generator` type
+    GeneratorWitness(GeneratorId<I>),
+
+    /// foreign types, declared with `extern type`; these are unsized and
+    /// only implement auto traits through explicit impls
+    Foreign(ForeignDefId<I>),
+
     /// the string primitive type
     Str,
 
@@ -290,6 +309,26 @@ pub struct OpaqueTyId<I: Interner>(pub I::DefId);
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FnDefId<I: Interner>(pub I::DefId);
 
+/// The id of a closure; the signature, upvars and kind of the closure can
+/// be found by invoking the [`closure_datum`] method.
+///
+/// [`closure_datum`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.closure_datum
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClosureId<I: Interner>(pub I::DefId);
+
+/// The id of a generator; the signature, upvars and witness types of the
+/// generator can be found by invoking the [`generator_datum`] and
+/// [`generator_witness_datum`] methods.
+///
+/// [`generator_datum`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.generator_datum
+/// [`generator_witness_datum`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.generator_witness_datum
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GeneratorId<I: Interner>(pub I::DefId);
+
+/// The id of a foreign type, declared with `extern type`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ForeignDefId<I: Interner>(pub I::DefId);
+
 impl_debugs!(ImplId, ClauseId);
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
@@ -1190,12 +1229,21 @@ pub struct LifetimeOutlives<I: Interner> {
     pub a: Lifetime<I>,
     pub b: Lifetime<I>,
 }
+
+/// Type outlives, e.g. `T: 'a`
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+pub struct TypeOutlives<I: Interner> {
+    pub ty: Ty<I>,
+    pub lifetime: Lifetime<I>,
+}
+
 /// Where clauses that can be written by a Rust programmer.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, SuperVisit, HasInterner, Zip)]
 pub enum WhereClause<I: Interner> {
     Implemented(TraitRef<I>),
     AliasEq(AliasEq<I>),
     LifetimeOutlives(LifetimeOutlives<I>),
+    TypeOutlives(TypeOutlives<I>),
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
@@ -1358,6 +1406,7 @@ impl<I: Interner> WhereClause<I> {
             WhereClause::Implemented(trait_ref) => Some(trait_ref.trait_id),
             WhereClause::AliasEq(_) => None,
             WhereClause::LifetimeOutlives(_) => None,
+            WhereClause::TypeOutlives(_) => None,
         }
     }
 }
@@ -1468,6 +1517,15 @@ pub struct EqGoal<I: Interner> {
     pub b: GenericArg<I>,
 }
 
+/// Proves that the type `a` is a subtype of the type `b`. Subtyping
+/// differs from equality only in the lifetimes involved, so proving a
+/// subtype goal may produce outlives constraints.
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
+pub struct SubtypeGoal<I: Interner> {
+    pub a: Ty<I>,
+    pub b: Ty<I>,
+}
+
 /// Proves that the given type alias **normalizes** to the given
 /// type. A projection `T::Foo` normalizes to the type `U` if we can
 /// **match it to an impl** and that impl has a `type Foo = V` where
@@ -1953,6 +2011,99 @@ impl<I: Interner> CanonicalVarKinds<I> {
     }
 }
 
+/// The variance of a generic parameter, which determines how subtyping
+/// of the parameter relates to subtyping of the type it appears in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Variance {
+    /// `a <: b` implies `F<a> <: F<b>`.
+    Covariant,
+    /// `F<a> <: F<b>` only if `a == b`.
+    Invariant,
+    /// `a <: b` implies `F<b> <: F<a>`.
+    Contravariant,
+}
+
+impl Variance {
+    /// Composes two variances: given a parameter with variance `self`
+    /// appearing in a position with variance `other`, returns the
+    /// variance of the parameter in the overall type.
+    pub fn xform(self, other: Variance) -> Variance {
+        match (self, other) {
+            (Variance::Invariant, _) | (_, Variance::Invariant) => Variance::Invariant,
+            (Variance::Covariant, other) => other,
+            (Variance::Contravariant, Variance::Covariant) => Variance::Contravariant,
+            (Variance::Contravariant, Variance::Contravariant) => Variance::Covariant,
+        }
+    }
+
+    /// Flips the direction of the variance: covariance becomes
+    /// contravariance and vice versa.
+    pub fn invert(self) -> Variance {
+        match self {
+            Variance::Covariant => Variance::Contravariant,
+            Variance::Invariant => Variance::Invariant,
+            Variance::Contravariant => Variance::Covariant,
+        }
+    }
+}
+
+/// The variances of the generic parameters of some item, in order.
+#[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+pub struct Variances<I: Interner> {
+    interned: I::InternedVariances,
+}
+
+impl<I: Interner> Variances<I> {
+    pub fn interned(&self) -> &I::InternedVariances {
+        &self.interned
+    }
+
+    pub fn from(interner: &I, variances: impl IntoIterator<Item = Variance>) -> Self {
+        Self::from_fallible(
+            interner,
+            variances
+                .into_iter()
+                .map(|p| -> Result<Variance, ()> { Ok(p) }),
+        )
+        .unwrap()
+    }
+
+    pub fn from_fallible<E>(
+        interner: &I,
+        variances: impl IntoIterator<Item = Result<Variance, E>>,
+    ) -> Result<Self, E> {
+        Ok(Variances {
+            interned: I::intern_variances(interner, variances.into_iter())?,
+        })
+    }
+
+    pub fn iter(&self, interner: &I) -> std::slice::Iter<'_, Variance> {
+        self.as_slice(interner).iter()
+    }
+
+    pub fn is_empty(&self, interner: &I) -> bool {
+        self.as_slice(interner).is_empty()
+    }
+
+    pub fn len(&self, interner: &I) -> usize {
+        self.as_slice(interner).len()
+    }
+
+    pub fn as_slice(&self, interner: &I) -> &[Variance] {
+        interner.variances_data(&self.interned)
+    }
+}
+
+/// Information needed by unification and subtyping that the inference
+/// machinery cannot compute on its own.
+pub trait UnificationDatabase<I: Interner> {
+    /// The variances of the generic parameters of the given function.
+    fn fn_def_variance(&self, fn_def_id: FnDefId<I>) -> Variances<I>;
+
+    /// The variances of the generic parameters of the given ADT.
+    fn adt_variance(&self, adt_id: AdtId<I>) -> Variances<I>;
+}
+
 /// Wraps a "canonicalized item". Items are canonicalized as follows:
 ///
 /// All unresolved existential variables are "renumbered" according to their
@@ -2188,6 +2339,10 @@ pub enum GoalData<I: Interner> {
     /// Make two things equal; the rules for doing so are well known to the logic
     EqGoal(EqGoal<I>),
 
+    /// Make one type a subtype of another; like `EqGoal`, the rules for
+    /// doing so are well known to the logic
+    SubtypeGoal(SubtypeGoal<I>),
+
     /// A "domain goal" indicates some base sort of goal that can be
     /// proven via program clauses
     DomainGoal(DomainGoal<I>),
@@ -2224,7 +2379,13 @@ pub enum QuantifierKind {
 /// checking in the compiler.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
 pub enum Constraint<I: Interner> {
+    /// Outlives constraint `'a: 'b`, indicating that the value of `'a` must be
+    /// a superset of the value of `'b`.
     Outlives(Lifetime<I>, Lifetime<I>),
+
+    /// Type outlives constraint `T: 'a`, indicating that all lifetimes
+    /// appearing in `T` must outlive `'a`.
+    TypeOutlives(Ty<I>, Lifetime<I>),
 }
 
 /// A mapping of inference variables to instantiations thereof.
@@ -2478,3 +2639,17 @@ pub struct AnswerSubst<I: Interner> {
     pub constraints: Vec<InEnvironment<Constraint<I>>>,
     pub delayed_subgoals: Vec<InEnvironment<Goal<I>>>,
 }
+
+/// A goal whose solutions could not be enumerated (it "floundered"),
+/// e.g. `?T: Sized`, which holds for far too many types to list them all.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+pub struct FlounderedGoal<I: Interner> {
+    /// The goal that floundered. Its canonical variables are the inference
+    /// variables that were still unresolved at the time.
+    pub goal: Canonical<InEnvironment<Goal<I>>>,
+
+    /// The index of the canonical variable that made the goal floundered,
+    /// if it is known. This is `None` if, for example, the goal got too
+    /// large rather than too unconstrained.
+    pub variable: Option<usize>,
+}
@@ -5,10 +5,11 @@
 //! The more interesting impls of `Visit` remain in the `visit` module.
 
 use crate::{
-    AdtId, AssocTypeId, ClausePriority, DebruijnIndex, FloatTy, FnDefId, GenericArg, Goals, ImplId,
-    IntTy, Interner, Mutability, OpaqueTyId, PlaceholderIndex, ProgramClause, ProgramClauseData,
-    ProgramClauses, QuantifiedWhereClauses, QuantifierKind, Scalar, Substitution, SuperVisit,
-    TraitId, UintTy, UniverseIndex, Visit, VisitResult, Visitor,
+    AdtId, AssocTypeId, ClausePriority, ClosureId, DebruijnIndex, FloatTy, FnDefId, ForeignDefId,
+    GeneratorId, GenericArg, Goals, ImplId, IntTy, Interner, Mutability, OpaqueTyId,
+    PlaceholderIndex, ProgramClause, ProgramClauseData, ProgramClauses, QuantifiedWhereClauses,
+    QuantifierKind, Scalar, Substitution, SuperVisit, TraitId, UintTy, UniverseIndex, Visit,
+    VisitResult, Visitor,
 };
 use std::{marker::PhantomData, sync::Arc};
 
@@ -233,6 +234,9 @@ id_visit!(ImplId);
 id_visit!(AdtId);
 id_visit!(TraitId);
 id_visit!(OpaqueTyId);
+id_visit!(ClosureId);
+id_visit!(GeneratorId);
+id_visit!(ForeignDefId);
 id_visit!(AssocTypeId);
 id_visit!(FnDefId);
 
@@ -212,6 +212,9 @@ eq_zip!(I => AdtId<I>);
 eq_zip!(I => TraitId<I>);
 eq_zip!(I => AssocTypeId<I>);
 eq_zip!(I => OpaqueTyId<I>);
+eq_zip!(I => ClosureId<I>);
+eq_zip!(I => GeneratorId<I>);
+eq_zip!(I => ForeignDefId<I>);
 eq_zip!(I => TypeName<I>);
 eq_zip!(I => QuantifierKind);
 eq_zip!(I => PhantomData<I>);
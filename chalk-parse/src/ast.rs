@@ -11,6 +11,16 @@ impl Span {
     pub fn new(lo: usize, hi: usize) -> Self {
         Span { lo, hi }
     }
+
+    /// Returns the 1-based line and column of the start of this span
+    /// within `text`.
+    pub fn line_col(&self, text: &str) -> (usize, usize) {
+        let before = &text[..self.lo.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let col = before[line_start..].chars().count() + 1;
+        (line, col)
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -20,30 +30,67 @@ pub struct Program {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Item {
-    StructDefn(StructDefn),
+    AdtDefn(AdtDefn),
     FnDefn(FnDefn),
+    ClosureDefn(ClosureDefn),
+    GeneratorDefn(GeneratorDefn),
+    Foreign(ForeignDefn),
     TraitDefn(TraitDefn),
     OpaqueTyDefn(OpaqueTyDefn),
     Impl(Impl),
     Clause(Clause),
 }
 
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::AdtDefn(defn) => defn.span,
+            Item::FnDefn(defn) => defn.span,
+            Item::ClosureDefn(defn) => defn.span,
+            Item::GeneratorDefn(defn) => defn.span,
+            Item::Foreign(defn) => defn.span,
+            Item::TraitDefn(defn) => defn.span,
+            Item::OpaqueTyDefn(defn) => defn.span,
+            Item::Impl(defn) => defn.span,
+            Item::Clause(clause) => clause.span,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct StructDefn {
+pub struct AdtDefn {
     pub name: Identifier,
     pub variable_kinds: Vec<VariableKind>,
     pub where_clauses: Vec<QuantifiedWhereClause>,
-    pub fields: Vec<Field>,
-    pub flags: StructFlags,
+    pub variants: Vec<Variant>,
+    pub flags: AdtFlags,
+    pub kind: AdtKind,
+    pub variances: Option<Vec<Variance>>,
+    pub span: Span,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AdtKind {
+    Struct,
+    Enum,
+    Union,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct StructFlags {
+pub struct AdtFlags {
     pub upstream: bool,
     pub fundamental: bool,
     pub phantom_data: bool,
 }
 
+/// A single variant of an ADT. Structs and unions have exactly one
+/// variant, which carries the name of the ADT itself.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Variant {
+    pub name: Identifier,
+    pub fields: Vec<Field>,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FnDefn {
     pub name: Identifier,
@@ -51,6 +98,45 @@ pub struct FnDefn {
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub argument_types: Vec<Ty>,
     pub return_type: Ty,
+    pub variances: Option<Vec<Variance>>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClosureDefn {
+    pub name: Identifier,
+    pub kind: ClosureKind,
+    pub variable_kinds: Vec<VariableKind>,
+    pub argument_types: Vec<Ty>,
+    pub return_type: Ty,
+    pub upvars: Vec<Ty>,
+    pub span: Span,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ClosureKind {
+    Fn,
+    FnMut,
+    FnOnce,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GeneratorDefn {
+    pub name: Identifier,
+    pub variable_kinds: Vec<VariableKind>,
+    pub upvars: Vec<Ty>,
+    pub resume_ty: Ty,
+    pub yield_ty: Ty,
+    pub return_ty: Ty,
+    pub witness_types: Vec<Ty>,
+    pub witness_lifetimes: Vec<Identifier>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ForeignDefn {
+    pub name: Identifier,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -61,6 +147,7 @@ pub struct TraitDefn {
     pub assoc_ty_defns: Vec<AssocTyDefn>,
     pub flags: TraitFlags,
     pub well_known: Option<WellKnownTrait>,
+    pub span: Span,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -73,6 +160,7 @@ pub enum WellKnownTrait {
     FnMut,
     Fn,
     Unsize,
+    Generator,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -83,7 +171,6 @@ pub struct TraitFlags {
     pub fundamental: bool,
     pub non_enumerable: bool,
     pub coinductive: bool,
-    pub object_safe: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -92,6 +179,7 @@ pub struct AssocTyDefn {
     pub variable_kinds: Vec<VariableKind>,
     pub bounds: Vec<QuantifiedInlineBound>,
     pub where_clauses: Vec<QuantifiedWhereClause>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -100,6 +188,7 @@ pub struct OpaqueTyDefn {
     pub variable_kinds: Vec<VariableKind>,
     pub identifier: Identifier,
     pub bounds: Vec<QuantifiedInlineBound>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -181,6 +270,7 @@ pub struct Impl {
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub assoc_ty_values: Vec<AssocTyValue>,
     pub impl_type: ImplType,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -195,6 +285,7 @@ pub struct AssocTyValue {
     pub variable_kinds: Vec<VariableKind>,
     pub value: Ty,
     pub default: bool,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -284,6 +375,13 @@ pub enum Mutability {
     Not,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Variance {
+    Invariant,
+    Covariant,
+    Contravariant,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Lifetime {
     Id { name: Identifier },
@@ -338,6 +436,7 @@ pub enum WhereClause {
     Implemented { trait_ref: TraitRef },
     ProjectionEq { projection: ProjectionTy, ty: Ty },
     LifetimeOutlives { a: Lifetime, b: Lifetime },
+    TypeOutlives { ty: Ty, lifetime: Lifetime },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -362,6 +461,7 @@ pub enum DomainGoal {
 pub enum LeafGoal {
     DomainGoal { goal: DomainGoal },
     UnifyGenericArgs { a: GenericArg, b: GenericArg },
+    SubtypeTys { a: Ty, b: Ty },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -383,6 +483,7 @@ pub struct Clause {
     pub variable_kinds: Vec<VariableKind>,
     pub consequence: DomainGoal,
     pub conditions: Vec<Box<Goal>>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
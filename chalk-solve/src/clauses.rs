@@ -1,6 +1,9 @@
 use self::builder::ClauseBuilder;
 use self::env_elaborator::elaborate_env_clauses;
 use self::program_clauses::ToProgramClauses;
+use crate::goal_builder::GoalBuilder;
+use crate::object_safety;
+use crate::solve::stats;
 use crate::split::Split;
 use crate::RustIrDatabase;
 use chalk_ir::cast::Cast;
@@ -8,6 +11,7 @@ use chalk_ir::could_match::CouldMatch;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use rustc_hash::FxHashSet;
+use tracing::{debug, debug_span};
 
 pub mod builder;
 mod builtin_traits;
@@ -51,7 +55,7 @@ pub fn push_auto_trait_impls<I: Interner>(
     auto_trait_id: TraitId<I>,
     adt_id: AdtId<I>,
 ) {
-    debug_heading!("push_auto_trait_impls({:?}, {:?})", auto_trait_id, adt_id);
+    let _span = debug_span!("push_auto_trait_impls", ?auto_trait_id, ?adt_id).entered();
 
     let adt_datum = &builder.db.adt_datum(adt_id);
     let interner = builder.interner();
@@ -73,7 +77,10 @@ pub fn push_auto_trait_impls<I: Interner>(
         return;
     }
 
-    let binders = adt_datum.binders.map_ref(|b| &b.fields);
+    // The auto trait must hold for the fields of every variant of the ADT.
+    let binders = adt_datum
+        .binders
+        .map_ref(|b| b.all_fields().cloned().collect::<Vec<_>>());
     builder.push_binders(&binders, |builder, fields| {
         let self_ty: Ty<_> = ApplicationTy {
             name: adt_id.cast(interner),
@@ -93,6 +100,8 @@ pub fn push_auto_trait_impls<I: Interner>(
         //      ...
         //      FieldN: MyAutoTrait
         // }
+        //
+        // For enums, the fields of all variants are included.
         builder.push_clause(
             auto_trait_ref,
             fields.iter().map(|field_ty| TraitRef {
@@ -123,11 +132,7 @@ pub fn push_auto_trait_impls_opaque<I: Interner>(
     auto_trait_id: TraitId<I>,
     opaque_id: OpaqueTyId<I>,
 ) {
-    debug_heading!(
-        "push_auto_trait_impls_opaque({:?}, {:?})",
-        auto_trait_id,
-        opaque_id
-    );
+    let _span = debug_span!("push_auto_trait_impls_opaque", ?auto_trait_id, ?opaque_id).entered();
 
     let opaque_ty_datum = &builder.db.opaque_ty_data(opaque_id);
     let interner = builder.interner();
@@ -167,21 +172,195 @@ pub fn push_auto_trait_impls_opaque<I: Interner>(
     });
 }
 
+/// Auto traits for closures are implemented when all of their upvars
+/// implement the auto trait.
+///
+/// ```notrust
+/// forall<P0..Pn> { // generic parameters from the closure
+///     Closure<..>: AutoTrait :-
+///         Upvar0: AutoTrait,
+///         ...
+///         UpvarN: AutoTrait
+/// }
+/// ```
+pub fn push_auto_trait_impls_closure<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    auto_trait_id: TraitId<I>,
+    closure_id: ClosureId<I>,
+) {
+    let _span = debug_span!("push_auto_trait_impls_closure", ?auto_trait_id, ?closure_id).entered();
+    let interner = builder.interner();
+    let closure_datum = builder.db.closure_datum(closure_id);
+    let binders = closure_datum.binders.map_ref(|b| &b.upvars);
+
+    builder.push_binders(&binders, |builder, upvars| {
+        let self_ty: Ty<_> = ApplicationTy {
+            name: closure_id.cast(interner),
+            substitution: builder.substitution_in_scope(),
+        }
+        .intern(interner);
+
+        // trait_ref = `Closure<...>: MyAutoTrait`
+        let auto_trait_ref = TraitRef {
+            trait_id: auto_trait_id,
+            substitution: Substitution::from1(interner, self_ty),
+        };
+
+        // The upvars are given as a tuple; require the auto trait for
+        // each of the captured types individually.
+        let upvar_tys = match upvars.data(interner) {
+            TyData::Apply(ApplicationTy {
+                name: TypeName::Tuple(_),
+                substitution,
+            }) => substitution
+                .iter(interner)
+                .map(|param| param.assert_ty_ref(interner).clone())
+                .collect(),
+            _ => vec![upvars.clone()],
+        };
+
+        builder.push_clause(
+            auto_trait_ref,
+            upvar_tys.into_iter().map(|ty| TraitRef {
+                trait_id: auto_trait_id,
+                substitution: Substitution::from1(interner, ty),
+            }),
+        );
+    });
+}
+
+/// Auto traits for generators are implemented when all of their upvars,
+/// as well as their witness type, implement the auto trait.
+///
+/// ```notrust
+/// forall<P0..Pn> { // generic parameters from the generator
+///     Generator<..>: AutoTrait :-
+///         Upvar0: AutoTrait,
+///         ...
+///         UpvarN: AutoTrait,
+///         GeneratorWitness<..>: AutoTrait
+/// }
+/// ```
+pub fn push_auto_trait_impls_generator<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    auto_trait_id: TraitId<I>,
+    generator_id: GeneratorId<I>,
+) {
+    let _span = debug_span!(
+        "push_auto_trait_impls_generator",
+        ?auto_trait_id,
+        ?generator_id
+    )
+    .entered();
+    let interner = builder.interner();
+    let generator_datum = builder.db.generator_datum(generator_id);
+    let binders = generator_datum.input_output.map_ref(|io| &io.upvars);
+
+    builder.push_binders(&binders, |builder, upvars| {
+        let substitution = builder.substitution_in_scope();
+        let self_ty: Ty<_> = ApplicationTy {
+            name: TypeName::Generator(generator_id),
+            substitution: substitution.clone(),
+        }
+        .intern(interner);
+        let witness_ty: Ty<_> = ApplicationTy {
+            name: TypeName::GeneratorWitness(generator_id),
+            substitution,
+        }
+        .intern(interner);
+
+        // trait_ref = `Generator<...>: MyAutoTrait`
+        let auto_trait_ref = TraitRef {
+            trait_id: auto_trait_id,
+            substitution: Substitution::from1(interner, self_ty),
+        };
+
+        builder.push_clause(
+            auto_trait_ref,
+            upvars
+                .into_iter()
+                .chain(std::iter::once(witness_ty))
+                .map(|ty| TraitRef {
+                    trait_id: auto_trait_id,
+                    substitution: Substitution::from1(interner, ty),
+                }),
+        );
+    });
+}
+
+/// Auto traits for generator witnesses are implemented when all of the
+/// witness types implement the auto trait, for *all* choices of the
+/// existentially bound lifetimes.
+///
+/// ```notrust
+/// forall<P0..Pn> { // generic parameters from the generator
+///     GeneratorWitness<..>: AutoTrait :-
+///         forall<'l0..'ln> { // existential lifetimes of the witness types
+///             WitnessType0: AutoTrait,
+///             ...
+///             WitnessTypeN: AutoTrait
+///         }
+/// }
+/// ```
+///
+/// We require the auto trait to hold for all lifetimes because the precise
+/// regions are erased during type checking: we only know that the witness
+/// types refer to *some* regions.
+pub fn push_auto_trait_impls_generator_witness<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    auto_trait_id: TraitId<I>,
+    generator_id: GeneratorId<I>,
+) {
+    let _span = debug_span!(
+        "push_auto_trait_impls_generator_witness",
+        ?auto_trait_id,
+        ?generator_id
+    )
+    .entered();
+    let interner = builder.interner();
+    let witness_datum = builder.db.generator_witness_datum(generator_id);
+
+    builder.push_binders(&witness_datum.inner_types, |builder, inner_types| {
+        let witness_ty: Ty<_> = ApplicationTy {
+            name: TypeName::GeneratorWitness(generator_id),
+            substitution: builder.substitution_in_scope(),
+        }
+        .intern(interner);
+
+        // trait_ref = `GeneratorWitness<...>: MyAutoTrait`
+        let auto_trait_ref = TraitRef {
+            trait_id: auto_trait_id,
+            substitution: Substitution::from1(interner, witness_ty),
+        };
+
+        let mut gb = GoalBuilder::new(builder.db);
+        let witness_goal = gb.forall(
+            &inner_types.types,
+            auto_trait_id,
+            |gb, _, types, auto_trait_id| {
+                let interner = gb.interner();
+                gb.all(types.iter().map(|ty| TraitRef {
+                    trait_id: auto_trait_id,
+                    substitution: Substitution::from1(interner, ty.clone()),
+                }))
+            },
+        );
+
+        builder.push_clause(auto_trait_ref, std::iter::once(witness_goal));
+    });
+}
+
 /// Given some goal `goal` that must be proven, along with
 /// its `environment`, figures out the program clauses that apply
 /// to this goal from the Rust program. So for example if the goal
 /// is `Implemented(T: Clone)`, then this function might return clauses
 /// derived from the trait `Clone` and its impls.
-pub(crate) fn program_clauses_for_goal<'db, I: Interner>(
+pub fn program_clauses_for_goal<'db, I: Interner>(
     db: &'db dyn RustIrDatabase<I>,
     environment: &Environment<I>,
     goal: &DomainGoal<I>,
-) -> Result<Vec<ProgramClause<I>>, Floundered> {
-    debug_heading!(
-        "program_clauses_for_goal(goal={:?}, environment={:?})",
-        goal,
-        environment
-    );
+) -> Result<Vec<ProgramClause<I>>, Floundered<I>> {
+    let _span = debug_span!("program_clauses_for_goal", ?goal, ?environment).entered();
     let interner = db.interner();
 
     let custom_clauses = db.custom_clauses().into_iter();
@@ -199,6 +378,7 @@ pub(crate) fn program_clauses_for_goal<'db, I: Interner>(
         .collect();
 
     debug!("vec = {:#?}", clauses);
+    stats::record_clauses(goal, clauses.len());
 
     Ok(clauses)
 }
@@ -211,12 +391,12 @@ fn program_clauses_that_could_match<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     environment: &Environment<I>,
     goal: &DomainGoal<I>,
-) -> Result<Vec<ProgramClause<I>>, Floundered> {
+) -> Result<Vec<ProgramClause<I>>, Floundered<I>> {
     let interner = db.interner();
     let mut clauses: Vec<ProgramClause<I>> = vec![];
     let builder = &mut ClauseBuilder::new(db, &mut clauses);
 
-    debug_heading!("program_clauses_that_could_match(goal={:?})", goal);
+    let _span = debug_span!("program_clauses_that_could_match", ?goal).entered();
 
     match goal {
         DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
@@ -234,7 +414,7 @@ fn program_clauses_that_could_match<I: Interner>(
                 } else if self_ty.bound_var(interner).is_some()
                     || self_ty.inference_var(interner).is_some()
                 {
-                    return Err(Floundered);
+                    return Err(Floundered { ty: self_ty });
                 }
             }
 
@@ -253,15 +433,29 @@ fn program_clauses_that_could_match<I: Interner>(
             // the automatic impls for `Foo`.
             let trait_datum = db.trait_datum(trait_id);
             if trait_datum.is_auto_trait() {
-                match trait_ref.self_type_parameter(interner).data(interner) {
+                let self_ty = trait_ref.self_type_parameter(interner);
+                match self_ty.data(interner) {
                     TyData::Apply(apply) => match &apply.name {
                         TypeName::Adt(adt_id) => {
                             push_auto_trait_impls(builder, trait_id, *adt_id);
                         }
+                        TypeName::Closure(closure_id) => {
+                            push_auto_trait_impls_closure(builder, trait_id, *closure_id);
+                        }
+                        TypeName::Generator(generator_id) => {
+                            push_auto_trait_impls_generator(builder, trait_id, *generator_id);
+                        }
+                        TypeName::GeneratorWitness(generator_id) => {
+                            push_auto_trait_impls_generator_witness(
+                                builder,
+                                trait_id,
+                                *generator_id,
+                            );
+                        }
                         _ => {}
                     },
                     TyData::InferenceVar(_, _) | TyData::BoundVar(_) => {
-                        return Err(Floundered);
+                        return Err(Floundered { ty: self_ty });
                     }
                     _ => {}
                 }
@@ -323,13 +517,16 @@ fn program_clauses_that_could_match<I: Interner>(
                 .to_program_clauses(builder),
         },
         DomainGoal::Holds(WhereClause::LifetimeOutlives(_)) => {}
+        DomainGoal::Holds(WhereClause::TypeOutlives(_)) => {}
         DomainGoal::WellFormed(WellFormed::Trait(trait_ref))
         | DomainGoal::LocalImplAllowed(trait_ref) => {
             db.trait_datum(trait_ref.trait_id)
                 .to_program_clauses(builder);
         }
         DomainGoal::ObjectSafe(trait_id) => {
-            if builder.db.is_object_safe(*trait_id) {
+            if builder.db.is_object_safe(*trait_id)
+                && object_safety::is_object_safe(builder.db, *trait_id)
+            {
                 builder.push_fact(DomainGoal::ObjectSafe(*trait_id));
             }
         }
@@ -364,7 +561,7 @@ fn program_clauses_that_could_match<I: Interner>(
                 //
                 // e.g., Normalize(<?X as Iterator>::Item = u32)
                 if (self_ty.is_var(interner)) && trait_datum.is_non_enumerable_trait() {
-                    return Err(Floundered);
+                    return Err(Floundered { ty: self_ty });
                 }
 
                 if let Some(well_known) = trait_datum.well_known {
@@ -406,13 +603,12 @@ fn push_program_clauses_for_associated_type_values_in_impls_of<I: Interner>(
     trait_id: TraitId<I>,
     trait_parameters: &[GenericArg<I>],
 ) {
-    debug_heading!(
-        "push_program_clauses_for_associated_type_values_in_impls_of(\
-         trait_id={:?}, \
-         trait_parameters={:?})",
-        trait_id,
-        trait_parameters,
-    );
+    let _span = debug_span!(
+        "push_program_clauses_for_associated_type_values_in_impls_of",
+        ?trait_id,
+        ?trait_parameters
+    )
+    .entered();
 
     for impl_id in builder.db.impls_for_trait(trait_id, trait_parameters) {
         let impl_datum = builder.db.impl_datum(impl_id);
@@ -445,7 +641,7 @@ fn match_ty<I: Interner>(
     builder: &mut ClauseBuilder<'_, I>,
     environment: &Environment<I>,
     ty: &Ty<I>,
-) -> Result<(), Floundered> {
+) -> Result<(), Floundered<I>> {
     let interner = builder.interner();
     Ok(match ty.data(interner) {
         TyData::Apply(application_ty) => match_type_name(builder, interner, application_ty),
@@ -467,9 +663,11 @@ fn match_ty<I: Interner>(
                 .iter(interner)
                 .map(|p| p.assert_ty_ref(interner))
                 .map(|ty| match_ty(builder, environment, &ty))
-                .collect::<Result<_, Floundered>>()?;
+                .collect::<Result<(), Floundered<I>>>()?;
+        }
+        TyData::BoundVar(_) | TyData::InferenceVar(_, _) => {
+            return Err(Floundered { ty: ty.clone() })
         }
-        TyData::BoundVar(_) | TyData::InferenceVar(_, _) => return Err(Floundered),
         TyData::Dyn(_) => {}
     })
 }
@@ -496,6 +694,10 @@ fn match_type_name<I: Interner>(
             .fn_def_datum(fn_def_id)
             .to_program_clauses(builder),
         TypeName::Tuple(_)
+        | TypeName::Closure(_)
+        | TypeName::Generator(_)
+        | TypeName::GeneratorWitness(_)
+        | TypeName::Foreign(_)
         | TypeName::Scalar(_)
         | TypeName::Str
         | TypeName::Slice
@@ -1,5 +1,6 @@
 use std::iter;
 use std::marker::PhantomData;
+use tracing::{debug, debug_span};
 
 use crate::cast::{Cast, CastTo};
 use crate::RustIrDatabase;
@@ -123,7 +124,7 @@ impl<'me, I: Interner> ClauseBuilder<'me, I> {
         V: Fold<I> + HasInterner<Interner = I>,
         V::Result: std::fmt::Debug,
     {
-        debug_heading!("push_binders({:?})", binders);
+        let _span = debug_span!("push_binders", ?binders).entered();
 
         let old_len = self.binders.len();
         let interner = self.interner();
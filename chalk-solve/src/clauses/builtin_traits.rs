@@ -5,6 +5,7 @@ use chalk_ir::{Floundered, Substitution, Ty};
 mod clone;
 mod copy;
 mod fn_family;
+mod generator;
 mod sized;
 mod unsize;
 
@@ -15,12 +16,12 @@ pub fn add_builtin_program_clauses<I: Interner>(
     builder: &mut ClauseBuilder<'_, I>,
     well_known: WellKnownTrait,
     trait_ref: &TraitRef<I>,
-) -> Result<(), Floundered> {
+) -> Result<(), Floundered<I>> {
     // If `trait_ref` contains bound vars, we want to universally quantify them.
     // `Generalize` collects them for us.
     let generalized = generalize::Generalize::apply(db.interner(), trait_ref);
 
-    builder.push_binders(&generalized, |builder, trait_ref| {
+    let result = builder.push_binders(&generalized, |builder, trait_ref| {
         let self_ty = trait_ref.self_type_parameter(db.interner());
         let ty = self_ty.data(db.interner());
         if let Some(force_impl) = db.force_impl_for(well_known, ty) {
@@ -40,26 +41,39 @@ pub fn add_builtin_program_clauses<I: Interner>(
             WellKnownTrait::Unsize => {
                 unsize::add_unsize_program_clauses(db, builder, &trait_ref, ty)
             }
+            WellKnownTrait::Generator => {
+                generator::add_generator_program_clauses(db, builder, self_ty)?
+            }
             // Drop impls are provided explicitly
             WellKnownTrait::Drop => (),
         }
         Ok(())
+    });
+
+    // Only the self type can make us flounder; report it as it appears
+    // in `trait_ref` rather than in terms of the generalized binders.
+    result.map_err(|_: Floundered<I>| Floundered {
+        ty: trait_ref.self_type_parameter(db.interner()),
     })
 }
 
 /// Like `add_builtin_program_clauses`, but for `DomainGoal::Normalize` involving
-/// a projection (e.g. `<fn(u8) as FnOnce<(u8,)>>::Output`)
+/// a projection (e.g. `<fn(u8) as FnOnce<(u8,)>>::Output` or
+/// `<gen as Generator<()>>::Yield`)
 pub fn add_builtin_assoc_program_clauses<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     builder: &mut ClauseBuilder<'_, I>,
     well_known: WellKnownTrait,
     self_ty: Ty<I>,
-) -> Result<(), Floundered> {
+) -> Result<(), Floundered<I>> {
     match well_known {
         WellKnownTrait::FnOnce => {
             let trait_id = db.well_known_trait_id(well_known).unwrap();
             fn_family::add_fn_trait_program_clauses(db, builder, trait_id, self_ty)?;
         }
+        WellKnownTrait::Generator => {
+            generator::add_generator_program_clauses(db, builder, self_ty)?;
+        }
         _ => {}
     }
     Ok(())
@@ -54,6 +54,16 @@ pub fn add_copy_program_clauses<I: Interner>(
             TypeName::FnDef(_) => {
                 builder.push_fact(trait_ref.clone());
             }
+            TypeName::Closure(closure_id) => {
+                // A closure is Copy if all of its upvars are Copy
+                let interner = db.interner();
+                let upvars = db
+                    .closure_datum(*closure_id)
+                    .binders
+                    .map_ref(|b| &b.upvars)
+                    .substitute(interner, substitution);
+                needs_impl_for_tys(db, builder, trait_ref, iter::once(upvars));
+            }
             _ => return,
         },
         TyData::Function(_) => builder.push_fact(trait_ref.clone()),
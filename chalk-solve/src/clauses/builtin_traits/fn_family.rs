@@ -1,12 +1,115 @@
 use crate::clauses::ClauseBuilder;
 use crate::infer::instantiate::IntoBindersAndValue;
-use crate::rust_ir::WellKnownTrait;
+use crate::rust_ir::{ClosureKind, WellKnownTrait};
 use crate::{Interner, RustIrDatabase, TraitRef};
 use chalk_ir::{
-    AliasTy, ApplicationTy, Binders, Floundered, Normalize, ProjectionTy, Substitution, TraitId,
-    Ty, TyData, TypeName, VariableKinds,
+    AliasTy, ApplicationTy, Binders, ClosureId, Floundered, Normalize, ProjectionTy, Substitution,
+    TraitId, Ty, TyData, TypeName, VariableKinds,
 };
 
+/// Pushes the clauses for a callable type `self_ty` with the given argument
+/// types and output type:
+/// `Implemented(self_ty: FnTrait<(A1, A2, ..., AN)>)` and, if `trait_id` is
+/// `FnOnce`, `Normalize(<self_ty as FnOnce<(A1, ..., AN)>>::Output -> O)`.
+fn push_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_id: TraitId<I>,
+    self_ty: Ty<I>,
+    arg_sub: Substitution<I>,
+    fn_output_ty: Ty<I>,
+) {
+    let interner = db.interner();
+
+    // We are constructing a reference to `FnOnce<Args>`, where
+    // `Args` is a tuple of the function's argument types
+    let tupled = Ty::new(
+        interner,
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Tuple(arg_sub.len(interner)),
+            substitution: arg_sub,
+        }),
+    );
+
+    let tupled_sub = Substitution::from(interner, vec![self_ty, tupled]);
+    // Given a function type `fn(A1, A2, ..., AN)`, construct a `TraitRef`
+    // of the form `fn(A1, A2, ..., AN): FnOnce<(A1, A2, ..., AN)>`
+    let new_trait_ref = TraitRef {
+        trait_id,
+        substitution: tupled_sub.clone(),
+    };
+
+    builder.push_fact(new_trait_ref);
+
+    if let Some(WellKnownTrait::FnOnce) = db.trait_datum(trait_id).well_known {
+        //The `Output` type is defined on the `FnOnce`
+        let fn_once = db.trait_datum(trait_id);
+        assert_eq!(fn_once.well_known, Some(WellKnownTrait::FnOnce));
+        let assoc_types = &fn_once.associated_ty_ids;
+        assert_eq!(
+            assoc_types.len(),
+            1,
+            "FnOnce trait should have exactly one associated type, found {:?}",
+            assoc_types
+        );
+
+        // Construct `Normalize(<fn(A) -> B as FnOnce<(A,)>>::Output -> B)`
+        let assoc_output_ty = assoc_types[0];
+        let proj_ty = ProjectionTy {
+            associated_ty_id: assoc_output_ty,
+            substitution: tupled_sub,
+        };
+        let normalize = Normalize {
+            alias: AliasTy::Projection(proj_ty),
+            ty: fn_output_ty,
+        };
+
+        builder.push_fact(normalize);
+    }
+}
+
+/// Pushes the clauses for a closure type `Closure<..>`. The closure only
+/// implements the `Fn`-family traits that its closure kind allows: e.g. a
+/// `FnMut` closure implements `FnMut` and `FnOnce`, but not `Fn`.
+fn push_closure_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_id: TraitId<I>,
+    self_ty: Ty<I>,
+    closure_id: ClosureId<I>,
+    substitution: &Substitution<I>,
+) {
+    let interner = db.interner();
+    let trait_kind = match db.trait_datum(trait_id).well_known {
+        Some(WellKnownTrait::Fn) => ClosureKind::Fn,
+        Some(WellKnownTrait::FnMut) => ClosureKind::FnMut,
+        Some(WellKnownTrait::FnOnce) => ClosureKind::FnOnce,
+        _ => return,
+    };
+
+    let closure_datum = db.closure_datum(closure_id);
+    if !closure_datum.kind.implements(trait_kind) {
+        return;
+    }
+
+    let inputs_and_output = closure_datum
+        .binders
+        .map_ref(|bound| &bound.inputs_and_output)
+        .substitute(interner, substitution);
+
+    builder.push_binders(&inputs_and_output, |builder, inputs_and_output| {
+        let arg_sub = Substitution::from(interner, inputs_and_output.argument_types);
+        push_clauses(
+            db,
+            builder,
+            trait_id,
+            self_ty,
+            arg_sub,
+            inputs_and_output.return_type,
+        );
+    });
+}
+
 /// Handles clauses for FnOnce/FnMut/Fn.
 /// If `self_ty` is a function, we push a clause of the form
 /// `fn(A1, A2, ..., AN) -> O: FnTrait<(A1, A2, ..., AN)>`, where `FnTrait`
@@ -16,12 +119,15 @@ use chalk_ir::{
 /// `Normalize(<fn(A) -> B as FnOnce<(A,)>>::Output -> B)`
 /// We do not add the usual `Implemented(fn(A) -> b as FnOnce<(A,)>` clause
 /// as a condition, since we already called `push_fact` with it
+///
+/// If `self_ty` is a closure, the same clauses are pushed using the closure's
+/// signature, as long as the closure kind permits implementing `FnTrait`.
 pub fn add_fn_trait_program_clauses<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     builder: &mut ClauseBuilder<'_, I>,
     trait_id: TraitId<I>,
     self_ty: Ty<I>,
-) -> Result<(), Floundered> {
+) -> Result<(), Floundered<I>> {
     let interner = db.interner();
     match self_ty.data(interner) {
         TyData::Function(fn_val) => {
@@ -35,56 +141,33 @@ pub fn add_fn_trait_program_clauses<I: Interner>(
                 let arg_sub = Substitution::from(interner, arg_sub);
                 let fn_output_ty = fn_output_ty[0].assert_ty_ref(interner);
 
-                // We are constructing a reference to `FnOnce<Args>`, where
-                // `Args` is a tuple of the function's argument types
-                let tupled = Ty::new(
-                    interner,
-                    TyData::Apply(ApplicationTy {
-                        name: TypeName::Tuple(arg_sub.len(interner)),
-                        substitution: arg_sub.clone(),
-                    }),
-                );
-
-                let tupled_sub = Substitution::from(interner, vec![self_ty.clone(), tupled]);
-                // Given a function type `fn(A1, A2, ..., AN)`, construct a `TraitRef`
-                // of the form `fn(A1, A2, ..., AN): FnOnce<(A1, A2, ..., AN)>`
-                let new_trait_ref = TraitRef {
+                push_clauses(
+                    db,
+                    builder,
                     trait_id,
-                    substitution: tupled_sub.clone(),
-                };
-
-                builder.push_fact(new_trait_ref.clone());
-
-                if let Some(WellKnownTrait::FnOnce) = db.trait_datum(trait_id).well_known {
-                    //The `Output` type is defined on the `FnOnce`
-                    let fn_once = db.trait_datum(trait_id);
-                    assert_eq!(fn_once.well_known, Some(WellKnownTrait::FnOnce));
-                    let assoc_types = &fn_once.associated_ty_ids;
-                    assert_eq!(
-                        assoc_types.len(),
-                        1,
-                        "FnOnce trait should have exactly one associated type, found {:?}",
-                        assoc_types
-                    );
-
-                    // Construct `Normalize(<fn(A) -> B as FnOnce<(A,)>>::Output -> B)`
-                    let assoc_output_ty = assoc_types[0];
-                    let proj_ty = ProjectionTy {
-                        associated_ty_id: assoc_output_ty,
-                        substitution: tupled_sub,
-                    };
-                    let normalize = Normalize {
-                        alias: AliasTy::Projection(proj_ty),
-                        ty: fn_output_ty.clone(),
-                    };
-
-                    builder.push_fact(normalize);
-                }
+                    self_ty.clone(),
+                    arg_sub,
+                    fn_output_ty.clone(),
+                );
             });
             Ok(())
         }
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Closure(closure_id),
+            substitution,
+        }) => {
+            push_closure_clauses(
+                db,
+                builder,
+                trait_id,
+                self_ty.clone(),
+                *closure_id,
+                substitution,
+            );
+            Ok(())
+        }
         // Function traits are non-enumerable
-        TyData::InferenceVar(..) | TyData::Alias(..) => Err(Floundered),
+        TyData::InferenceVar(..) | TyData::Alias(..) => Err(Floundered { ty: self_ty }),
         _ => Ok(()),
     }
 }
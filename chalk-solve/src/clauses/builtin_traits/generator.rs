@@ -0,0 +1,86 @@
+use crate::clauses::ClauseBuilder;
+use crate::rust_ir::{WellKnownAssocType, WellKnownTrait};
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::cast::Cast;
+use chalk_ir::{
+    AliasTy, ApplicationTy, Floundered, Normalize, ProjectionTy, Substitution, Ty, TyData, TypeName,
+};
+
+/// Add implicit impls of the generator trait, i.e., add a clause that all generators implement
+/// `Generator` and clauses for `Generator`'s associated types.
+///
+/// For a generator `gen` with resume type `R`, yield type `Y` and return type `O`, we push:
+///
+/// ```notrust
+/// Implemented(gen: Generator<R>)
+/// Normalize(<gen as Generator<R>>::Yield -> Y)
+/// Normalize(<gen as Generator<R>>::Return -> O)
+/// ```
+pub fn add_generator_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    self_ty: Ty<I>,
+) -> Result<(), Floundered<I>> {
+    let interner = db.interner();
+
+    match self_ty.data(interner) {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Generator(generator_id),
+            substitution,
+        }) => {
+            let generator_datum = db.generator_datum(*generator_id);
+            let generator_io_datum = generator_datum
+                .input_output
+                .substitute(interner, substitution);
+
+            let trait_id = db.well_known_trait_id(WellKnownTrait::Generator).unwrap();
+
+            let substitution = Substitution::from(
+                interner,
+                &[
+                    self_ty.clone().cast(interner),
+                    generator_io_datum.resume_type.cast(interner),
+                ],
+            );
+
+            // generator: Generator<resume_type>
+            builder.push_fact(TraitRef {
+                trait_id,
+                substitution: substitution.clone(),
+            });
+
+            // `Generator::Yield`
+            let yield_id = db
+                .well_known_assoc_type_id(WellKnownAssocType::GeneratorYield)
+                .unwrap();
+            let yield_alias = AliasTy::Projection(ProjectionTy {
+                associated_ty_id: yield_id,
+                substitution: substitution.clone(),
+            });
+            builder.push_fact(Normalize {
+                alias: yield_alias,
+                ty: generator_io_datum.yield_type,
+            });
+
+            // `Generator::Return`
+            let return_id = db
+                .well_known_assoc_type_id(WellKnownAssocType::GeneratorReturn)
+                .unwrap();
+            let return_alias = AliasTy::Projection(ProjectionTy {
+                associated_ty_id: return_id,
+                substitution,
+            });
+            builder.push_fact(Normalize {
+                alias: return_alias,
+                ty: generator_io_datum.return_type,
+            });
+
+            Ok(())
+        }
+        // Generator trait is non-enumerable
+        TyData::InferenceVar(..) | TyData::BoundVar(_) | TyData::Alias(..) => {
+            Err(Floundered { ty: self_ty })
+        }
+        _ => Ok(()),
+    }
+}
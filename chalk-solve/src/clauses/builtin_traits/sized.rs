@@ -2,6 +2,7 @@ use std::iter;
 
 use crate::clauses::builtin_traits::needs_impl_for_tys;
 use crate::clauses::ClauseBuilder;
+use crate::rust_ir::AdtKind;
 use crate::{Interner, RustIrDatabase, TraitRef};
 use chalk_ir::{AdtId, ApplicationTy, Substitution, TyData, TypeName};
 
@@ -14,8 +15,18 @@ fn push_adt_sized_conditions<I: Interner>(
 ) {
     let adt_datum = db.adt_datum(adt_id);
 
+    // Enums and unions are always Sized, since the WF checks require all
+    // of their fields to be Sized.
+    if adt_datum.kind != AdtKind::Struct {
+        builder.push_fact(trait_ref.clone());
+        return;
+    }
+
     // ADTs with no fields are always Sized
-    if adt_datum.binders.skip_binders().fields.is_empty() {
+    if adt_datum.binders.skip_binders().variants[0]
+        .fields
+        .is_empty()
+    {
         builder.push_fact(trait_ref.clone());
         return;
     }
@@ -26,7 +37,7 @@ fn push_adt_sized_conditions<I: Interner>(
     // This is because the WF checks for ADTs require that all the other fields must be Sized.
     let last_field_ty = adt_datum
         .binders
-        .map_ref(|b| b.fields.last().unwrap())
+        .map_ref(|b| b.variants[0].fields.last().unwrap())
         .substitute(interner, substitution);
 
     needs_impl_for_tys(db, builder, trait_ref, iter::once(last_field_ty));
@@ -77,6 +88,9 @@ pub fn add_sized_program_clauses<I: Interner>(
             TypeName::Array
             | TypeName::Never
             | TypeName::FnDef(_)
+            | TypeName::Closure(_)
+            | TypeName::Generator(_)
+            | TypeName::GeneratorWitness(_)
             | TypeName::Scalar(_)
             | TypeName::Raw(_)
             | TypeName::Ref(_) => builder.push_fact(trait_ref.clone()),
@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use std::iter;
 
 use crate::clauses::ClauseBuilder;
+use crate::rust_ir::AdtKind;
 use crate::{Interner, RustIrDatabase, TraitRef, WellKnownTrait};
 use chalk_ir::{
     cast::Cast,
@@ -355,7 +356,12 @@ pub fn add_unsize_program_clauses<I: Interner>(
 
             let struct_id = *struct_id_a;
             let struct_datum = db.adt_datum(struct_id);
-            let fields_len = struct_datum.binders.skip_binders().fields.len();
+
+            if struct_datum.kind != AdtKind::Struct {
+                return;
+            }
+
+            let fields_len = struct_datum.binders.skip_binders().variants[0].fields.len();
 
             if fields_len == 0 {
                 return;
@@ -363,7 +369,7 @@ pub fn add_unsize_program_clauses<I: Interner>(
 
             let adt_tail_field = struct_datum
                 .binders
-                .map_ref(|bound| bound.fields.last().unwrap());
+                .map_ref(|bound| bound.variants[0].fields.last().unwrap());
 
             // Collect unsize parameters that last field contains and
             // ensure there at least one of them.
@@ -381,7 +387,7 @@ pub fn add_unsize_program_clauses<I: Interner>(
                 interner,
                 &struct_datum
                     .binders
-                    .map_ref(|bound| &bound.fields[..fields_len - 1]),
+                    .map_ref(|bound| &bound.variants[0].fields[..fields_len - 1]),
                 &unsize_parameter_candidates,
             ) {
                 return;
@@ -73,6 +73,7 @@ pub(super) fn build_dyn_self_ty_clauses<I: Interner>(
                 // Associated item bindings are just taken as facts (?)
                 WhereClause::AliasEq(_) => builder.push_fact(wc),
                 WhereClause::LifetimeOutlives(..) => {}
+                WhereClause::TypeOutlives(..) => {}
             });
         }
     });
@@ -162,6 +163,7 @@ pub fn super_traits<I: Interner>(
                             }
                             WhereClause::AliasEq(_) => None,
                             WhereClause::LifetimeOutlives(..) => None,
+                            WhereClause::TypeOutlives(..) => None,
                         })
                     })
                     .collect::<Vec<_>>()
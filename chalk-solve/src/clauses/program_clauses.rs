@@ -5,6 +5,7 @@ use chalk_ir::cast::{Cast, CastTo, Caster};
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use std::iter;
+use tracing::debug_span;
 
 /// Trait for lowering a given piece of rust-ir source (e.g., an impl
 /// or struct definition) into its associated "program clauses" --
@@ -129,7 +130,7 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
     /// ```
     /// where `!T<..>` is the placeholder for the unnormalized type `T<..>`.
     fn to_program_clauses(&self, builder: &mut ClauseBuilder<'_, I>) {
-        debug_heading!("to_program_clauses({:?})", self);
+        let _span = debug_span!("to_program_clauses", ?self).entered();
         builder.push_binders(&self.bound, |builder, opaque_ty_bound| {
             let interner = builder.interner();
             let substitution = builder.substitution_in_scope();
@@ -352,7 +353,7 @@ impl<I: Interner> ToProgramClauses<I> for AdtDatum<I> {
     /// ```
     ///
     fn to_program_clauses(&self, builder: &mut ClauseBuilder<'_, I>) {
-        debug_heading!("AdtDatum::to_program_clauses(self={:?})", self);
+        let _span = debug_span!("AdtDatum::to_program_clauses", ?self).entered();
 
         let interner = builder.interner();
         let binders = self.binders.map_ref(|b| &b.where_clauses);
@@ -442,7 +443,7 @@ impl<I: Interner> ToProgramClauses<I> for FnDefDatum<I> {
     /// }
     /// ```
     fn to_program_clauses(&self, builder: &mut ClauseBuilder<'_, I>) {
-        debug_heading!("FnDatum::to_program_clauses(self={:?})", self);
+        let _span = debug_span!("FnDatum::to_program_clauses", ?self).entered();
 
         let binders = self.binders.map_ref(|b| &b.where_clauses);
         let id = self.id;
@@ -3,7 +3,7 @@ use petgraph::prelude::*;
 use crate::solve::SolverChoice;
 use crate::RustIrDatabase;
 use chalk_ir::interner::Interner;
-use chalk_ir::{self, ImplId, TraitId};
+use chalk_ir::{self, Canonical, ImplId, TraitId, TraitRef};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
@@ -19,19 +19,89 @@ pub struct CoherenceSolver<'db, I: Interner> {
 
 #[derive(Debug)]
 pub enum CoherenceError<I: Interner> {
-    OverlappingImpls(TraitId<I>),
-    FailedOrphanCheck(TraitId<I>),
+    /// Two impls of the same trait apply to the same types, and neither
+    /// specializes the other.
+    OverlappingImpls {
+        trait_id: TraitId<I>,
+        impls: (ImplId<I>, ImplId<I>),
+        /// A trait reference that both impls apply to, e.g. `Vec<i32>: Foo`
+        /// for `impl<T> Foo for Vec<T>` and `impl Foo for Vec<i32>`.
+        witness: Canonical<TraitRef<I>>,
+    },
+    /// A local impl violates the orphan rules.
+    FailedOrphanCheck {
+        trait_id: TraitId<I>,
+        impl_id: ImplId<I>,
+        rule: OrphanRule,
+    },
+}
+
+/// The orphan rule that a local impl of an upstream trait failed.
+///
+/// Given `impl<P..> ForeignTrait<T1..Tn> for T0`, at least one of the
+/// input types `T0..Tn` must be a local type, and none of the input types
+/// before the first local type may be "uncovered" (i.e. mention one of the
+/// impl's type parameters `P..`, other than through fundamental types).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OrphanRule {
+    /// None of the input types is a local type.
+    NoLocalInputType,
+    /// The input type at index `uncovered` is not fully visible, but comes
+    /// before the first local input type at index `first_local`. Index 0 is
+    /// the self type.
+    UncoveredInputType {
+        uncovered: usize,
+        first_local: usize,
+    },
+    /// Each condition of the orphan rules holds on its own, but they could
+    /// not be proven together (e.g., because one of them is ambiguous).
+    Unknown,
+}
+
+impl fmt::Display for OrphanRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrphanRule::NoLocalInputType => write!(f, "none of its input types is local"),
+            OrphanRule::UncoveredInputType {
+                uncovered,
+                first_local,
+            } => write!(
+                f,
+                "input type {} mentions an uncovered type parameter, \
+                 but comes before the first local input type {}",
+                uncovered, first_local
+            ),
+            OrphanRule::Unknown => write!(f, "the orphan rules could not be proven to hold"),
+        }
+    }
 }
 
 impl<I: Interner> fmt::Display for CoherenceError<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            CoherenceError::OverlappingImpls(id) => {
-                write!(f, "overlapping impls of trait `{:?}`", id)
-            }
-            CoherenceError::FailedOrphanCheck(id) => {
-                write!(f, "impl for trait `{:?}` violates the orphan rules", id)
-            }
+            CoherenceError::OverlappingImpls {
+                trait_id,
+                impls: (lhs, rhs),
+                witness,
+            } => write!(
+                f,
+                "overlapping impls of trait `{:?}`: `{}` and `{}` both apply to `{:?}`",
+                trait_id,
+                lhs.display(),
+                rhs.display(),
+                witness.value.with_colon()
+            ),
+            CoherenceError::FailedOrphanCheck {
+                trait_id,
+                impl_id,
+                rule,
+            } => write!(
+                f,
+                "impl for trait `{:?}` violates the orphan rules: `{}`: {}",
+                trait_id,
+                impl_id.display(),
+                rule
+            ),
         }
     }
 }
@@ -57,6 +127,12 @@ impl<I: Interner> SpecializationPriorities<I> {
         self.map[&impl_id]
     }
 
+    /// Lookup the priority of an impl, if it is in the set. Impls that
+    /// neither specialize nor are specialized by another impl are not.
+    pub fn get(&self, impl_id: ImplId<I>) -> Option<SpecializationPriority> {
+        self.map.get(&impl_id).copied()
+    }
+
     /// Store the priority of an impl (used during construction).
     /// Panics if we have already stored the priority for this impl.
     fn insert(&mut self, impl_id: ImplId<I>, p: SpecializationPriority) {
@@ -71,6 +147,12 @@ impl<I: Interner> SpecializationPriorities<I> {
 #[derive(Copy, Clone, Default, PartialOrd, Ord, PartialEq, Eq, Debug)]
 pub struct SpecializationPriority(usize);
 
+impl fmt::Display for SpecializationPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl<'db, I> CoherenceSolver<'db, I>
 where
     I: Interner,
@@ -1,10 +1,12 @@
-use crate::coherence::CoherenceError;
+use crate::coherence::{CoherenceError, OrphanRule};
 use crate::ext::GoalExt;
+use crate::rust_ir::ImplDatum;
 use crate::solve::SolverChoice;
 use crate::RustIrDatabase;
 use chalk_ir::cast::*;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
+use tracing::{debug, debug_span};
 
 // Test if a local impl violates the orphan rules.
 //
@@ -18,7 +20,7 @@ pub fn perform_orphan_check<I: Interner>(
     solver_choice: SolverChoice,
     impl_id: ImplId<I>,
 ) -> Result<(), CoherenceError<I>> {
-    debug_heading!("orphan_check(impl={:#?})", impl_id);
+    let _span = debug_span!("orphan_check", ?impl_id).entered();
 
     let impl_datum = db.impl_datum(impl_id);
     debug!("impl_datum={:#?}", impl_datum);
@@ -40,8 +42,57 @@ pub fn perform_orphan_check<I: Interner>(
 
     if !is_allowed {
         let trait_id = impl_datum.trait_id();
-        Err(CoherenceError::FailedOrphanCheck(trait_id))?;
+        let rule = failed_orphan_rule(db, solver_choice, &impl_datum);
+        Err(CoherenceError::FailedOrphanCheck {
+            trait_id,
+            impl_id,
+            rule,
+        })?;
     }
 
     Ok(())
 }
+
+// Figure out which orphan rule an impl that failed the orphan check
+// violates. This mirrors the `LocalImplAllowed` clauses, but tests each of
+// their conditions separately:
+//
+//     forall<T> { IsLocal(Ti) }
+//     forall<T> { IsFullyVisible(Tj) }    for each j < i
+//
+// where `Ti` is the first input type that is local.
+fn failed_orphan_rule<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    solver_choice: SolverChoice,
+    impl_datum: &ImplDatum<I>,
+) -> OrphanRule {
+    let interner = db.interner();
+    let input_types = impl_datum.binders.map_ref(|bound_impl| {
+        bound_impl
+            .trait_ref
+            .type_parameters(interner)
+            .collect::<Vec<_>>()
+    });
+    let holds = |make_goal: fn(Ty<I>) -> DomainGoal<I>, index: usize| {
+        let goal: Goal<I> = input_types
+            .map_ref(|tys| make_goal(tys[index].clone()))
+            .cast(interner);
+        solver_choice
+            .into_solver()
+            .solve(db, &goal.into_closed_goal(interner))
+            .is_some()
+    };
+
+    let num_input_types = input_types.skip_binders().len();
+    let first_local = match (0..num_input_types).find(|&i| holds(DomainGoal::IsLocal, i)) {
+        Some(first_local) => first_local,
+        None => return OrphanRule::NoLocalInputType,
+    };
+    match (0..first_local).find(|&j| !holds(DomainGoal::IsFullyVisible, j)) {
+        Some(uncovered) => OrphanRule::UncoveredInputType {
+            uncovered,
+            first_local,
+        },
+        None => OrphanRule::Unknown,
+    }
+}
@@ -1,12 +1,13 @@
 use crate::coherence::{CoherenceError, CoherenceSolver};
 use crate::ext::*;
 use crate::rust_ir::*;
-use crate::{goal_builder::GoalBuilder, Solution};
+use crate::{goal_builder::GoalBuilder, Guidance, Solution};
 use chalk_ir::cast::*;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use itertools::Itertools;
+use tracing::{debug, debug_span};
 
 impl<I: Interner> CoherenceSolver<'_, I> {
     pub(super) fn visit_specializations_of_trait(
@@ -38,7 +39,11 @@ impl<I: Interner> CoherenceSolver<'_, I> {
                     (true, false) => record_specialization(l_id, r_id),
                     (false, true) => record_specialization(r_id, l_id),
                     (_, _) => {
-                        Err(CoherenceError::OverlappingImpls(self.trait_id))?;
+                        Err(CoherenceError::OverlappingImpls {
+                            trait_id: self.trait_id,
+                            impls: (l_id, r_id),
+                            witness: self.overlap_witness(lhs, rhs),
+                        })?;
                     }
                 }
             }
@@ -81,7 +86,7 @@ impl<I: Interner> CoherenceSolver<'_, I> {
     //      not { compatible { exists<T> { exists<U> { Vec<T> = Vec<U>, T: Bar, U: Baz } } } }
     //
     fn disjoint(&self, lhs: &ImplDatum<I>, rhs: &ImplDatum<I>) -> bool {
-        debug_heading!("overlaps(lhs={:#?}, rhs={:#?})", lhs, rhs);
+        let _span = debug_span!("overlaps", ?lhs, ?rhs).entered();
 
         let interner = self.db.interner();
 
@@ -146,6 +151,102 @@ impl<I: Interner> CoherenceSolver<'_, I> {
         result
     }
 
+    // Finds a trait reference that both (overlapping) impls apply to, for
+    // error reporting. This asks the same question as `disjoint`, except that
+    // the variables of both impls are canonical variables rather than being
+    // bound by `exists`, so that the solution tells us how to instantiate
+    // them. With the second example from `disjoint`, we solve:
+    //
+    //     compatible { Vec<?U> = Vec<i32>, ?T1 = ?T2 }
+    //
+    // and get `Vec<i32>: Foo<?T1>`. If the solver can't give us any
+    // substitution (e.g. the overlap is ambiguous), we fall back to the
+    // header of `lhs`.
+    fn overlap_witness(&self, lhs: &ImplDatum<I>, rhs: &ImplDatum<I>) -> Canonical<TraitRef<I>> {
+        let interner = self.db.interner();
+
+        // Join the binders of both impls, `lhs` first
+        let lhs_len = lhs.binders.len(interner);
+        let kinds: Vec<_> = lhs
+            .binders
+            .binders
+            .iter(interner)
+            .chain(rhs.binders.binders.iter(interner))
+            .cloned()
+            .collect();
+        let vars: Vec<_> = kinds
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| (kind, i).to_generic_arg(interner))
+            .collect();
+        let lhs_bound = lhs.binders.substitute(interner, &vars[..lhs_len]);
+        let rhs_bound = rhs.binders.substitute(interner, &vars[lhs_len..]);
+
+        let params_goals = lhs_bound
+            .trait_ref
+            .substitution
+            .parameters(interner)
+            .iter()
+            .cloned()
+            .zip(
+                rhs_bound
+                    .trait_ref
+                    .substitution
+                    .parameters(interner)
+                    .iter()
+                    .cloned(),
+            )
+            .map(|(a, b)| GoalData::EqGoal(EqGoal { a, b }).intern(interner));
+        let wc_goals = lhs_bound
+            .where_clauses
+            .into_iter()
+            .chain(rhs_bound.where_clauses)
+            .map(|wc| wc.cast(interner));
+        let goal = Goal::all(interner, params_goals.chain(wc_goals)).compatible(interner);
+
+        let binders = CanonicalVarKinds::from(
+            interner,
+            kinds
+                .into_iter()
+                .map(|kind| WithKind::new(kind, UniverseIndex::ROOT)),
+        );
+        let canonical_goal = UCanonical {
+            canonical: Canonical {
+                binders: binders.clone(),
+                value: InEnvironment::new(&Environment::new(interner), goal),
+            },
+            universes: 1,
+        };
+        let subst = match self
+            .solver_choice
+            .into_solver()
+            .solve(self.db, &canonical_goal)
+        {
+            Some(Solution::Unique(constrained)) => Canonical {
+                binders: constrained.binders,
+                value: constrained.value.subst,
+            },
+            Some(Solution::Ambig(Guidance::Definite(subst)))
+            | Some(Solution::Ambig(Guidance::Suggested(subst))) => subst,
+            Some(Solution::Ambig(Guidance::Unknown))
+            | Some(Solution::Ambig(Guidance::Floundered(_)))
+            | Some(Solution::Ambig(Guidance::Overflow(_)))
+            | None => Canonical {
+                binders,
+                value: Substitution::from(interner, vars),
+            },
+        };
+        debug!("overlap_witness: subst = {:?}", subst);
+
+        Canonical {
+            value: lhs
+                .binders
+                .map_ref(|bound| bound.trait_ref.clone())
+                .substitute(interner, &subst.value.parameters(interner)[..lhs_len]),
+            binders: subst.binders,
+        }
+    }
+
     // Creates a goal which, if provable, means "more special" impl specializes the "less special" one.
     //
     // # General rule
@@ -196,11 +297,7 @@ impl<I: Interner> CoherenceSolver<'_, I> {
     fn specializes(&self, less_special_id: ImplId<I>, more_special_id: ImplId<I>) -> bool {
         let more_special = &self.db.impl_datum(more_special_id);
         let less_special = &self.db.impl_datum(less_special_id);
-        debug_heading!(
-            "specializes(less_special={:#?}, more_special={:#?})",
-            less_special,
-            more_special
-        );
+        let _span = debug_span!("specializes", ?less_special, ?more_special).entered();
 
         let interner = self.db.interner();
 
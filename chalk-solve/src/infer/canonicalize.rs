@@ -3,8 +3,10 @@ use chalk_ir::fold::{Fold, Folder};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
 use std::cmp::max;
+use tracing::{debug, debug_span};
 
 use super::{InferenceTable, ParameterEnaVariable};
+use crate::solve::stats;
 
 impl<I: Interner> InferenceTable<I> {
     /// Given a value `value` with variables in it, replaces those variables
@@ -31,6 +33,7 @@ impl<I: Interner> InferenceTable<I> {
         T::Result: HasInterner<Interner = I>,
     {
         debug!("canonicalize({:#?})", value);
+        let _timer = stats::Timer::canonicalization();
         let mut q = Canonicalizer {
             table: self,
             free_vars: Vec::new(),
@@ -149,12 +152,7 @@ where
         kind: TyKind,
         outer_binder: DebruijnIndex,
     ) -> Fallible<Ty<I>> {
-        debug_heading!(
-            "fold_inference_ty(depth={:?}, kind={:?}, binders={:?})",
-            var,
-            kind,
-            outer_binder
-        );
+        let _span = debug_span!("fold_inference_ty", ?var, ?kind, ?outer_binder).entered();
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(ty) => {
@@ -184,11 +182,7 @@ where
         var: InferenceVar,
         outer_binder: DebruijnIndex,
     ) -> Fallible<Lifetime<I>> {
-        debug_heading!(
-            "fold_inference_lifetime(depth={:?}, outer_binder={:?})",
-            var,
-            outer_binder
-        );
+        let _span = debug_span!("fold_inference_lifetime", ?var, ?outer_binder).entered();
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(l) => {
@@ -216,11 +210,7 @@ where
         var: InferenceVar,
         outer_binder: DebruijnIndex,
     ) -> Fallible<Const<I>> {
-        debug_heading!(
-            "fold_inference_const(depth={:?}, outer_binder={:?})",
-            var,
-            outer_binder
-        );
+        let _span = debug_span!("fold_inference_const", ?var, ?outer_binder).entered();
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(c) => {
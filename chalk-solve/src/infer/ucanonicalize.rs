@@ -2,8 +2,10 @@ use chalk_ir::fold::{Fold, Folder};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::{Visit, Visitor};
 use chalk_ir::*;
+use tracing::debug;
 
 use super::InferenceTable;
+use crate::solve::stats;
 
 impl<I: Interner> InferenceTable<I> {
     pub(crate) fn u_canonicalize<T>(
@@ -16,6 +18,7 @@ impl<I: Interner> InferenceTable<I> {
         T::Result: HasInterner<Interner = I>,
     {
         debug!("u_canonicalize({:#?})", value0);
+        let _timer = stats::Timer::canonicalization();
 
         // First, find all the universes that appear in `value`.
         let mut universes = UniverseMap::new();
@@ -1,11 +1,13 @@
 use super::var::*;
 use super::*;
 use crate::infer::instantiate::IntoBindersAndValue;
+use crate::solve::stats;
 use chalk_ir::cast::Cast;
 use chalk_ir::fold::{Fold, Folder};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::zip::{Zip, Zipper};
 use std::fmt::Debug;
+use tracing::{debug, debug_span};
 
 impl<I: Interner> InferenceTable<I> {
     pub(crate) fn unify<T>(
@@ -18,12 +20,8 @@ impl<I: Interner> InferenceTable<I> {
     where
         T: ?Sized + Zip<I>,
     {
-        debug_heading!(
-            "unify(a={:?}\
-             ,\n      b={:?})",
-            a,
-            b
-        );
+        let _span = debug_span!("unify", ?a, ?b).entered();
+        let _timer = stats::Timer::unification();
         let snapshot = self.snapshot();
         match Unifier::new(interner, self, environment).unify(a, b) {
             Ok(r) => {
@@ -36,6 +34,35 @@ impl<I: Interner> InferenceTable<I> {
             }
         }
     }
+
+    /// Relates the types `a` and `b` according to `variance`: `a` must
+    /// be a subtype of `b` if covariant, a supertype if contravariant,
+    /// and equal to `b` if invariant. Relating lifetimes in a covariant
+    /// or contravariant position yields outlives constraints rather than
+    /// equalities.
+    pub(crate) fn relate_tys(
+        &mut self,
+        interner: &I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<UnificationResult<I>> {
+        let _span = debug_span!("relate_tys", ?variance, ?a, ?b).entered();
+        let _timer = stats::Timer::unification();
+        let snapshot = self.snapshot();
+        match Unifier::new(interner, self, environment).relate(db, variance, a, b) {
+            Ok(r) => {
+                self.commit(snapshot);
+                Ok(r)
+            }
+            Err(e) => {
+                self.rollback_to(snapshot);
+                Err(e)
+            }
+        }
+    }
 }
 
 struct Unifier<'t, I: Interner> {
@@ -81,7 +108,33 @@ impl<'t, I: Interner> Unifier<'t, I> {
         })
     }
 
-    fn unify_ty_ty(&mut self, a: &Ty<I>, b: &Ty<I>) -> Fallible<()> {
+    /// Like `unify`, but relates the types `a` and `b` with the given
+    /// variance instead of requiring them to be equal.
+    fn relate(
+        mut self,
+        db: &dyn UnificationDatabase<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<UnificationResult<I>> {
+        self.relate_ty_ty(db, variance, a, b)?;
+        Ok(UnificationResult {
+            goals: self.goals,
+            constraints: self.constraints,
+        })
+    }
+
+    fn relate_ty_ty(
+        &mut self,
+        db: &dyn UnificationDatabase<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<()> {
+        if variance == Variance::Invariant {
+            return self.unify_ty_ty(a, b);
+        }
+
         let interner = self.interner;
 
         let n_a = self.table.normalize_ty_shallow(interner, a);
@@ -89,13 +142,204 @@ impl<'t, I: Interner> Unifier<'t, I> {
         let a = n_a.as_ref().unwrap_or(a);
         let b = n_b.as_ref().unwrap_or(b);
 
-        debug_heading!(
-            "unify_ty_ty(a={:?}\
-             ,\n            b={:?})",
-            a,
-            b
+        let _span = debug_span!("relate_ty_ty", ?variance, ?a, ?b).entered();
+
+        match (a.data(interner), b.data(interner)) {
+            // Relating a general inference variable with a type that may
+            // contain lifetimes.
+            (&TyData::InferenceVar(var, TyKind::General), &TyData::Apply(_))
+            | (&TyData::InferenceVar(var, TyKind::General), &TyData::Function(_)) => {
+                self.relate_var_ty(db, variance, var, b)
+            }
+            (&TyData::Apply(_), &TyData::InferenceVar(var, TyKind::General))
+            | (&TyData::Function(_), &TyData::InferenceVar(var, TyKind::General)) => {
+                self.relate_var_ty(db, variance.invert(), var, a)
+            }
+
+            (TyData::Function(fn1), TyData::Function(fn2)) => {
+                self.relate_fn_fn(db, variance, fn1, fn2)
+            }
+
+            (TyData::Apply(apply1), TyData::Apply(apply2)) => {
+                if apply1.name != apply2.name {
+                    return Err(NoSolution);
+                }
+                let variances =
+                    self.type_name_variances(db, &apply1.name, apply1.substitution.len(interner));
+                self.relate_substitutions(
+                    db,
+                    variance,
+                    &variances,
+                    &apply1.substitution,
+                    &apply2.substitution,
+                )
+            }
+
+            // Everything else -- placeholders, `dyn` types, aliases and
+            // mismatched types -- is related by equality.
+            _ => self.unify_ty_ty(a, b),
+        }
+    }
+
+    /// Relates the general inference variable `var` with the type `ty`
+    /// (so `?var <: ty` if `variance` is covariant). Binding `var` to `ty`
+    /// directly would force their lifetimes to be equal, so instead we bind
+    /// `var` to a copy of `ty` in which every free lifetime is replaced by a
+    /// fresh inference variable, and then relate that copy with `ty`.
+    fn relate_var_ty(
+        &mut self,
+        db: &dyn UnificationDatabase<I>,
+        variance: Variance,
+        var: InferenceVar,
+        ty: &Ty<I>,
+    ) -> Fallible<()> {
+        debug!(
+            "relate_var_ty(variance={:?}, var={:?}, ty={:?})",
+            variance, var, ty
         );
 
+        let universe_index = self.table.universe_of_unbound_var(EnaVariable::from(var));
+        let generalized = ty.fold_with(
+            &mut Generalizer::new(self.interner, self.table, universe_index),
+            DebruijnIndex::INNERMOST,
+        )?;
+
+        self.unify_var_ty(var, &generalized)?;
+        self.relate_ty_ty(db, variance, &generalized, ty)
+    }
+
+    /// Relates two function pointer types. Argument types are related
+    /// contravariantly and the return type covariantly. For `a <: b`, the
+    /// bound lifetimes of `b` are instantiated universally and those of `a`
+    /// existentially, so that `for<'a> fn(&'a u8) <: fn(&'x u8)` holds but
+    /// the reverse only holds if `'x` outlives every lifetime.
+    fn relate_fn_fn(
+        &mut self,
+        db: &dyn UnificationDatabase<I>,
+        variance: Variance,
+        fn1: &Fn<I>,
+        fn2: &Fn<I>,
+    ) -> Fallible<()> {
+        let interner = self.interner;
+
+        let (sub, sup) = match variance {
+            Variance::Covariant => (fn1, fn2),
+            Variance::Contravariant => (fn2, fn1),
+            Variance::Invariant => return self.unify_binders(fn1, fn2),
+        };
+
+        let sup_universal = self.table.instantiate_binders_universally(interner, sup);
+        let sub_existential = self.table.instantiate_binders_existentially(interner, sub);
+
+        // The last parameter represents the function return type
+        let num_args = sub_existential.len(interner) - 1;
+        let mut variances = vec![Variance::Contravariant; num_args];
+        variances.push(Variance::Covariant);
+
+        self.relate_substitutions(
+            db,
+            Variance::Covariant,
+            &variances,
+            &sub_existential,
+            &sup_universal,
+        )
+    }
+
+    /// Relates each pair of generic arguments in `a` and `b` with the
+    /// ambient `variance` composed with the variance of the corresponding
+    /// parameter.
+    fn relate_substitutions(
+        &mut self,
+        db: &dyn UnificationDatabase<I>,
+        variance: Variance,
+        variances: &[Variance],
+        a: &Substitution<I>,
+        b: &Substitution<I>,
+    ) -> Fallible<()> {
+        let interner = self.interner;
+        assert_eq!(a.len(interner), variances.len());
+        assert_eq!(b.len(interner), variances.len());
+
+        for ((a, b), param_variance) in a.iter(interner).zip(b.iter(interner)).zip(variances) {
+            let variance = variance.xform(*param_variance);
+            match (a.data(interner), b.data(interner)) {
+                (GenericArgData::Ty(a), GenericArgData::Ty(b)) => {
+                    self.relate_ty_ty(db, variance, a, b)?
+                }
+                (GenericArgData::Lifetime(a), GenericArgData::Lifetime(b)) => {
+                    self.relate_lifetime_lifetime(variance, a, b)?
+                }
+                (GenericArgData::Const(a), GenericArgData::Const(b)) => {
+                    self.unify_const_const(a, b)?
+                }
+                (_, _) => panic!("relating mismatched generic arguments: {:?} {:?}", a, b),
+            }
+        }
+        Ok(())
+    }
+
+    /// The variances of the generic parameters of a type with the given name.
+    fn type_name_variances(
+        &self,
+        db: &dyn UnificationDatabase<I>,
+        name: &TypeName<I>,
+        len: usize,
+    ) -> Vec<Variance> {
+        let interner = self.interner;
+        match name {
+            TypeName::Adt(adt_id) => db.adt_variance(*adt_id).as_slice(interner).to_vec(),
+            TypeName::FnDef(fn_def_id) => {
+                db.fn_def_variance(*fn_def_id).as_slice(interner).to_vec()
+            }
+            // `&'a T` is covariant in `'a`, and covariant in `T` unless
+            // it is a mutable reference
+            TypeName::Ref(Mutability::Not) => vec![Variance::Covariant, Variance::Covariant],
+            TypeName::Ref(Mutability::Mut) => vec![Variance::Covariant, Variance::Invariant],
+            TypeName::Raw(Mutability::Not) => vec![Variance::Covariant],
+            TypeName::Raw(Mutability::Mut) => vec![Variance::Invariant],
+            TypeName::Tuple(_) | TypeName::Slice => vec![Variance::Covariant; len],
+            // `[T; N]` is covariant in `T`; the length must match exactly
+            TypeName::Array => vec![Variance::Covariant, Variance::Invariant],
+            _ => vec![Variance::Invariant; len],
+        }
+    }
+
+    fn relate_lifetime_lifetime(
+        &mut self,
+        variance: Variance,
+        a: &Lifetime<I>,
+        b: &Lifetime<I>,
+    ) -> Fallible<()> {
+        let interner = self.interner;
+
+        let n_a = self.table.normalize_lifetime_shallow(interner, a);
+        let n_b = self.table.normalize_lifetime_shallow(interner, b);
+        let a = n_a.as_ref().unwrap_or(a);
+        let b = n_b.as_ref().unwrap_or(b);
+
+        let _span = debug_span!("relate_lifetime_lifetime", ?variance, ?a, ?b).entered();
+
+        // `'a <: 'b` if `'a: 'b`
+        let (sub, sup) = match variance {
+            Variance::Invariant => return self.unify_lifetime_lifetime(a, b),
+            _ if a == b => return Ok(()),
+            Variance::Covariant => (a, b),
+            Variance::Contravariant => (b, a),
+        };
+        self.push_constraint(Constraint::Outlives(sub.clone(), sup.clone()));
+        Ok(())
+    }
+
+    fn unify_ty_ty(&mut self, a: &Ty<I>, b: &Ty<I>) -> Fallible<()> {
+        let interner = self.interner;
+
+        let n_a = self.table.normalize_ty_shallow(interner, a);
+        let n_b = self.table.normalize_ty_shallow(interner, b);
+        let a = n_a.as_ref().unwrap_or(a);
+        let b = n_b.as_ref().unwrap_or(b);
+
+        let _span = debug_span!("unify_ty_ty", ?a, ?b).entered();
+
         match (a.data(interner), b.data(interner)) {
             // Unifying two inference variables: unify them in the underlying
             // ena table.
@@ -328,7 +572,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
         let a = n_a.as_ref().unwrap_or(a);
         let b = n_b.as_ref().unwrap_or(b);
 
-        debug_heading!("unify_lifetime_lifetime({:?}, {:?})", a, b);
+        let _span = debug_span!("unify_lifetime_lifetime", ?a, ?b).entered();
 
         match (a.data(interner), b.data(interner)) {
             (&LifetimeData::InferenceVar(var_a), &LifetimeData::InferenceVar(var_b)) => {
@@ -375,12 +619,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
         value: &Lifetime<I>,
         value_ui: UniverseIndex,
     ) -> Fallible<()> {
-        debug_heading!(
-            "unify_lifetime_var(var={:?}, value={:?}, value_ui={:?})",
-            var,
-            value,
-            value_ui
-        );
+        let _span = debug_span!("unify_lifetime_var", ?var, ?value, ?value_ui).entered();
         let var = EnaVariable::from(var);
         let var_ui = self.table.universe_of_unbound_var(var);
         if var_ui.can_see(value_ui) {
@@ -413,12 +652,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
         let a = n_a.as_ref().unwrap_or(a);
         let b = n_b.as_ref().unwrap_or(b);
 
-        debug_heading!(
-            "unify_const_const(a={:?}\
-             ,\n               b={:?})",
-            a,
-            b
-        );
+        let _span = debug_span!("unify_const_const", ?a, ?b).entered();
 
         let ConstData {
             ty: a_ty,
@@ -497,14 +731,18 @@ impl<'t, I: Interner> Unifier<'t, I> {
     }
 
     fn push_lifetime_eq_constraint(&mut self, a: Lifetime<I>, b: Lifetime<I>) {
-        self.constraints.push(InEnvironment::new(
-            self.environment,
-            Constraint::Outlives(a.clone(), b.clone()),
-        ));
-        self.constraints.push(InEnvironment::new(
-            self.environment,
-            Constraint::Outlives(b, a),
-        ));
+        self.push_constraint(Constraint::Outlives(a.clone(), b.clone()));
+        self.push_constraint(Constraint::Outlives(b, a));
+    }
+
+    /// Records a region constraint, unless an identical one was already
+    /// recorded (relating a type may produce the same constraint twice,
+    /// e.g. `&'a mut &'a T <: &'b mut &'b T`).
+    fn push_constraint(&mut self, constraint: Constraint<I>) {
+        let constraint = InEnvironment::new(self.environment, constraint);
+        if !self.constraints.contains(&constraint) {
+            self.constraints.push(constraint);
+        }
     }
 }
 
@@ -544,6 +782,67 @@ impl<'i, I: Interner> Zipper<'i, I> for Unifier<'i, I> {
     }
 }
 
+/// Replaces every free lifetime in a type with a fresh inference variable
+/// in the given universe; see `relate_var_ty`.
+struct Generalizer<'t, I: Interner> {
+    interner: &'t I,
+    table: &'t mut InferenceTable<I>,
+    universe_index: UniverseIndex,
+}
+
+impl<'t, I: Interner> Generalizer<'t, I> {
+    fn new(
+        interner: &'t I,
+        table: &'t mut InferenceTable<I>,
+        universe_index: UniverseIndex,
+    ) -> Self {
+        Generalizer {
+            interner,
+            table,
+            universe_index,
+        }
+    }
+
+    fn fresh_lifetime(&mut self) -> Lifetime<I> {
+        self.table
+            .new_variable(self.universe_index)
+            .to_lifetime(self.interner)
+    }
+}
+
+impl<'i, I: Interner> Folder<'i, I> for Generalizer<'i, I>
+where
+    I: 'i,
+{
+    fn as_dyn(&mut self) -> &mut dyn Folder<'i, I> {
+        self
+    }
+
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        _ui: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Fallible<Lifetime<I>> {
+        Ok(self.fresh_lifetime())
+    }
+
+    fn fold_inference_lifetime(
+        &mut self,
+        _var: InferenceVar,
+        _outer_binder: DebruijnIndex,
+    ) -> Fallible<Lifetime<I>> {
+        Ok(self.fresh_lifetime())
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn target_interner(&self) -> &'i I {
+        self.interner
+    }
+}
+
 struct OccursCheck<'u, 't, I: Interner> {
     unifier: &'u mut Unifier<'t, I>,
     var: EnaVariable<I>,
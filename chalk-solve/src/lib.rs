@@ -16,6 +16,8 @@ mod coinductive_goal;
 pub mod ext;
 pub mod goal_builder;
 mod infer;
+pub mod logging;
+pub mod object_safety;
 #[cfg(feature = "recursive-solver")]
 pub mod recursive;
 pub mod rust_ir;
@@ -39,6 +41,20 @@ pub trait RustIrDatabase<I: Interner>: Debug {
 
     fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>>;
 
+    /// Returns the datum for the closure with the given id, which
+    /// describes its signature, upvars and closure kind.
+    fn closure_datum(&self, closure_id: ClosureId<I>) -> Arc<ClosureDatum<I>>;
+
+    /// Returns the datum for the generator with the given id, which
+    /// describes its signature and upvars.
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>>;
+
+    /// Returns the witness types for the generator with the given id.
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>>;
+
     /// Returns the datum for the impl with the given id.
     fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>>;
 
@@ -93,20 +109,38 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     /// Returns id of a trait lang item, if found
     fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>>;
 
+    /// Returns id of an associated type of a trait lang item, if found
+    fn well_known_assoc_type_id(
+        &self,
+        well_known_assoc_type: WellKnownAssocType,
+    ) -> Option<AssocTypeId<I>>;
+
     /// Calculates program clauses from an env. This is intended to call the
     /// `program_clauses_for_env` function and then possibly cache the clauses.
     fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I>;
 
     fn interner(&self) -> &I;
 
-    /// Check if a trait is object safe
+    /// Returns the database used to look up variances when relating types.
+    fn unification_database(&self) -> &dyn UnificationDatabase<I>;
+
+    /// Check if a trait is object safe as far as the parts of it that the
+    /// Rust IR does not describe, like its method signatures, are
+    /// concerned. Chalk checks the rest itself (see `object_safety`); a
+    /// trait is only object safe if both agree.
     fn is_object_safe(&self, trait_id: TraitId<I>) -> bool;
 }
 
-pub use clauses::program_clauses_for_env;
+pub use clauses::{program_clauses_for_env, program_clauses_for_goal};
 
+pub use solve::AmbiguityCandidates;
+pub use solve::Dependency;
 pub use solve::Guidance;
 pub use solve::Solution;
 pub use solve::Solver;
 pub use solve::SolverChoice;
 pub use solve::SubstitutionResult;
+pub use solve::{compare_solvers, SolverComparison};
+pub use solve::{BudgetExceeded, SolveBudget, SolverStats};
+pub use solve::{Candidate, FailureReason, FailureTree, ProofStep, ProofTree};
+pub use solve::{Overflow, OverflowDisplay, OverflowLimit};
@@ -0,0 +1,53 @@
+//! The solvers report their progress through [`tracing`]. Each step of a
+//! solve opens a span (e.g. `solve_goal` or `ensure_root_answer`), and the
+//! milestones of a solve are emitted as events with the following names,
+//! carrying their data as fields:
+//!
+//! | Event             | SLG solver fields      | Recursive solver fields |
+//! |-------------------|------------------------|-------------------------|
+//! | `goal_entered`    | `table`, `goal`        | `goal`, `depth`         |
+//! | `clause_tried`    | `table`, `clause`      | `clause`                |
+//! | `answer_found`    | `table`, `answer`      | `goal`, `solution`      |
+//! | `cycle_detected`  | `table`, `depth`       | `goal`, `depth`         |
+//! | `table_completed` | `table`                | `goal`, `solution`      |
+//!
+//! Any [`tracing::Subscriber`] can observe a solve, for example by installing
+//! it with [`tracing::subscriber::with_default`] and matching on
+//! [`tracing::Metadata::name`]. [`with_tracing_logs`] installs one that
+//! prints everything to stderr.
+
+/// Runs `action` with a subscriber that prints the spans and events selected
+/// by the `CHALK_DEBUG` environment variable to stderr. `CHALK_DEBUG=1`
+/// selects the `info` level and `CHALK_DEBUG=2` the `debug` level; any other
+/// value is used as an `EnvFilter` directive, such as
+/// `chalk_solve::recursive=debug`.
+///
+/// Without the `tracing-full` feature, this just runs `action`.
+#[cfg(feature = "tracing-full")]
+pub fn with_tracing_logs<T>(action: impl FnOnce() -> T) -> T {
+    use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
+    use tracing_tree::HierarchicalLayer;
+
+    let directives = match std::env::var("CHALK_DEBUG") {
+        Ok(value) => match value.parse::<u32>() {
+            Ok(0) => return action(),
+            Ok(1) => "info".to_string(),
+            Ok(_) => "debug".to_string(),
+            Err(_) => value,
+        },
+        Err(_) => return action(),
+    };
+    let subscriber = Registry::default()
+        .with(EnvFilter::new(directives))
+        .with(HierarchicalLayer::new(2).with_writer(std::io::stderr));
+    tracing::subscriber::with_default(subscriber, action)
+}
+
+/// Runs `action` with a subscriber that prints the spans and events selected
+/// by the `CHALK_DEBUG` environment variable to stderr.
+///
+/// Without the `tracing-full` feature, this just runs `action`.
+#[cfg(not(feature = "tracing-full"))]
+pub fn with_tracing_logs<T>(action: impl FnOnce() -> T) -> T {
+    action()
+}
@@ -0,0 +1,211 @@
+//! Object safety: whether a trait may be used as the principal trait of a
+//! `dyn Trait` type.
+//!
+//! A trait is object safe if neither it nor any of its supertraits
+//! violates one of the rules below. Method signatures are not checked,
+//! since traits in the Rust IR do not record their methods; hosts that
+//! know them report those violations through
+//! `RustIrDatabase::is_object_safe`.
+
+use crate::rust_ir::WellKnownTrait;
+use crate::split::Split;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::visitors::FindAny;
+use chalk_ir::visit::{SuperVisit, Visit, VisitResult, Visitor};
+use chalk_ir::{
+    AliasTy, AssocTypeId, BoundVar, DebruijnIndex, GenericArg, TraitId, Ty, TyData, WhereClause,
+};
+use rustc_hash::FxHashSet;
+use std::fmt;
+
+/// A reason why a trait is not object safe. Each violation records the
+/// trait (the trait being checked or one of its supertraits) in which it
+/// was found.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectSafetyViolation<I: Interner> {
+    /// The trait requires `Self: Sized`, e.g. `trait Foo where Self: Sized`.
+    SizedSelf(TraitId<I>),
+
+    /// A supertrait bound refers to `Self` other than as its self type,
+    /// e.g. `trait Foo: Bar<Self>`.
+    SupertraitSelf(TraitId<I>),
+
+    /// The trait has an associated type with generic parameters of its
+    /// own, e.g. `type Item<T>;`.
+    GenericAssociatedType(TraitId<I>, AssocTypeId<I>),
+}
+
+impl<I: Interner> fmt::Display for ObjectSafetyViolation<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectSafetyViolation::SizedSelf(id) => {
+                write!(f, "trait `{:?}` requires `Self: Sized`", id)
+            }
+            ObjectSafetyViolation::SupertraitSelf(id) => write!(
+                f,
+                "a supertrait bound of trait `{:?}` references the `Self` type",
+                id
+            ),
+            ObjectSafetyViolation::GenericAssociatedType(id, assoc_id) => write!(
+                f,
+                "associated type `{:?}` of trait `{:?}` has generic parameters",
+                assoc_id, id
+            ),
+        }
+    }
+}
+
+/// Returns true if the given trait is object safe.
+pub fn is_object_safe<I: Interner>(db: &dyn RustIrDatabase<I>, trait_id: TraitId<I>) -> bool {
+    object_safety_violations(db, trait_id).is_empty()
+}
+
+/// Returns all the reasons why the given trait is not object safe, in the
+/// order the traits are visited (the trait itself first, then its
+/// supertraits).
+pub fn object_safety_violations<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+) -> Vec<ObjectSafetyViolation<I>> {
+    let mut violations = vec![];
+    for trait_id in supertrait_ids(db, trait_id) {
+        trait_violations(db, trait_id, &mut violations);
+    }
+    violations
+}
+
+/// Returns the trait and all its (transitive) supertraits.
+fn supertrait_ids<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+) -> Vec<TraitId<I>> {
+    let mut seen = FxHashSet::default();
+    let mut stack = vec![trait_id];
+    let mut trait_ids = vec![];
+    while let Some(trait_id) = stack.pop() {
+        // Avoid cycles
+        if !seen.insert(trait_id) {
+            continue;
+        }
+        trait_ids.push(trait_id);
+        let trait_datum = db.trait_datum(trait_id);
+        for qwc in &trait_datum.binders.skip_binders().where_clauses {
+            if let WhereClause::Implemented(trait_ref) = qwc.skip_binders() {
+                if is_self(db, &trait_ref.self_type_parameter(db.interner())) {
+                    stack.push(trait_ref.trait_id);
+                }
+            }
+        }
+    }
+    trait_ids
+}
+
+/// Checks the where clauses and associated types of a single trait.
+fn trait_violations<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+    violations: &mut Vec<ObjectSafetyViolation<I>>,
+) {
+    let interner = db.interner();
+    let trait_datum = db.trait_datum(trait_id);
+    let sized_trait_id = db.well_known_trait_id(WellKnownTrait::Sized);
+
+    let mut sized_self = false;
+    let mut supertrait_self = false;
+    for qwc in &trait_datum.binders.skip_binders().where_clauses {
+        match qwc.skip_binders() {
+            WhereClause::Implemented(trait_ref) => {
+                let (self_ty, args) = trait_ref
+                    .substitution
+                    .parameters(interner)
+                    .split_first()
+                    .unwrap();
+                if !is_self(db, self_ty.assert_ty_ref(interner)) {
+                    continue;
+                }
+                if Some(trait_ref.trait_id) == sized_trait_id {
+                    sized_self = true;
+                }
+                supertrait_self |= references_self(db, args);
+            }
+            WhereClause::AliasEq(alias_eq) => match &alias_eq.alias {
+                AliasTy::Projection(projection) => {
+                    let trait_params = db.trait_parameters_from_projection(projection);
+                    let (self_ty, args) = trait_params.split_first().unwrap();
+                    if is_self(db, self_ty.assert_ty_ref(interner)) {
+                        supertrait_self |= references_self(db, args);
+                    }
+                }
+                AliasTy::Opaque(_) => {}
+            },
+            WhereClause::LifetimeOutlives(_) | WhereClause::TypeOutlives(_) => {}
+        }
+    }
+
+    if sized_self {
+        violations.push(ObjectSafetyViolation::SizedSelf(trait_id));
+    }
+    if supertrait_self {
+        violations.push(ObjectSafetyViolation::SupertraitSelf(trait_id));
+    }
+
+    let num_trait_params = trait_datum.binders.len(interner);
+    for &assoc_id in &trait_datum.associated_ty_ids {
+        let assoc_datum = db.associated_ty_data(assoc_id);
+        if assoc_datum.binders.len(interner) > num_trait_params {
+            violations.push(ObjectSafetyViolation::GenericAssociatedType(
+                trait_id, assoc_id,
+            ));
+        }
+    }
+}
+
+/// True if `ty` is the `Self` parameter of the trait, as seen from inside
+/// one of the trait's (quantified) where clauses. That's `^1.0` because
+/// we're one binder in.
+fn is_self<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &Ty<I>) -> bool {
+    ty.bound_var(db.interner()) == Some(BoundVar::new(DebruijnIndex::ONE, 0))
+}
+
+/// True if any of `args` (taken from inside one of the trait's where
+/// clauses) mentions the trait's `Self` parameter.
+fn references_self<I: Interner>(db: &dyn RustIrDatabase<I>, args: &[GenericArg<I>]) -> bool {
+    let mut visitor = SelfVisitor {
+        interner: db.interner(),
+    };
+    args.iter()
+        .any(|arg| arg.visit_with(&mut visitor, DebruijnIndex::ONE) == FindAny::FOUND)
+}
+
+/// Looks for occurrences of the trait's `Self` parameter. Visiting starts
+/// at `DebruijnIndex::ONE` (inside a quantified where clause), so `Self` is
+/// always `^n.0` where `n` is the current `outer_binder`.
+struct SelfVisitor<'i, I: Interner> {
+    interner: &'i I,
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for SelfVisitor<'i, I> {
+    type Result = FindAny;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Self::Result {
+        match ty.data(self.interner) {
+            TyData::BoundVar(bound_var) => {
+                if *bound_var == BoundVar::new(outer_binder, 0) {
+                    FindAny::FOUND
+                } else {
+                    FindAny::new()
+                }
+            }
+            _ => ty.super_visit_with(self, outer_binder),
+        }
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+}
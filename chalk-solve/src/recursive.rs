@@ -4,26 +4,36 @@ mod search_graph;
 mod stack;
 
 use self::fulfill::{Fulfill, RecursiveInferenceTable, RecursiveSolver};
-use self::lib::{Guidance, Minimums, Solution, UCanonicalGoal};
+use self::lib::{Guidance, Minimums, ProofRecord, ProvenSubgoals, Solution, UCanonicalGoal};
 use self::search_graph::{DepthFirstNumber, SearchGraph};
 use self::stack::{Stack, StackDepth};
 use crate::clauses::program_clauses_for_goal;
 use crate::infer::{InferenceTable, ParameterEnaVariableExt};
-use crate::solve::truncate;
+use crate::solve::budget::BudgetTracker;
+use crate::solve::stats;
+use crate::solve::{truncate, Overflow, OverflowLimit};
 use crate::{coinductive_goal::IsCoinductive, RustIrDatabase};
+use chalk_ir::cast::Cast;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::Visit;
 use chalk_ir::zip::Zip;
-use chalk_ir::{debug, debug_heading, info, info_heading};
 use chalk_ir::{
-    Binders, Canonical, ClausePriority, ConstrainedSubst, Constraint, DomainGoal, Environment,
-    Fallible, Floundered, GenericArg, Goal, GoalData, InEnvironment, NoSolution, ProgramClause,
-    ProgramClauseData, ProgramClauseImplication, Substitution, UCanonical, UniverseMap,
-    VariableKinds,
+    Binders, Canonical, ClausePriority, ConstrainedSubst, Constraint, DebruijnIndex, DomainGoal,
+    Environment, Fallible, Floundered, FlounderedGoal, GenericArg, Goal, GoalData, InEnvironment,
+    NoSolution, ProgramClause, ProgramClauseData, ProgramClauseImplication, Substitution, Ty,
+    UCanonical, UnificationDatabase, UniverseMap, VariableKinds, Variance, WhereClause,
 };
 use rustc_hash::FxHashMap;
 use std::fmt::Debug;
+use tracing::{debug, debug_span, info, info_span};
+
+/// How many levels of ambiguous obligations are split into their
+/// candidates when explaining an ambiguous solution.
+const MAX_CANDIDATE_DEPTH: usize = 4;
+
+/// The clauses that yielded a solution to a goal, with their priorities.
+type ApplicableClauses<I> = Vec<(Binders<ProgramClauseImplication<I>>, ClausePriority)>;
 
 pub(crate) struct RecursiveContext<I: Interner> {
     stack: Stack,
@@ -38,8 +48,13 @@ pub(crate) struct RecursiveContext<I: Interner> {
     cache: FxHashMap<UCanonicalGoal<I>, Fallible<Solution<I>>>,
 
     caching_enabled: bool,
+
+    /// How each goal with a unique solution was proven, once
+    /// `record_proofs` has been called.
+    proofs: Option<FxHashMap<UCanonicalGoal<I>, ProofRecord<I>>>,
 }
 
+#[derive(Clone)]
 pub(crate) struct RecursiveInferenceTableImpl<I: Interner> {
     pub(crate) infer: InferenceTable<I>,
 }
@@ -115,6 +130,24 @@ impl<I: Interner> RecursiveInferenceTable<I> for RecursiveInferenceTableImpl<I>
         Ok((res.goals, res.constraints))
     }
 
+    fn relate_tys(
+        &mut self,
+        interner: &I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<(
+        Vec<InEnvironment<DomainGoal<I>>>,
+        Vec<InEnvironment<Constraint<I>>>,
+    )> {
+        let res = self
+            .infer
+            .relate_tys(interner, db, environment, variance, a, b)?;
+        Ok((res.goals, res.constraints))
+    }
+
     fn instantiate_canonical<T>(&mut self, interner: &I, bound: &Canonical<T>) -> T::Result
     where
         T: HasInterner<Interner = I> + Fold<I> + Debug,
@@ -146,6 +179,7 @@ impl<I: Interner> RecursiveInferenceTable<I> for RecursiveInferenceTableImpl<I>
 pub(crate) struct Solver<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     context: &'me mut RecursiveContext<I>,
+    budget: Option<&'me BudgetTracker<'me>>,
 }
 
 /// An extension trait for merging `Result`s
@@ -175,9 +209,34 @@ impl<I: Interner> RecursiveContext<I> {
             search_graph: SearchGraph::new(),
             cache: FxHashMap::default(),
             caching_enabled,
+            proofs: None,
+        }
+    }
+
+    /// Forgets the solutions of all goals solved so far.
+    pub(crate) fn clear_cache(&mut self) {
+        self.cache.clear();
+        if let Some(proofs) = &mut self.proofs {
+            proofs.clear();
         }
     }
 
+    /// Makes the solver record how it proves the goals that it solves from
+    /// now on, see `proof`. The cached solutions were found without doing
+    /// so, so the first call clears the cache.
+    pub(crate) fn record_proofs(&mut self) {
+        if self.proofs.is_none() {
+            self.cache.clear();
+            self.proofs = Some(FxHashMap::default());
+        }
+    }
+
+    /// How `goal` was proven, if it has a unique solution that was found
+    /// while recording proofs.
+    pub(crate) fn proof(&self, goal: &UCanonicalGoal<I>) -> Option<&ProofRecord<I>> {
+        self.proofs.as_ref()?.get(goal)
+    }
+
     pub(crate) fn solver<'me>(
         &'me mut self,
         program: &'me dyn RustIrDatabase<I>,
@@ -185,6 +244,20 @@ impl<I: Interner> RecursiveContext<I> {
         Solver {
             program,
             context: self,
+            budget: None,
+        }
+    }
+
+    /// Like `solver`, but the solver gives up once `budget` is exhausted.
+    pub(crate) fn solver_with_budget<'me>(
+        &'me mut self,
+        program: &'me dyn RustIrDatabase<I>,
+        budget: &'me BudgetTracker<'me>,
+    ) -> Solver<'me, I> {
+        Solver {
+            program,
+            context: self,
+            budget: Some(budget),
         }
     }
 }
@@ -234,18 +307,26 @@ impl<'me, I: Interner> Solver<'me, I> {
         self.solve_goal(canonical_goal.clone(), minimums)
     }
 
+    /// Returns the distinct solutions that the ambiguous solution of
+    /// `canonical_goal` was merged from: one per clause that applies to a
+    /// domain goal, split further for each candidate of the obligations
+    /// that `Fulfill` could not choose between.
+    pub(crate) fn root_goal_candidates(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+    ) -> Vec<Canonical<ConstrainedSubst<I>>> {
+        assert!(self.context.stack.is_empty());
+        let minimums = &mut Minimums::new();
+        self.solve_goal_candidates(canonical_goal.clone(), MAX_CANDIDATE_DEPTH, minimums)
+    }
+
     fn solve_new_subgoal(
         &mut self,
         canonical_goal: UCanonicalGoal<I>,
         depth: StackDepth,
         dfn: DepthFirstNumber,
     ) -> Minimums {
-        debug_heading!(
-            "solve_new_subgoal(canonical_goal={:?}, depth={:?}, dfn={:?})",
-            canonical_goal,
-            depth,
-            dfn,
-        );
+        let _span = debug_span!("solve_new_subgoal", ?canonical_goal, ?depth, ?dfn).entered();
 
         // We start with `answer = None` and try to solve the goal. At the end of the iteration,
         // `answer` will be updated with the result of the solving process. If we detect a cycle
@@ -267,8 +348,10 @@ impl<'me, I: Interner> Solver<'me, I> {
                     },
             } = canonical_goal.clone();
 
-            let (current_answer, current_prio) = match goal.data(self.program.interner()) {
-                GoalData::DomainGoal(domain_goal) => {
+            let (current_answer, current_prio, proof) = match goal.data(self.program.interner()) {
+                // Outlives goals are never solved from clauses; they are
+                // turned into region constraints during simplification.
+                GoalData::DomainGoal(domain_goal) if !is_outlives_goal(domain_goal) => {
                     let canonical_goal = UCanonical {
                         universes,
                         canonical: Canonical {
@@ -288,22 +371,39 @@ impl<'me, I: Interner> Solver<'me, I> {
 
                     let InEnvironment { environment, goal } = &canonical_goal.canonical.value;
 
-                    let (prog_solution, prog_prio) = {
-                        debug_heading!("prog_clauses");
+                    let (prog_solution, prog_prio, prog_proof) = {
+                        let _span = debug_span!("prog_clauses").entered();
 
                         let prog_clauses = self.program_clauses_for_goal(environment, &goal);
                         match prog_clauses {
                             Ok(clauses) => {
-                                self.solve_from_clauses(&canonical_goal, clauses, minimums)
+                                self.solve_from_clauses(&canonical_goal, clauses, minimums, None)
                             }
-                            Err(Floundered) => {
-                                (Ok(Solution::Ambig(Guidance::Unknown)), ClausePriority::High)
+                            Err(Floundered { ty }) => {
+                                let interner = self.program.interner();
+                                let goal = Canonical {
+                                    binders: canonical_goal.canonical.binders.clone(),
+                                    value: InEnvironment::new(
+                                        environment,
+                                        goal.clone().cast(interner),
+                                    ),
+                                };
+                                let variable = ty
+                                    .bound_var(interner)
+                                    .filter(|bv| bv.debruijn == DebruijnIndex::INNERMOST)
+                                    .map(|bv| bv.index);
+                                let floundered_goal = FlounderedGoal { goal, variable };
+                                (
+                                    Ok(Solution::Ambig(Guidance::Floundered(floundered_goal))),
+                                    ClausePriority::High,
+                                    None,
+                                )
                             }
                         }
                     };
                     debug!("prog_solution={:?}", prog_solution);
 
-                    (prog_solution, prog_prio)
+                    (prog_solution, prog_prio, prog_proof)
                 }
 
                 _ => {
@@ -324,6 +424,13 @@ impl<'me, I: Interner> Solver<'me, I> {
                 current_answer, minimums
             );
 
+            if let Some(proofs) = &mut self.context.proofs {
+                match proof {
+                    Some(proof) => proofs.insert(canonical_goal.clone(), proof),
+                    None => proofs.remove(&canonical_goal),
+                };
+            }
+
             if !self.context.stack[depth].read_and_reset_cycle_flag() {
                 // None of our subgoals depended on us directly.
                 // We can return.
@@ -375,52 +482,75 @@ impl<'me, I: Interner> Solver<'me, I> {
         &mut self,
         canonical_goal: &UCanonicalGoal<I>,
         minimums: &mut Minimums,
-    ) -> (Fallible<Solution<I>>, ClausePriority) {
-        debug_heading!("solve_via_simplification({:?})", canonical_goal);
+    ) -> (
+        Fallible<Solution<I>>,
+        ClausePriority,
+        Option<ProofRecord<I>>,
+    ) {
+        let _span = debug_span!("solve_via_simplification", ?canonical_goal).entered();
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         match Fulfill::new_with_simplification(self, infer, subst, goal) {
-            Ok(fulfill) => (fulfill.solve(minimums), ClausePriority::High),
-            Err(e) => (Err(e), ClausePriority::High),
+            Ok(fulfill) => {
+                let (solution, subgoals) = fulfill.solve(minimums);
+                let proof = ProofRecord::new(&solution, None, subgoals);
+                (solution, ClausePriority::High, proof)
+            }
+            Err(e) => (Err(e), ClausePriority::High, None),
         }
     }
 
     /// See whether we can solve a goal by implication on any of the given
     /// clauses. If multiple such solutions are possible, we attempt to combine
-    /// them.
+    /// them. If we record proofs and the combined solution is unique, also
+    /// returns how it was proven. If `applicable` is given, the clauses that
+    /// yielded a solution are pushed onto it along with their priorities.
     fn solve_from_clauses<C>(
         &mut self,
         canonical_goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
         clauses: C,
         minimums: &mut Minimums,
-    ) -> (Fallible<Solution<I>>, ClausePriority)
+        mut applicable: Option<&mut ApplicableClauses<I>>,
+    ) -> (
+        Fallible<Solution<I>>,
+        ClausePriority,
+        Option<ProofRecord<I>>,
+    )
     where
         C: IntoIterator<Item = ProgramClause<I>>,
     {
         let mut cur_solution = None;
+        let mut cur_proof = None;
         for program_clause in clauses {
-            debug_heading!("clause={:?}", program_clause);
+            // If we have a completely ambiguous answer, it's not going to get better, so stop,
+            // unless the caller wants all the clauses that apply
+            if let Some((Solution::Ambig(guidance), ClausePriority::High)) = &cur_solution {
+                if guidance.is_unknown() && applicable.is_none() {
+                    let guidance = guidance.clone();
+                    return (Ok(Solution::Ambig(guidance)), ClausePriority::High, None);
+                }
+            }
 
-            // If we have a completely ambiguous answer, it's not going to get better, so stop
-            if cur_solution == Some((Solution::Ambig(Guidance::Unknown), ClausePriority::High)) {
-                return (Ok(Solution::Ambig(Guidance::Unknown)), ClausePriority::High);
+            if let Some(budget) = self.budget {
+                budget.charge_clause_instantiation();
             }
 
-            let res = match program_clause.data(self.program.interner()) {
-                ProgramClauseData::Implies(implication) => self.solve_via_implication(
-                    canonical_goal,
-                    &Binders::new(
-                        VariableKinds::from(self.program.interner(), vec![]),
-                        implication.clone(),
-                    ),
-                    minimums,
+            info!(name: "clause_tried", clause = ?program_clause, "clause tried");
+            let implication = match program_clause.data(self.program.interner()) {
+                ProgramClauseData::Implies(implication) => Binders::new(
+                    VariableKinds::from(self.program.interner(), vec![]),
+                    implication.clone(),
                 ),
-                ProgramClauseData::ForAll(implication) => {
-                    self.solve_via_implication(canonical_goal, implication, minimums)
-                }
+                ProgramClauseData::ForAll(implication) => implication.clone(),
             };
-            if let (Ok(solution), priority) = res {
+            let (res, priority, subgoals) =
+                self.solve_via_implication(canonical_goal, &implication, minimums);
+            if let (Ok(_), Some(applicable)) = (&res, &mut applicable) {
+                applicable.push((implication, priority));
+            }
+            let proof = ProofRecord::new(&res, Some(program_clause.clone()), subgoals);
+            if let Ok(solution) = res {
                 debug!("ok: solution={:?} prio={:?}", solution, priority);
-                cur_solution = Some(match cur_solution {
+                let (solution, priority) = match cur_solution {
                     None => (solution, priority),
                     Some((cur, cur_priority)) => combine_with_priorities(
                         self.program.interner(),
@@ -430,33 +560,44 @@ impl<'me, I: Interner> Solver<'me, I> {
                         solution,
                         priority,
                     ),
-                });
+                };
+                // Keep the proof of whichever clause the combined solution
+                // came from, preferring the earlier one if both agree.
+                cur_proof = match &solution {
+                    Solution::Unique(subst) => cur_proof
+                        .filter(|proof: &ProofRecord<I>| proof.solution == *subst)
+                        .or_else(|| proof.filter(|proof| proof.solution == *subst)),
+                    Solution::Ambig(_) => None,
+                };
+                cur_solution = Some((solution, priority));
             } else {
                 debug!("error");
             }
         }
-        cur_solution.map_or((Err(NoSolution), ClausePriority::High), |(s, p)| (Ok(s), p))
+        match cur_solution {
+            Some((solution, priority)) => (Ok(solution), priority, cur_proof),
+            None => (Err(NoSolution), ClausePriority::High, None),
+        }
     }
 
     /// Modus ponens! That is: try to apply an implication by proving its premises.
+    /// If we record proofs and the solution is unique, also returns the
+    /// premises that were proven.
     fn solve_via_implication(
         &mut self,
         canonical_goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
         clause: &Binders<ProgramClauseImplication<I>>,
         minimums: &mut Minimums,
-    ) -> (Fallible<Solution<I>>, ClausePriority) {
-        info_heading!(
-            "solve_via_implication(\
-             \n    canonical_goal={:?},\
-             \n    clause={:?})",
-            canonical_goal,
-            clause
-        );
+    ) -> (Fallible<Solution<I>>, ClausePriority, ProvenSubgoals<I>) {
+        let _span = info_span!("solve_via_implication", ?canonical_goal, ?clause).entered();
 
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         match Fulfill::new_with_clause(self, infer, subst, goal, clause) {
-            Ok(fulfill) => (fulfill.solve(minimums), clause.skip_binders().priority),
-            Err(e) => (Err(e), ClausePriority::High),
+            Ok(fulfill) => {
+                let (solution, subgoals) = fulfill.solve(minimums);
+                (solution, clause.skip_binders().priority, subgoals)
+            }
+            Err(e) => (Err(e), ClausePriority::High, None),
         }
     }
 
@@ -464,7 +605,7 @@ impl<'me, I: Interner> Solver<'me, I> {
         &self,
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
-    ) -> Result<Vec<ProgramClause<I>>, Floundered> {
+    ) -> Result<Vec<ProgramClause<I>>, Floundered<I>> {
         program_clauses_for_goal(self.program, environment, goal)
     }
 }
@@ -478,18 +619,22 @@ impl<'me, I: Interner> RecursiveSolver<I> for Solver<'me, I> {
         goal: UCanonicalGoal<I>,
         minimums: &mut Minimums,
     ) -> Fallible<Solution<I>> {
-        info_heading!("solve_goal({:?})", goal);
+        let _span = info_span!("solve_goal", ?goal).entered();
 
         // First check the cache.
         if let Some(value) = self.context.cache.get(&goal) {
             debug!("solve_reduced_goal: cache hit, value={:?}", value);
+            stats::record(|stats| stats.cache_hits += 1);
             return value.clone();
         }
+        stats::record(|stats| stats.cache_misses += 1);
 
         // Next, check if the goal is in the search tree already.
         if let Some(dfn) = self.context.search_graph.lookup(&goal) {
             // Check if this table is still on the stack.
             if let Some(depth) = self.context.search_graph[dfn].stack_depth {
+                info!(name: "cycle_detected", ?goal, ?depth, "cycle detected");
+
                 // Is this a coinductive goal? If so, that is success,
                 // so we can return normally. Note that this return is
                 // not tabled.
@@ -523,8 +668,28 @@ impl<'me, I: Interner> RecursiveSolver<I> for Solver<'me, I> {
         } else {
             // Otherwise, push the goal onto the stack and create a table.
             // The initial result for this table is error.
+            if self.budget_exhausted() {
+                return Ok(Solution::Ambig(Guidance::Unknown));
+            }
+            if let Some(budget) = self.budget {
+                budget.charge_table();
+            }
+            stats::record(|stats| stats.tables_created += 1);
             let coinductive_goal = goal.is_coinductive(self.program);
-            let depth = self.context.stack.push(coinductive_goal);
+            let depth = match self.context.stack.push(coinductive_goal) {
+                Some(depth) => depth,
+                None => {
+                    info!("solve_goal: overflow depth reached");
+                    let mut goals = self.goal_chain();
+                    goals.push(goal.canonical);
+                    let limit = OverflowLimit::OverflowDepth(self.context.stack.overflow_depth());
+                    return Ok(Solution::Ambig(Guidance::Overflow(Overflow {
+                        goals,
+                        limit,
+                    })));
+                }
+            };
+            info!(name: "goal_entered", ?goal, ?depth, "goal entered");
             let dfn = self.context.search_graph.insert(&goal, depth);
             let subgoal_minimums = self.solve_new_subgoal(goal, depth, dfn);
             self.context.search_graph[dfn].links = subgoal_minimums;
@@ -535,13 +700,20 @@ impl<'me, I: Interner> RecursiveSolver<I> for Solver<'me, I> {
             // Read final result from table.
             let result = self.context.search_graph[dfn].solution.clone();
             let priority = self.context.search_graph[dfn].solution_priority;
+            if let Ok(solution) = &result {
+                let goal = &self.context.search_graph[dfn].goal;
+                info!(name: "answer_found", ?goal, ?solution, "answer found");
+                stats::record(|stats| stats.answers_produced += 1);
+            }
 
             // If processing this subgoal did not involve anything
             // outside of its subtree, then we can promote it to the
             // cache now. This is a sort of hack to alleviate the
             // worst of the repeated work that we do during tabling.
             if subgoal_minimums.positive >= dfn {
-                if self.context.caching_enabled {
+                // Results computed after the budget ran out may be missing
+                // answers, so they must not outlive this solve.
+                if self.context.caching_enabled && !self.budget_exhausted() {
                     self.context
                         .search_graph
                         .move_to_cache(dfn, &mut self.context.cache);
@@ -559,9 +731,99 @@ impl<'me, I: Interner> RecursiveSolver<I> for Solver<'me, I> {
         }
     }
 
+    fn solve_goal_candidates(
+        &mut self,
+        canonical_goal: UCanonicalGoal<I>,
+        depth: usize,
+        minimums: &mut Minimums,
+    ) -> Vec<Canonical<ConstrainedSubst<I>>> {
+        let _span = debug_span!("solve_goal_candidates", ?canonical_goal, ?depth).entered();
+        let UCanonical {
+            universes,
+            canonical:
+                Canonical {
+                    binders,
+                    value: InEnvironment { environment, goal },
+                },
+        } = canonical_goal.clone();
+
+        let domain_goal = match goal.data(self.program.interner()) {
+            GoalData::DomainGoal(domain_goal) if !is_outlives_goal(domain_goal) => domain_goal,
+            _ => {
+                let (infer, subst, goal) = self.new_inference_table(&canonical_goal);
+                return match Fulfill::new_with_simplification(self, infer, subst, goal) {
+                    Ok(fulfill) => fulfill.candidates(depth, minimums),
+                    Err(NoSolution) => vec![],
+                };
+            }
+        };
+        let canonical_goal = UCanonical {
+            universes,
+            canonical: Canonical {
+                binders,
+                value: InEnvironment::new(&environment, domain_goal.clone()),
+            },
+        };
+        let clauses = match self.program_clauses_for_goal(&environment, domain_goal) {
+            Ok(clauses) => clauses,
+            Err(Floundered { .. }) => return vec![],
+        };
+
+        // Split up the solutions of the clauses that the combined solution
+        // came from; low priority clauses are dropped if a high priority
+        // one applies.
+        let mut applicable = vec![];
+        let (_, combined_priority, _) =
+            self.solve_from_clauses(&canonical_goal, clauses, minimums, Some(&mut applicable));
+
+        let mut candidates = vec![];
+        for (implication, priority) in applicable {
+            if priority != combined_priority {
+                continue;
+            }
+            let (infer, subst, goal) = self.new_inference_table(&canonical_goal);
+            if let Ok(fulfill) = Fulfill::new_with_clause(self, infer, subst, goal, &implication) {
+                for candidate in fulfill.candidates(depth, minimums) {
+                    if !candidates.contains(&candidate) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    fn budget(&self) -> Option<&BudgetTracker<'_>> {
+        self.budget
+    }
+
+    fn records_proofs(&self) -> bool {
+        self.context.proofs.is_some()
+    }
+
+    fn goal_chain(&self) -> Vec<Canonical<InEnvironment<Goal<I>>>> {
+        self.context
+            .search_graph
+            .stack_goals()
+            .map(|goal| goal.canonical.clone())
+            .collect()
+    }
+
     fn interner(&self) -> &I {
         &self.program.interner()
     }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self.program.unification_database()
+    }
+}
+
+fn is_outlives_goal<I: Interner>(domain_goal: &DomainGoal<I>) -> bool {
+    matches!(
+        domain_goal,
+        DomainGoal::Holds(WhereClause::LifetimeOutlives(_))
+            | DomainGoal::Holds(WhereClause::TypeOutlives(_))
+    )
 }
 
 fn calculate_inputs<I: Interner>(
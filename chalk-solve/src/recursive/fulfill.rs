@@ -1,17 +1,25 @@
-use super::lib::{Guidance, Minimums, Solution};
+use super::lib::{Guidance, Minimums, ProofSubgoal, ProvenSubgoals, Solution, UCanonicalGoal};
+use crate::solve::budget::BudgetTracker;
+use crate::solve::stats;
+use crate::solve::{Overflow, OverflowLimit};
 use chalk_ir::cast::Cast;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::Visit;
 use chalk_ir::zip::Zip;
-use chalk_ir::{debug, debug_heading};
 use chalk_ir::{
     Binders, Canonical, ConstrainedSubst, Constraint, DomainGoal, Environment, EqGoal, Fallible,
-    GenericArg, Goal, GoalData, InEnvironment, LifetimeOutlives, NoSolution,
-    ProgramClauseImplication, QuantifierKind, Substitution, UCanonical, UniverseMap, WhereClause,
+    FlounderedGoal, GenericArg, Goal, GoalData, InEnvironment, LifetimeOutlives, NoSolution,
+    ProgramClauseImplication, QuantifierKind, Substitution, SubtypeGoal, Ty, TypeOutlives,
+    UCanonical, UnificationDatabase, UniverseMap, Variance, WhereClause,
 };
 use rustc_hash::FxHashSet;
 use std::fmt::Debug;
+use tracing::{debug, debug_span};
+
+/// Obligations larger than this are not solved; the solution is ambiguous
+/// instead.
+const MAX_OBLIGATION_SIZE: usize = 30;
 
 enum Outcome {
     Complete,
@@ -47,6 +55,9 @@ struct PositiveSolution<I: Interner> {
     free_vars: Vec<GenericArg<I>>,
     universes: UniverseMap,
     solution: Solution<I>,
+
+    /// The canonical goal that was solved, if we record proofs.
+    goal: Option<UCanonicalGoal<I>>,
 }
 
 /// When refuting a goal, there's no impact on inference state.
@@ -104,6 +115,19 @@ pub(crate) trait RecursiveInferenceTable<I: Interner> {
     where
         T: ?Sized + Zip<I>;
 
+    fn relate_tys(
+        &mut self,
+        interner: &I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<(
+        Vec<InEnvironment<DomainGoal<I>>>,
+        Vec<InEnvironment<Constraint<I>>>,
+    )>;
+
     fn instantiate_canonical<T>(&mut self, interner: &I, bound: &Canonical<T>) -> T::Result
     where
         T: HasInterner<Interner = I> + Fold<I> + Debug;
@@ -126,7 +150,35 @@ pub trait RecursiveSolver<I: Interner> {
         minimums: &mut Minimums,
     ) -> Fallible<Solution<I>>;
 
+    /// Returns the distinct solutions that could solve `goal`, instead of
+    /// merging them into guidance like `solve_goal` does. Ambiguous
+    /// obligations are split into their candidates at most `depth` levels
+    /// deep.
+    fn solve_goal_candidates(
+        &mut self,
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        depth: usize,
+        minimums: &mut Minimums,
+    ) -> Vec<Canonical<ConstrainedSubst<I>>>;
+
+    /// The budget of the current solve, if it has one.
+    fn budget(&self) -> Option<&BudgetTracker<'_>>;
+
+    /// True if the solver records how goals were proven, in which case
+    /// `Fulfill` keeps track of the subgoals it solves.
+    fn records_proofs(&self) -> bool;
+
+    /// True once the current solve has used up its budget.
+    fn budget_exhausted(&self) -> bool {
+        matches!(self.budget(), Some(budget) if budget.is_exhausted())
+    }
+
+    /// The goals that are being solved, outermost first.
+    fn goal_chain(&self) -> Vec<Canonical<InEnvironment<Goal<I>>>>;
+
     fn interner(&self) -> &I;
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I>;
 }
 
 /// A `Fulfill` is where we actually break down complex goals, instantiate
@@ -160,6 +212,22 @@ pub(crate) struct Fulfill<
     /// refuted. In such a case the solution will be either `CannotProve`, or `Err`
     /// in the case where some other goal leads to an error.
     cannot_prove: bool,
+
+    /// A goal that floundered while solving one of the obligations that the
+    /// last round of `fulfill` left ambiguous, if any.
+    floundered: Option<FlounderedGoal<I>>,
+
+    /// Set along with `cannot_prove` if some obligation was too large to be
+    /// solved.
+    overflow: Option<Overflow<I>>,
+
+    /// An overflow that left one of the obligations of the last round of
+    /// `fulfill` ambiguous, if any.
+    subgoal_overflow: Option<Overflow<I>>,
+
+    /// The obligations that were proven or refuted so far, if the solver
+    /// records proofs.
+    proven: ProvenSubgoals<I>,
 }
 
 impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable<I>>
@@ -172,6 +240,7 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         canonical_goal: InEnvironment<DomainGoal<I>>,
         clause: &Binders<ProgramClauseImplication<I>>,
     ) -> Fallible<Self> {
+        let proven = solver.records_proofs().then(Vec::new);
         let mut fulfill = Fulfill {
             solver,
             infer,
@@ -179,6 +248,10 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             obligations: vec![],
             constraints: FxHashSet::default(),
             cannot_prove: false,
+            floundered: None,
+            overflow: None,
+            subgoal_overflow: None,
+            proven,
         };
 
         let ProgramClauseImplication {
@@ -215,6 +288,7 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         subst: Substitution<I>,
         canonical_goal: InEnvironment<Goal<I>>,
     ) -> Fallible<Self> {
+        let proven = solver.records_proofs().then(Vec::new);
         let mut fulfill = Fulfill {
             solver,
             infer,
@@ -222,6 +296,10 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             obligations: vec![],
             constraints: FxHashSet::default(),
             cannot_prove: false,
+            floundered: None,
+            overflow: None,
+            subgoal_overflow: None,
+            proven,
         };
 
         if let Err(e) = fulfill.push_goal(&canonical_goal.environment, canonical_goal.goal.clone())
@@ -238,20 +316,22 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             Obligation::Prove(goal) => {
                 if self
                     .infer
-                    .needs_truncation(self.solver.interner(), 30, goal)
+                    .needs_truncation(self.solver.interner(), MAX_OBLIGATION_SIZE, goal)
                 {
                     // the goal is too big. Record that we should return Ambiguous
                     self.cannot_prove = true;
+                    self.record_overflow(goal);
                     return;
                 }
             }
             Obligation::Refute(goal) => {
                 if self
                     .infer
-                    .needs_truncation(self.solver.interner(), 30, goal)
+                    .needs_truncation(self.solver.interner(), MAX_OBLIGATION_SIZE, goal)
                 {
                     // the goal is too big. Record that we should return Ambiguous
                     self.cannot_prove = true;
+                    self.record_overflow(goal);
                     return;
                 }
             }
@@ -259,6 +339,21 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         self.obligations.push(obligation);
     }
 
+    /// Records that `goal` was too large to be solved, along with the
+    /// goals that led to it, unless some other obligation already was.
+    fn record_overflow(&mut self, goal: &InEnvironment<Goal<I>>) {
+        if self.overflow.is_some() {
+            return;
+        }
+        let mut goals = self.solver.goal_chain();
+        let (goal, _) = self.infer.canonicalize(self.solver.interner(), goal);
+        goals.push(goal);
+        self.overflow = Some(Overflow {
+            goals,
+            limit: OverflowLimit::MaxSize(MAX_OBLIGATION_SIZE),
+        });
+    }
+
     /// Unifies `a` and `b` in the given environment.
     ///
     /// Wraps `InferenceTable::unify`; any resulting normalizations are added
@@ -281,6 +376,36 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         Ok(())
     }
 
+    /// Relates `a` and `b` with the given variance in the given environment.
+    ///
+    /// Wraps `InferenceTable::relate_tys`; like `unify`, any resulting
+    /// normalizations are added into our list of pending obligations.
+    pub(crate) fn relate(
+        &mut self,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+    ) -> Fallible<()> {
+        let (goals, constraints) = self.infer.relate_tys(
+            self.solver.interner(),
+            self.solver.unification_database(),
+            environment,
+            variance,
+            a,
+            b,
+        )?;
+        debug!("relate({:?}, {:?}, {:?}) succeeded", variance, a, b);
+        debug!("relate: goals={:?}", goals);
+        debug!("relate: constraints={:?}", constraints);
+        self.constraints.extend(constraints);
+        for goal in goals {
+            let goal = goal.cast(self.solver.interner());
+            self.push_obligation(Obligation::Prove(goal));
+        }
+        Ok(())
+    }
+
     /// Create obligations for the given goal in the given environment. This may
     /// ultimately create any number of obligations.
     pub(crate) fn push_goal(
@@ -324,6 +449,12 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
                         Constraint::Outlives(a.clone(), b.clone()),
                     ));
                 }
+                DomainGoal::Holds(WhereClause::TypeOutlives(TypeOutlives { ty, lifetime })) => {
+                    self.constraints.insert(InEnvironment::new(
+                        &environment,
+                        Constraint::TypeOutlives(ty.clone(), lifetime.clone()),
+                    ));
+                }
                 _ => {
                     let in_env = InEnvironment::new(environment, goal);
                     self.push_obligation(Obligation::Prove(in_env));
@@ -332,6 +463,9 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             GoalData::EqGoal(EqGoal { a, b }) => {
                 self.unify(&environment, &a, &b)?;
             }
+            GoalData::SubtypeGoal(SubtypeGoal { a, b }) => {
+                self.relate(&environment, Variance::Covariant, &a, &b)?;
+            }
             GoalData::CannotProve(()) => {
                 self.cannot_prove = true;
             }
@@ -347,11 +481,13 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         let interner = self.solver.interner();
         let (quantified, free_vars) = self.infer.canonicalize(interner, &wc);
         let (quantified, universes) = self.infer.u_canonicalize(interner, &quantified);
+        let goal = self.proven.as_ref().map(|_| quantified.clone());
         let result = self.solver.solve_goal(quantified, minimums);
         Ok(PositiveSolution {
             free_vars,
             universes,
             solution: result?,
+            goal,
         })
     }
 
@@ -364,6 +500,21 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             None => {
                 // Treat non-ground negatives as ambiguous. Note that, as inference
                 // proceeds, we may wind up with more information here.
+                if self.floundered.is_none() {
+                    let interner = self.solver.interner();
+                    let not_goal = GoalData::Not(goal.goal.clone()).intern(interner);
+                    let (goal, _) = self
+                        .infer
+                        .canonicalize(interner, &InEnvironment::new(&goal.environment, not_goal));
+                    // Any of the goal's unresolved variables could be to blame;
+                    // report the first one.
+                    let variable = if goal.binders.is_empty(interner) {
+                        None
+                    } else {
+                        Some(0)
+                    };
+                    self.floundered = Some(FlounderedGoal { goal, variable });
+                }
                 return Ok(NegativeSolution::Ambiguous);
             }
         };
@@ -373,6 +524,7 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             .infer
             .u_canonicalize(self.solver.interner(), &canonicalized);
         let mut minimums = Minimums::new(); // FIXME -- minimums here seems wrong
+        let goal = self.proven.as_ref().map(|_| quantified.clone());
         if let Ok(solution) = self.solver.solve_goal(quantified, &mut minimums) {
             if solution.is_unique() {
                 Err(NoSolution)
@@ -380,6 +532,9 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
                 Ok(NegativeSolution::Ambiguous)
             }
         } else {
+            if let (Some(proven), Some(goal)) = (&mut self.proven, goal) {
+                proven.push(ProofSubgoal::Refuted(goal));
+            }
             Ok(NegativeSolution::Refuted)
         }
     }
@@ -428,7 +583,7 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
     }
 
     fn fulfill(&mut self, minimums: &mut Minimums) -> Fallible<Outcome> {
-        debug_heading!("fulfill(obligations={:#?})", self.obligations);
+        let _span = debug_span!("fulfill", obligations = ?self.obligations).entered();
 
         // Try to solve all the obligations. We do this via a fixed-point
         // iteration. We try to solve each obligation in turn. Anything which is
@@ -440,7 +595,9 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
 
         while progress {
             progress = false;
-            debug_heading!("start of round, {} obligations", self.obligations.len());
+            self.floundered = None;
+            self.subgoal_overflow = None;
+            let _span = debug_span!("round", obligations = self.obligations.len()).entered();
 
             // Take the list of `obligations` to solve this round and replace it
             // with an empty vector. Iterate through each obligation to solve
@@ -450,14 +607,34 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
             // directly.
             assert!(obligations.is_empty());
             while let Some(obligation) = self.obligations.pop() {
+                if self.solver.budget_exhausted() {
+                    // Give up on the remaining obligations; the caller
+                    // reports that the budget was exceeded.
+                    self.obligations.push(obligation);
+                    self.obligations.append(&mut obligations);
+                    self.cannot_prove = true;
+                    return Ok(Outcome::Incomplete);
+                }
+                if let Some(budget) = self.solver.budget() {
+                    budget.charge_strand();
+                }
+                stats::record(|stats| stats.strands_processed += 1);
+
                 let ambiguous = match obligation {
                     Obligation::Prove(ref wc) => {
                         let PositiveSolution {
                             free_vars,
                             universes,
                             solution,
+                            goal,
                         } = self.prove(wc, minimums)?;
 
+                        if let (Some(proven), Some(goal), true) =
+                            (&mut self.proven, goal, solution.is_unique())
+                        {
+                            proven.push(ProofSubgoal::Proven(goal));
+                        }
+
                         if solution.has_definite() {
                             if let Some(constrained_subst) = solution.constrained_subst() {
                                 self.apply_solution(free_vars, universes, constrained_subst);
@@ -465,6 +642,20 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
                             }
                         }
 
+                        match &solution {
+                            Solution::Ambig(Guidance::Floundered(floundered_goal))
+                                if self.floundered.is_none() =>
+                            {
+                                self.floundered = Some(floundered_goal.clone());
+                            }
+                            Solution::Ambig(Guidance::Overflow(overflow))
+                                if self.subgoal_overflow.is_none() =>
+                            {
+                                self.subgoal_overflow = Some(overflow.clone());
+                            }
+                            _ => {}
+                        }
+
                         solution.is_ambig()
                     }
                     Obligation::Refute(ref goal) => {
@@ -498,14 +689,31 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
     /// Try to fulfill all pending obligations and build the resulting
     /// solution. The returned solution will transform `subst` substitution with
     /// the outcome of type inference by updating the replacements it provides.
-    pub(super) fn solve(mut self, minimums: &mut Minimums) -> Fallible<Solution<I>> {
-        let outcome = match self.fulfill(minimums) {
-            Ok(o) => o,
-            Err(e) => return Err(e),
-        };
+    ///
+    /// If the solver records proofs and the solution is unique, also
+    /// returns the subgoals that were proven or refuted to find it.
+    pub(super) fn solve(
+        mut self,
+        minimums: &mut Minimums,
+    ) -> (Fallible<Solution<I>>, ProvenSubgoals<I>) {
+        let outcome = self.fulfill(minimums);
+        let proven = self.proven.take();
+        match outcome.and_then(|outcome| self.into_solution(outcome, minimums)) {
+            Ok(Solution::Unique(subst)) => (Ok(Solution::Unique(subst)), proven),
+            result => (result, None),
+        }
+    }
 
+    /// Builds the solution once `fulfill` has run to completion.
+    fn into_solution(mut self, outcome: Outcome, minimums: &mut Minimums) -> Fallible<Solution<I>> {
         if self.cannot_prove {
-            return Ok(Solution::Ambig(Guidance::Unknown));
+            // Obligations that overflowed might have refuted the goal outright,
+            // so report that rather than the (possibly unrelated) reason we
+            // cannot prove it.
+            return match self.overflow.or(self.subgoal_overflow) {
+                Some(overflow) => Ok(Solution::Ambig(Guidance::Overflow(overflow))),
+                None => Ok(Solution::Ambig(Guidance::Unknown)),
+            };
         }
 
         if outcome.is_complete() {
@@ -545,6 +753,7 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
                         free_vars,
                         universes,
                         solution,
+                        goal: _,
                     } = self.prove(&goal, minimums).unwrap();
                     if let Some(constrained_subst) = solution.constrained_subst() {
                         self.apply_solution(free_vars, universes, constrained_subst);
@@ -553,7 +762,13 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
                 }
             }
 
-            Ok(Solution::Ambig(Guidance::Unknown))
+            match (self.floundered, self.subgoal_overflow) {
+                (Some(floundered_goal), _) => {
+                    Ok(Solution::Ambig(Guidance::Floundered(floundered_goal)))
+                }
+                (None, Some(overflow)) => Ok(Solution::Ambig(Guidance::Overflow(overflow))),
+                (None, None) => Ok(Solution::Ambig(Guidance::Unknown)),
+            }
         } else {
             // While we failed to prove the goal, we still learned that
             // something had to hold. Here's an example where this happens:
@@ -583,3 +798,82 @@ impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable
         self.solver.interner()
     }
 }
+
+impl<'s, I: Interner, Solver: RecursiveSolver<I>, Infer: RecursiveInferenceTable<I> + Clone>
+    Fulfill<'s, I, Solver, Infer>
+{
+    /// Like `solve`, but returns the distinct solutions that the pending
+    /// obligations could be resolved to, rather than merging them into
+    /// guidance. If some obligation remains ambiguous, each candidate
+    /// solution of that obligation is applied in turn and the remaining
+    /// obligations are fulfilled again; this is repeated at most `depth`
+    /// times.
+    pub(super) fn candidates(
+        mut self,
+        depth: usize,
+        minimums: &mut Minimums,
+    ) -> Vec<Canonical<ConstrainedSubst<I>>> {
+        let mut candidates = vec![];
+        self.push_candidates(depth, minimums, &mut candidates);
+        candidates
+    }
+
+    fn push_candidates(
+        &mut self,
+        depth: usize,
+        minimums: &mut Minimums,
+        candidates: &mut Vec<Canonical<ConstrainedSubst<I>>>,
+    ) {
+        let outcome = match self.fulfill(minimums) {
+            Ok(outcome) => outcome,
+            Err(NoSolution) => return,
+        };
+
+        let ambiguous = self
+            .obligations
+            .iter()
+            .find_map(|obligation| match obligation {
+                Obligation::Prove(goal) => Some(goal.clone()),
+                Obligation::Refute(_) => None,
+            });
+        if let (Outcome::Incomplete, Some(goal), false) = (outcome, ambiguous, self.cannot_prove) {
+            if depth > 0 {
+                let interner = self.solver.interner();
+                let (quantified, free_vars) = self.infer.canonicalize(interner, &goal);
+                let (quantified, universes) = self.infer.u_canonicalize(interner, &quantified);
+                let solutions = self
+                    .solver
+                    .solve_goal_candidates(quantified, depth - 1, minimums);
+                if solutions.len() > 1 {
+                    for solution in solutions {
+                        let infer = self.infer.clone();
+                        let subst = self.subst.clone();
+                        let obligations = self.obligations.clone();
+                        let constraints = self.constraints.clone();
+                        self.apply_solution(free_vars.clone(), universes.clone(), solution);
+                        self.push_candidates(depth - 1, minimums, candidates);
+                        self.infer = infer;
+                        self.subst = subst;
+                        self.obligations = obligations;
+                        self.constraints = constraints;
+                    }
+                    return;
+                }
+            }
+        }
+
+        let candidate = self
+            .infer
+            .canonicalize(
+                self.solver.interner(),
+                &ConstrainedSubst {
+                    subst: self.subst.clone(),
+                    constraints: self.constraints.iter().cloned().collect(),
+                },
+            )
+            .0;
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+}
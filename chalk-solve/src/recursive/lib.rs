@@ -1,8 +1,12 @@
 use super::search_graph::DepthFirstNumber;
-use chalk_ir::debug;
+use crate::solve::Overflow;
 use chalk_ir::interner::Interner;
-use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, Substitution, UCanonical};
+use chalk_ir::{
+    Canonical, ConstrainedSubst, Fallible, FlounderedGoal, Goal, InEnvironment, ProgramClause,
+    Substitution, UCanonical,
+};
 use std::fmt;
+use tracing::debug;
 
 pub type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
 
@@ -25,6 +29,55 @@ impl Minimums {
     }
 }
 
+/// How a goal with a unique solution was proven; see
+/// `RecursiveContext::record_proofs`.
+#[derive(Clone, Debug)]
+pub(crate) struct ProofRecord<I: Interner> {
+    /// The unique solution of the goal.
+    pub(crate) solution: Canonical<ConstrainedSubst<I>>,
+
+    /// The program clause that proved the goal, in terms of the canonical
+    /// variables of the goal. `None` if the goal is not a domain goal, and
+    /// was simplified into its subgoals instead.
+    pub(crate) clause: Option<ProgramClause<I>>,
+
+    /// The subgoals that this gave rise to, in the order they were solved.
+    pub(crate) subgoals: Vec<ProofSubgoal<I>>,
+}
+
+impl<I: Interner> ProofRecord<I> {
+    /// Records how `solution` was found, if it is unique and the subgoals
+    /// that were solved to find it were recorded.
+    pub(crate) fn new(
+        solution: &Fallible<Solution<I>>,
+        clause: Option<ProgramClause<I>>,
+        subgoals: ProvenSubgoals<I>,
+    ) -> Option<Self> {
+        match (solution, subgoals) {
+            (Ok(Solution::Unique(solution)), Some(subgoals)) => Some(ProofRecord {
+                solution: solution.clone(),
+                clause,
+                subgoals,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The subgoals that were proven or refuted to find a unique solution, if
+/// the solver records proofs.
+pub(crate) type ProvenSubgoals<I> = Option<Vec<ProofSubgoal<I>>>;
+
+/// A subgoal of a `ProofRecord`.
+#[derive(Clone, Debug)]
+pub(crate) enum ProofSubgoal<I: Interner> {
+    /// A goal with a unique solution.
+    Proven(UCanonicalGoal<I>),
+
+    /// A goal without any solution, from a `not { G }` goal.
+    Refuted(UCanonicalGoal<I>),
+}
+
 /// A (possible) solution for a proposed goal.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Solution<I: Interner> {
@@ -56,6 +109,26 @@ pub enum Guidance<I: Interner> {
 
     /// There's no useful information to feed back to type inference
     Unknown,
+
+    /// There's no useful information to feed back to type inference,
+    /// because solving the goal required enumerating the solutions of a
+    /// goal that has too many of them, e.g. `?T: Sized`.
+    Floundered(FlounderedGoal<I>),
+
+    /// There's no useful information to feed back to type inference,
+    /// because the solver ran into one of its limits.
+    Overflow(Overflow<I>),
+}
+
+impl<I: Interner> Guidance<I> {
+    /// True if there's no useful information to feed back to type
+    /// inference, i.e. there is no substitution at all.
+    pub(crate) fn is_unknown(&self) -> bool {
+        matches!(
+            self,
+            Guidance::Unknown | Guidance::Floundered(_) | Guidance::Overflow(_)
+        )
+    }
 }
 
 impl<I: Interner> Solution<I> {
@@ -101,6 +174,8 @@ impl<I: Interner> Solution<I> {
             (Suggested(ref subst1), Suggested(ref subst2)) if subst1 == subst2 => {
                 Suggested(subst1.clone())
             }
+            (Floundered(goal), _) | (_, Floundered(goal)) => Floundered(goal),
+            (Overflow(overflow), _) | (_, Overflow(overflow)) => Overflow(overflow),
             _ => Unknown,
         };
         Solution::Ambig(guidance)
@@ -189,6 +264,14 @@ impl<'a, I: Interner> fmt::Display for SolutionDisplay<'a, I> {
                 subst.display(interner)
             ),
             Solution::Ambig(Guidance::Unknown) => write!(f, "Ambiguous; no inference guidance"),
+            Solution::Ambig(Guidance::Floundered(floundered_goal)) => {
+                write!(f, "Ambiguous; no inference guidance; {}", floundered_goal)
+            }
+            Solution::Ambig(Guidance::Overflow(overflow)) => write!(
+                f,
+                "Ambiguous; no inference guidance; {}",
+                overflow.display(interner)
+            ),
         }
     }
 }
@@ -2,10 +2,10 @@ use std::ops::Add;
 use std::ops::Index;
 use std::ops::IndexMut;
 use std::usize;
+use tracing::{debug, info};
 
 use super::lib::{Minimums, Solution, UCanonicalGoal};
 use super::stack::StackDepth;
-use chalk_ir::debug;
 use chalk_ir::{interner::Interner, ClausePriority, Fallible, NoSolution};
 use rustc_hash::FxHashMap;
 
@@ -77,6 +77,15 @@ impl<I: Interner> SearchGraph<I> {
         dfn
     }
 
+    /// The goals that are actively being explored, from the bottom of the
+    /// stack to its top.
+    pub(crate) fn stack_goals(&self) -> impl Iterator<Item = &UCanonicalGoal<I>> + '_ {
+        self.nodes
+            .iter()
+            .filter(|node| node.stack_depth.is_some())
+            .map(|node| &node.goal)
+    }
+
     /// Clears all nodes with a depth-first number greater than or equal `dfn`.
     pub(crate) fn rollback_to(&mut self, dfn: DepthFirstNumber) {
         debug!("rollback_to(dfn={:?})", dfn);
@@ -96,7 +105,12 @@ impl<I: Interner> SearchGraph<I> {
         for node in self.nodes.drain(dfn.index..) {
             assert!(node.stack_depth.is_none());
             assert!(node.links.positive >= dfn);
-            debug!("caching solution {:?} for {:?}", node.solution, node.goal);
+            info!(
+                name: "table_completed",
+                goal = ?node.goal,
+                solution = ?node.solution,
+                "table completed"
+            );
             cache.insert(node.goal, node.solution);
         }
     }
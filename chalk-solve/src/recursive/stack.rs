@@ -39,23 +39,26 @@ impl Stack {
         self.entries.is_empty()
     }
 
-    pub(crate) fn push(&mut self, coinductive_goal: bool) -> StackDepth {
+    /// Pushes a goal onto the stack, or returns `None` if the stack is
+    /// already `overflow_depth` goals deep.
+    pub(crate) fn push(&mut self, coinductive_goal: bool) -> Option<StackDepth> {
         let depth = StackDepth {
             depth: self.entries.len(),
         };
 
         if depth.depth >= self.overflow_depth {
-            // This shoudl perhaps be a result or something, though
-            // really I'd prefer to move to subgoal abstraction for
-            // guaranteeing termination. -nmatsakis
-            panic!("overflow depth reached")
+            return None;
         }
 
         self.entries.push(StackEntry {
             coinductive_goal,
             cycle: false,
         });
-        depth
+        Some(depth)
+    }
+
+    pub(crate) fn overflow_depth(&self) -> usize {
+        self.overflow_depth
     }
 
     pub(crate) fn pop(&mut self, depth: StackDepth) {
@@ -9,9 +9,9 @@ use chalk_ir::cast::Cast;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::{Interner, TargetInterner};
 use chalk_ir::{
-    AdtId, AliasEq, AliasTy, AssocTypeId, Binders, DebruijnIndex, FnDefId, GenericArg, ImplId,
-    OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Substitution, ToGenericArg, TraitId, TraitRef,
-    Ty, TyData, TypeName, VariableKind, WhereClause, WithKind,
+    AdtId, AliasEq, AliasTy, AssocTypeId, Binders, ClosureId, DebruijnIndex, FnDefId, GeneratorId,
+    GenericArg, ImplId, OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Substitution,
+    ToGenericArg, TraitId, TraitRef, Ty, TyData, TypeName, VariableKind, WhereClause, WithKind,
 };
 use std::iter;
 
@@ -83,6 +83,15 @@ pub struct AdtDatum<I: Interner> {
     pub binders: Binders<AdtDatumBound<I>>,
     pub id: AdtId<I>,
     pub flags: AdtFlags,
+    pub kind: AdtKind,
+}
+
+/// The kind of an ADT: a struct, an enum or a union.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AdtKind {
+    Struct,
+    Enum,
+    Union,
 }
 
 impl<I: Interner> AdtDatum<I> {
@@ -93,10 +102,28 @@ impl<I: Interner> AdtDatum<I> {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
 pub struct AdtDatumBound<I: Interner> {
-    pub fields: Vec<Ty<I>>,
+    /// The variants of the ADT. A struct or a union always has exactly
+    /// one variant; an enum has one variant per enum variant (possibly
+    /// none at all, e.g. `enum Void {}`).
+    pub variants: Vec<AdtVariantDatum<I>>,
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
+/// The field types of a single variant of an ADT.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+pub struct AdtVariantDatum<I: Interner> {
+    pub fields: Vec<Ty<I>>,
+}
+
+impl<I: Interner> AdtDatumBound<I> {
+    /// Iterates over the types of all fields of all variants.
+    pub fn all_fields(&self) -> impl Iterator<Item = &Ty<I>> {
+        self.variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct AdtFlags {
     pub upstream: bool,
@@ -167,11 +194,117 @@ pub struct FnDefDatumBound<I: Interner> {
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A rust intermediate representation (rust_ir) of a closure. For example,
+/// in the following rust code:
+///
+/// ```ignore
+/// fn foo<T: Clone>(t: T) {
+///     let bar = move |x: u32| -> T { t.clone() };
+/// }
+/// ```
+///
+/// This would represent the closure `bar`, which has the generic parameter
+/// `T` (inherited from `foo`), the signature `fn(u32) -> T`, the upvar
+/// tuple `(T,)` and the closure kind `Fn`.
+pub struct ClosureDatum<I: Interner> {
+    pub id: ClosureId<I>,
+
+    /// The closure kind, which determines which of the `Fn`, `FnMut`
+    /// and `FnOnce` traits the closure implements.
+    pub kind: ClosureKind,
+
+    pub binders: Binders<ClosureDatumBound<I>>,
+}
+
+/// Represents the signature and upvars of a `ClosureDatum`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+pub struct ClosureDatumBound<I: Interner> {
+    /// Inputs and output of the closure. As with `FnDefDatumBound`, these
+    /// may refer to late-bound regions, which are bound here.
+    pub inputs_and_output: Binders<FnDefInputsAndOutputDatum<I>>,
+
+    /// The types captured by the closure, as a tuple.
+    pub upvars: Ty<I>,
+}
+
+/// The kind of a closure, i.e. the most specific `Fn`-family trait it implements.
+/// A closure of kind `Fn` also implements `FnMut` and `FnOnce`, and one of kind
+/// `FnMut` also implements `FnOnce`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClosureKind {
+    Fn,
+    FnMut,
+    FnOnce,
+}
+
+impl ClosureKind {
+    /// Returns true if a closure of this kind implements the given `Fn`-family trait.
+    pub fn implements(self, trait_kind: ClosureKind) -> bool {
+        // The variants are declared from most to least restrictive
+        self <= trait_kind
+    }
+}
+
+/// A rust intermediate representation (rust_ir) of a generator. For example,
+/// in the following rust code:
+///
+/// ```ignore
+/// fn foo<T: Clone>(t: T) {
+///     let gen = move |resume: u32| {
+///         let local = t.clone();
+///         yield 1u8;
+///         local
+///     };
+/// }
+/// ```
+///
+/// This would represent the generator `gen`, which has the generic parameter
+/// `T` (inherited from `foo`), the resume type `u32`, the yield type `u8`,
+/// the return type `T` and the upvars `[T]`. The types that are live across
+/// the yield point (here, `T` from `local`) are described separately by the
+/// generator's [`GeneratorWitnessDatum`].
+///
+/// [`GeneratorWitnessDatum`]: struct.GeneratorWitnessDatum.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GeneratorDatum<I: Interner> {
+    pub id: GeneratorId<I>,
+
+    pub input_output: Binders<GeneratorInputOutputDatum<I>>,
+}
+
+/// The signature and upvars of a `GeneratorDatum`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+pub struct GeneratorInputOutputDatum<I: Interner> {
+    pub resume_type: Ty<I>,
+    pub yield_type: Ty<I>,
+    pub return_type: Ty<I>,
+    /// The types captured by the generator.
+    pub upvars: Vec<Ty<I>>,
+}
+
+/// The types that are live across the yield points of a generator, i.e.
+/// the types stored in the generator's state. These are represented by the
+/// `GeneratorWitness` type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GeneratorWitnessDatum<I: Interner> {
+    /// The outer binders are the generic parameters of the generator.
+    pub inner_types: Binders<GeneratorWitnessExistential<I>>,
+}
+
+/// The witness types of a generator. The lifetimes of these types are
+/// erased during type checking, so they are bound existentially: we only
+/// know that the witness types use *some* regions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+pub struct GeneratorWitnessExistential<I: Interner> {
+    pub types: Binders<Vec<Ty<I>>>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 /// A rust intermediate representation (rust_ir) of a Trait Definition. For
 /// example, given the following rust code:
 ///
-/// ```compile_fail
+/// ```ignore
 /// use std::fmt::Debug;
 ///
 /// trait Foo<T>
@@ -224,6 +357,20 @@ pub enum WellKnownTrait {
     FnMut,
     Fn,
     Unsize,
+    /// The trait `Generator<R>`, with the associated types `Yield` and
+    /// `Return`. The generic argument `R` is the type the generator is
+    /// resumed with.
+    Generator,
+}
+
+/// A list of the associated types of well-known traits that chalk-solve
+/// refers to when it generates the hard-coded impls for those traits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum WellKnownAssocType {
+    /// `Generator::Yield`
+    GeneratorYield,
+    /// `Generator::Return`
+    GeneratorReturn,
 }
 
 impl<I: Interner> TraitDatum<I> {
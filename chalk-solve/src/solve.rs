@@ -13,12 +13,31 @@ use {
 };
 
 #[cfg(feature = "recursive-solver")]
-use crate::recursive::RecursiveContext;
+use {crate::ext::CanonicalExt, crate::recursive::RecursiveContext};
 
+use budget::BudgetTracker;
+
+pub(crate) mod budget;
+mod compare;
+mod dependencies;
+mod explain;
+mod failure;
+mod overflow;
+mod proof;
 #[cfg(feature = "slg-solver")]
 mod slg;
+pub(crate) mod stats;
 pub(crate) mod truncate;
 
+pub use budget::{BudgetExceeded, SolveBudget};
+pub use compare::{compare_solvers, SolverComparison};
+pub use dependencies::Dependency;
+pub use explain::Candidate;
+pub use failure::{FailureReason, FailureTree};
+pub use overflow::{Overflow, OverflowDisplay, OverflowLimit};
+pub use proof::{ProofStep, ProofTree};
+pub use stats::SolverStats;
+
 /// A (possible) solution for a proposed goal.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Solution<I: Interner> {
@@ -34,6 +53,13 @@ pub enum Solution<I: Interner> {
     Ambig(Guidance<I>),
 }
 
+/// The distinct substitutions behind an ambiguous solution, e.g. one for
+/// each impl that could apply; see `Solver::solve_with_candidates`.
+pub type AmbiguityCandidates<I> = Vec<Canonical<Substitution<I>>>;
+
+/// How many candidates `Solver::solve_with_candidates` returns at most.
+const MAX_AMBIGUITY_CANDIDATES: usize = 16;
+
 /// When a goal holds ambiguously (e.g., because there are multiple possible
 /// solutions), we issue a set of *guidance* back to type inference.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -50,6 +76,15 @@ pub enum Guidance<I: Interner> {
 
     /// There's no useful information to feed back to type inference
     Unknown,
+
+    /// There's no useful information to feed back to type inference,
+    /// because solving the goal required enumerating the solutions of a
+    /// goal that has too many of them, e.g. `?T: Sized`.
+    Floundered(FlounderedGoal<I>),
+
+    /// There's no useful information to feed back to type inference,
+    /// because the solver ran into one of its limits.
+    Overflow(Overflow<I>),
 }
 
 impl<I: Interner> Solution<I> {
@@ -60,6 +95,32 @@ impl<I: Interner> Solution<I> {
         }
     }
 
+    /// Sorts the lifetime constraints of a unique solution. The solvers may
+    /// produce the same constraints in different orders, so this makes
+    /// solutions comparable.
+    pub fn sort_constraints(&mut self) {
+        if let Solution::Unique(constrained) = self {
+            constrained
+                .value
+                .constraints
+                .sort_by_key(|constraint| format!("{:?}", constraint));
+        }
+    }
+
+    #[cfg(feature = "recursive-solver")]
+    fn from_recursive(solution: crate::recursive::lib::Solution<I>) -> Self {
+        match solution {
+            crate::recursive::lib::Solution::Unique(c) => Solution::Unique(c),
+            crate::recursive::lib::Solution::Ambig(g) => Solution::Ambig(match g {
+                crate::recursive::lib::Guidance::Definite(g) => Guidance::Definite(g),
+                crate::recursive::lib::Guidance::Suggested(g) => Guidance::Suggested(g),
+                crate::recursive::lib::Guidance::Unknown => Guidance::Unknown,
+                crate::recursive::lib::Guidance::Floundered(g) => Guidance::Floundered(g),
+                crate::recursive::lib::Guidance::Overflow(o) => Guidance::Overflow(o),
+            }),
+        }
+    }
+
     pub fn display<'a>(&'a self, interner: &'a I) -> SolutionDisplay<'a, I> {
         SolutionDisplay {
             solution: self,
@@ -89,6 +150,14 @@ impl<'a, I: Interner> fmt::Display for SolutionDisplay<'a, I> {
                 subst.display(interner)
             ),
             Solution::Ambig(Guidance::Unknown) => write!(f, "Ambiguous; no inference guidance"),
+            Solution::Ambig(Guidance::Floundered(floundered_goal)) => {
+                write!(f, "Ambiguous; no inference guidance; {}", floundered_goal)
+            }
+            Solution::Ambig(Guidance::Overflow(overflow)) => write!(
+                f,
+                "Ambiguous; no inference guidance; {}",
+                overflow.display(interner)
+            ),
         }
     }
 }
@@ -141,7 +210,7 @@ impl SolverChoice {
             SolverChoice::SLG {
                 max_size,
                 expected_answers,
-            } => Solver(SolverImpl::Slg {
+            } => Solver::new(SolverImpl::Slg {
                 forest: Box::new(Forest::new()),
                 max_size,
                 expected_answers,
@@ -150,7 +219,7 @@ impl SolverChoice {
             SolverChoice::Recursive {
                 overflow_depth,
                 caching_enabled,
-            } => Solver(SolverImpl::Recursive(Box::new(RecursiveContext::new(
+            } => Solver::new(SolverImpl::Recursive(Box::new(RecursiveContext::new(
                 overflow_depth,
                 caching_enabled,
             )))),
@@ -176,7 +245,18 @@ impl Default for SolverChoice {
 /// out what sets of types implement which traits. Also, between
 /// queries, this struct stores the cached state from previous solver
 /// attempts, which can then be re-used later.
-pub struct Solver<I: Interner>(SolverImpl<I>);
+pub struct Solver<I: Interner> {
+    solver_impl: SolverImpl<I>,
+
+    /// The statistics of all solves so far.
+    stats: SolverStats,
+
+    /// The statistics of the most recent solve.
+    last_solve_stats: SolverStats,
+
+    /// Whether solves collect statistics; see `set_collect_stats`.
+    collect_stats: bool,
+}
 
 enum SolverImpl<I: Interner> {
     #[cfg(feature = "slg-solver")]
@@ -194,6 +274,64 @@ enum SolverImpl<I: Interner> {
 }
 
 impl<I: Interner> Solver<I> {
+    fn new(solver_impl: SolverImpl<I>) -> Self {
+        Solver {
+            solver_impl,
+            stats: SolverStats::default(),
+            last_solve_stats: SolverStats::default(),
+            collect_stats: false,
+        }
+    }
+
+    /// Sets whether later solves collect statistics, see `stats`. This is
+    /// off by default, since it costs some extra work in the hot paths of
+    /// both solvers.
+    pub fn set_collect_stats(&mut self, collect_stats: bool) {
+        self.collect_stats = collect_stats;
+    }
+
+    /// The statistics of all solves done by this solver so far, including
+    /// the ones done by `solve_with_proof`, `explain_failure` etc. Only
+    /// solves done while `set_collect_stats` was on count.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    /// The statistics of the most recent solve.
+    pub fn last_solve_stats(&self) -> &SolverStats {
+        &self.last_solve_stats
+    }
+
+    /// Runs `op`, recording the statistics of the work it does if we
+    /// collect statistics. Nested calls, like the solves that
+    /// `explain_failure` does to build its explanation, count towards the
+    /// outermost call.
+    fn with_stats<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R {
+        if !self.collect_stats {
+            return op(self);
+        }
+        let (result, stats) = stats::collect(|| op(self));
+        if let Some(stats) = stats {
+            self.stats.add(&stats);
+            self.last_solve_stats = stats;
+        }
+        result
+    }
+
+    /// Prepares the solver for solving goals against a changed program, in
+    /// which the items for which `changed` returns true may differ. The SLG
+    /// solver keeps the completed tables that do not depend on any of those
+    /// items, so later solves can reuse their answers; the recursive solver
+    /// forgets everything it has cached.
+    pub fn invalidate(&mut self, changed: impl std::ops::Fn(&Dependency<I>) -> bool) {
+        match &mut self.solver_impl {
+            #[cfg(feature = "slg-solver")]
+            SolverImpl::Slg { forest, .. } => forest.invalidate(changed),
+            #[cfg(feature = "recursive-solver")]
+            SolverImpl::Recursive(ctx) => ctx.clear_cache(),
+        }
+    }
+
     /// Attempts to solve the given goal, which must be in canonical
     /// form. Returns a unique solution (if one exists).  This will do
     /// only as much work towards `goal` as it has to (and that work
@@ -204,7 +342,7 @@ impl<I: Interner> Solver<I> {
     /// - `program` -- defines the program clauses in scope.
     ///   - **Important:** You must supply the same set of program clauses
     ///     each time you invoke `solve`, as otherwise the cached data may be
-    ///     invalid.
+    ///     invalid. If the program changes, call `invalidate` first.
     /// - `goal` the goal to solve
     ///
     /// # Returns
@@ -217,7 +355,7 @@ impl<I: Interner> Solver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<Solution<I>> {
-        match &mut self.0 {
+        self.with_stats(|solver| match &mut solver.solver_impl {
             #[cfg(feature = "slg-solver")]
             SolverImpl::Slg {
                 forest,
@@ -228,30 +366,117 @@ impl<I: Interner> Solver<I> {
                 ops.make_solution(goal, forest.iter_answers(&ops, goal), || true)
             }
             #[cfg(feature = "recursive-solver")]
+            SolverImpl::Recursive(ctx) => ctx
+                .solver(program)
+                .solve_root_goal(goal)
+                .ok()
+                .map(Solution::from_recursive),
+        })
+    }
+
+    /// Like `solve`, but if the goal has a unique solution, also returns
+    /// a proof tree explaining why it holds: which clauses and impls were
+    /// used to prove it and its subgoals.
+    ///
+    /// The proof tree is recorded while solving, in the order in which the
+    /// solver proved the subgoals. The first call makes the solver record
+    /// proofs from then on, which costs some extra work on every later
+    /// solve, and drops everything cached so far.
+    pub fn solve_with_proof(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<(Solution<I>, Option<ProofTree<I>>)> {
+        self.with_stats(|solver| {
+            match &mut solver.solver_impl {
+                #[cfg(feature = "slg-solver")]
+                SolverImpl::Slg { forest, .. } => forest.record_derivations(),
+                #[cfg(feature = "recursive-solver")]
+                SolverImpl::Recursive(ctx) => ctx.record_proofs(),
+            }
+            let solution = solver.solve(program, goal)?;
+            if !solution.is_unique() {
+                return Some((solution, None));
+            }
+            let proof = match &solver.solver_impl {
+                #[cfg(feature = "slg-solver")]
+                SolverImpl::Slg { forest, .. } => proof::slg_proof(program, forest, goal),
+                #[cfg(feature = "recursive-solver")]
+                SolverImpl::Recursive(ctx) => Some(proof::recursive_proof(program, ctx, goal)),
+            };
+            Some((solution, proof))
+        })
+    }
+
+    /// Like `solve`, but if the goal is ambiguous, also returns the distinct
+    /// candidate substitutions behind the ambiguity, e.g. one for each impl
+    /// that could apply. For the SLG solver, these are the answers that
+    /// were merged into the guidance; for the recursive solver, they are
+    /// the solutions of the clauses and obligations that it could not
+    /// choose between. For unique solutions, no candidates are returned;
+    /// otherwise, at most 16 are.
+    pub fn solve_with_candidates(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<(Solution<I>, AmbiguityCandidates<I>)> {
+        self.with_stats(|solver| match &mut solver.solver_impl {
+            #[cfg(feature = "slg-solver")]
+            SolverImpl::Slg {
+                forest,
+                max_size,
+                expected_answers,
+            } => {
+                let ops = SlgContextOps::new(program, *max_size, *expected_answers);
+                let mut candidates = vec![];
+                let solution = ops.make_solution_with_candidates(
+                    goal,
+                    forest.iter_answers(&ops, goal),
+                    || true,
+                    &mut candidates,
+                    MAX_AMBIGUITY_CANDIDATES,
+                )?;
+                if solution.is_unique() {
+                    candidates.clear();
+                }
+                Some((solution, candidates))
+            }
+            #[cfg(feature = "recursive-solver")]
             SolverImpl::Recursive(ctx) => {
-                ctx.solver(program)
-                    .solve_root_goal(goal)
-                    .ok()
-                    .map(|s| match s {
-                        crate::recursive::lib::Solution::Unique(c) => {
-                            crate::solve::Solution::Unique(c)
-                        }
-                        crate::recursive::lib::Solution::Ambig(g) => {
-                            crate::solve::Solution::Ambig(match g {
-                                crate::recursive::lib::Guidance::Definite(g) => {
-                                    crate::solve::Guidance::Definite(g)
-                                }
-                                crate::recursive::lib::Guidance::Suggested(g) => {
-                                    crate::solve::Guidance::Suggested(g)
-                                }
-                                crate::recursive::lib::Guidance::Unknown => {
-                                    crate::solve::Guidance::Unknown
-                                }
-                            })
-                        }
-                    })
+                let interner = program.interner();
+                let mut solver = ctx.solver(program);
+                let solution = solver.solve_root_goal(goal).ok()?;
+                let candidates = if solution.is_ambig() {
+                    solver
+                        .root_goal_candidates(goal)
+                        .into_iter()
+                        .take(MAX_AMBIGUITY_CANDIDATES)
+                        .map(|candidate| candidate.map(interner, |cs| cs.subst))
+                        .collect()
+                } else {
+                    vec![]
+                };
+                Some((Solution::from_recursive(solution), candidates))
             }
-        }
+        })
+    }
+
+    /// If the goal has no solution, returns an explanation of why: the
+    /// nearest candidate clause for each goal that failed, down to the
+    /// leaf obligations that could not be proven. Returns `None` if the
+    /// goal has a (possibly ambiguous) solution.
+    ///
+    /// The explanation is reconstructed by solving the subgoals one at a
+    /// time, so this is more expensive than `solve`.
+    pub fn explain_failure(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<FailureTree<I>> {
+        self.with_stats(|solver| match solver.solve(program, goal) {
+            Some(_) => None,
+            None => Some(failure::explain_failure(solver, program, goal)),
+        })
     }
 
     /// Attempts to solve the given goal, which must be in canonical
@@ -265,11 +490,13 @@ impl<I: Interner> Solver<I> {
     /// - `program` -- defines the program clauses in scope.
     ///   - **Important:** You must supply the same set of program clauses
     ///     each time you invoke `solve`, as otherwise the cached data may be
-    ///     invalid.
+    ///     invalid. If the program changes, call `invalidate` first.
     /// - `goal` the goal to solve
     /// - `should_continue` if `false` is returned, the no further solving
     ///   will be done. A `Guidance(Suggested(...))` will be returned a
     ///   `Solution`, using any answers that were generated up to that point.
+    ///   The recursive solver has no such answers, so it returns
+    ///   `Guidance::Unknown` instead.
     ///
     /// # Returns
     ///
@@ -282,7 +509,7 @@ impl<I: Interner> Solver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         should_continue: impl std::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
-        match &mut self.0 {
+        self.with_stats(|solver| match &mut solver.solver_impl {
             #[cfg(feature = "slg-solver")]
             SolverImpl::Slg {
                 forest,
@@ -294,30 +521,62 @@ impl<I: Interner> Solver<I> {
             }
             #[cfg(feature = "recursive-solver")]
             SolverImpl::Recursive(ctx) => {
-                // TODO support should_continue in recursive solver
-                ctx.solver(program)
+                let budget = BudgetTracker::new(SolveBudget::default(), Some(&should_continue));
+                let solution = ctx
+                    .solver_with_budget(program, &budget)
                     .solve_root_goal(goal)
                     .ok()
-                    .map(|s| match s {
-                        crate::recursive::lib::Solution::Unique(c) => {
-                            crate::solve::Solution::Unique(c)
-                        }
-                        crate::recursive::lib::Solution::Ambig(g) => {
-                            crate::solve::Solution::Ambig(match g {
-                                crate::recursive::lib::Guidance::Definite(g) => {
-                                    crate::solve::Guidance::Definite(g)
-                                }
-                                crate::recursive::lib::Guidance::Suggested(g) => {
-                                    crate::solve::Guidance::Suggested(g)
-                                }
-                                crate::recursive::lib::Guidance::Unknown => {
-                                    crate::solve::Guidance::Unknown
-                                }
-                            })
-                        }
-                    })
+                    .map(Solution::from_recursive);
+                match budget.exceeded() {
+                    Some(_) => Some(Solution::Ambig(Guidance::Unknown)),
+                    None => solution,
+                }
             }
-        }
+        })
+    }
+
+    /// Like `solve`, but gives up once solving the goal has used up
+    /// `budget`, returning the limit that was exceeded. Work that was done
+    /// before giving up is kept as far as it is still valid, so a later
+    /// attempt at the same goal can pick up where this one stopped.
+    ///
+    /// # Returns
+    ///
+    /// - `Err(exceeded)` if the budget ran out before the goal was solved.
+    /// - `Ok(None)` is the goal cannot be proven.
+    /// - `Ok(Some(solution))` if we succeeded in finding *some* answers,
+    ///   although `solution` may reflect ambiguity and unknowns.
+    pub fn solve_with_budget(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        budget: SolveBudget,
+    ) -> Result<Option<Solution<I>>, BudgetExceeded> {
+        self.with_stats(|solver| {
+            let budget = BudgetTracker::new(budget, None);
+            let solution = match &mut solver.solver_impl {
+                #[cfg(feature = "slg-solver")]
+                SolverImpl::Slg {
+                    forest,
+                    max_size,
+                    expected_answers,
+                } => {
+                    let ops = SlgContextOps::new(program, *max_size, *expected_answers)
+                        .with_budget(&budget);
+                    ops.make_solution(goal, forest.iter_answers(&ops, goal), || true)
+                }
+                #[cfg(feature = "recursive-solver")]
+                SolverImpl::Recursive(ctx) => ctx
+                    .solver_with_budget(program, &budget)
+                    .solve_root_goal(goal)
+                    .ok()
+                    .map(Solution::from_recursive),
+            };
+            match budget.exceeded() {
+                Some(exceeded) => Err(exceeded),
+                None => Ok(solution),
+            }
+        })
     }
 
     /// Attempts to solve the given goal, which must be in canonical
@@ -330,7 +589,7 @@ impl<I: Interner> Solver<I> {
     /// - `program` -- defines the program clauses in scope.
     ///   - **Important:** You must supply the same set of program clauses
     ///     each time you invoke `solve`, as otherwise the cached data may be
-    ///     invalid.
+    ///     invalid. If the program changes, call `invalidate` first.
     /// - `goal` the goal to solve
     /// - `f` -- function to proceed solution. New solutions will be generated
     /// while function returns `true`.
@@ -349,7 +608,7 @@ impl<I: Interner> Solver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         mut f: impl FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        match &mut self.0 {
+        self.with_stats(|solver| match &mut solver.solver_impl {
             SolverImpl::Slg {
                 forest,
                 max_size,
@@ -370,7 +629,7 @@ impl<I: Interner> Solver<I> {
                                 }
                             }
                         }
-                        AnswerResult::Floundered => SubstitutionResult::Floundered,
+                        AnswerResult::Floundered(_) => SubstitutionResult::Floundered,
                         AnswerResult::NoMoreSolutions => {
                             return true;
                         }
@@ -384,7 +643,7 @@ impl<I: Interner> Solver<I> {
             }
             #[cfg(feature = "recursive-solver")]
             SolverImpl::Recursive(_ctx) => unimplemented!(),
-        }
+        })
     }
 }
 
@@ -0,0 +1,154 @@
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Limits on how much work a single solve may do; see
+/// `Solver::solve_with_budget`. Limits that are `None` are not enforced.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveBudget {
+    /// How many strands (for the SLG solver) or obligations (for the
+    /// recursive solver) may be pursued.
+    pub max_strands: Option<usize>,
+
+    /// How many tables (for the SLG solver) or new goals (for the recursive
+    /// solver) may be created.
+    pub max_tables: Option<usize>,
+
+    /// How many program clauses may be instantiated.
+    pub max_clause_instantiations: Option<usize>,
+
+    /// When solving has to stop.
+    pub deadline: Option<Instant>,
+}
+
+impl SolveBudget {
+    /// A budget that only limits how long solving may take, starting now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        SolveBudget {
+            deadline: Some(Instant::now() + timeout),
+            ..SolveBudget::default()
+        }
+    }
+}
+
+/// The limit of a `SolveBudget` that made solving stop early.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BudgetExceeded {
+    Strands,
+    Tables,
+    ClauseInstantiations,
+    Deadline,
+
+    /// The caller asked to stop, e.g. through the `should_continue`
+    /// callback of `Solver::solve_limited`.
+    Cancelled,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::Strands => write!(f, "exceeded the maximum number of strands"),
+            BudgetExceeded::Tables => write!(f, "exceeded the maximum number of tables"),
+            BudgetExceeded::ClauseInstantiations => {
+                write!(f, "exceeded the maximum number of clause instantiations")
+            }
+            BudgetExceeded::Deadline => write!(f, "exceeded the deadline"),
+            BudgetExceeded::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Tracks the work done by a single solve against its budget. Once a limit
+/// is exceeded, the tracker stays exhausted.
+pub(crate) struct BudgetTracker<'a> {
+    budget: SolveBudget,
+    should_continue: Option<&'a dyn Fn() -> bool>,
+    strands: Cell<usize>,
+    tables: Cell<usize>,
+    clause_instantiations: Cell<usize>,
+    exceeded: Cell<Option<BudgetExceeded>>,
+}
+
+impl<'a> BudgetTracker<'a> {
+    pub(crate) fn new(budget: SolveBudget, should_continue: Option<&'a dyn Fn() -> bool>) -> Self {
+        BudgetTracker {
+            budget,
+            should_continue,
+            strands: Cell::new(0),
+            tables: Cell::new(0),
+            clause_instantiations: Cell::new(0),
+            exceeded: Cell::new(None),
+        }
+    }
+
+    pub(crate) fn charge_strand(&self) {
+        self.charge(
+            &self.strands,
+            self.budget.max_strands,
+            BudgetExceeded::Strands,
+        );
+    }
+
+    pub(crate) fn charge_table(&self) {
+        self.charge(&self.tables, self.budget.max_tables, BudgetExceeded::Tables);
+    }
+
+    pub(crate) fn charge_clause_instantiation(&self) {
+        self.charge(
+            &self.clause_instantiations,
+            self.budget.max_clause_instantiations,
+            BudgetExceeded::ClauseInstantiations,
+        );
+    }
+
+    fn charge(&self, count: &Cell<usize>, limit: Option<usize>, exceeded: BudgetExceeded) {
+        count.set(count.get() + 1);
+        if matches!(limit, Some(limit) if count.get() > limit) {
+            self.exceed(exceeded);
+        }
+    }
+
+    fn exceed(&self, exceeded: BudgetExceeded) {
+        if self.exceeded.get().is_none() {
+            self.exceeded.set(Some(exceeded));
+        }
+    }
+
+    /// True once any limit has been exceeded, the deadline has passed or the
+    /// caller asked to stop.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        if self.exceeded.get().is_some() {
+            return true;
+        }
+        if let Some(deadline) = self.budget.deadline {
+            if Instant::now() >= deadline {
+                self.exceed(BudgetExceeded::Deadline);
+            }
+        }
+        if let Some(should_continue) = self.should_continue {
+            if !should_continue() {
+                self.exceed(BudgetExceeded::Cancelled);
+            }
+        }
+        self.exceeded.get().is_some()
+    }
+
+    /// The limit that was exceeded first, if any.
+    pub(crate) fn exceeded(&self) -> Option<BudgetExceeded> {
+        self.exceeded.get()
+    }
+}
+
+impl fmt::Debug for BudgetTracker<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetTracker")
+            .field("budget", &self.budget)
+            .field("strands", &self.strands.get())
+            .field("tables", &self.tables.get())
+            .field("clause_instantiations", &self.clause_instantiations.get())
+            .field("exceeded", &self.exceeded.get())
+            .finish()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::solve::{Guidance, Solution, SolverChoice};
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+
+/// The solutions that several solvers found for the same goal; see
+/// `compare_solvers`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolverComparison<I: Interner> {
+    /// Each solver, along with the solution it found, with its lifetime
+    /// constraints sorted by `Solution::sort_constraints`.
+    pub solutions: Vec<(SolverChoice, Option<Solution<I>>)>,
+
+    /// True if all the solvers found the same solution.
+    pub agree: bool,
+}
+
+/// Solves `goal` with a fresh solver for each of `solver_choices`, to find
+/// goals on which the solvers disagree, which points at a bug in one of
+/// them.
+///
+/// Two solutions are the same if they display the same once their lifetime
+/// constraints are sorted, since the solvers may produce those in different
+/// orders. Ambiguous solutions without inference guidance are the same,
+/// whether or not the solvers explain the lack of guidance the same way.
+pub fn compare_solvers<I: Interner>(
+    program: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+    solver_choices: &[SolverChoice],
+) -> SolverComparison<I> {
+    let interner = program.interner();
+    let solutions: Vec<_> = solver_choices
+        .iter()
+        .map(|&solver_choice| {
+            let mut solution = solver_choice.into_solver().solve(program, goal);
+            if let Some(solution) = &mut solution {
+                solution.sort_constraints();
+            }
+            (solver_choice, solution)
+        })
+        .collect();
+
+    let mut keys = solutions
+        .iter()
+        .map(|(_, solution)| comparison_key(solution.as_ref(), interner));
+    let agree = match keys.next() {
+        Some(first) => keys.all(|key| key == first),
+        None => true,
+    };
+    SolverComparison { solutions, agree }
+}
+
+/// What solutions are compared by; see `compare_solvers`.
+fn comparison_key<I: Interner>(solution: Option<&Solution<I>>, interner: &I) -> String {
+    match solution {
+        None => "No possible solution".to_string(),
+        Some(Solution::Ambig(Guidance::Floundered(_)))
+        | Some(Solution::Ambig(Guidance::Overflow(_))) => Solution::Ambig(Guidance::Unknown)
+            .display(interner)
+            .to_string(),
+        Some(solution) => solution.display(interner).to_string(),
+    }
+}
@@ -0,0 +1,15 @@
+use chalk_ir::interner::Interner;
+use chalk_ir::{ImplId, TraitId};
+
+/// An item of the program that the solver's cached work may depend on; see
+/// `Solver::invalidate`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dependency<I: Interner> {
+    /// The trait with the given id: its datum, its associated types and
+    /// which impls it has.
+    Trait(TraitId<I>),
+
+    /// The impl with the given id: its datum, including its where clauses,
+    /// and its associated type values.
+    Impl(ImplId<I>),
+}
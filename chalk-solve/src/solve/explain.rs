@@ -0,0 +1,323 @@
+//! Machinery shared by proof trees and failure explanations.
+//!
+//! Proof trees are recorded by the solvers, but there is nothing to record
+//! for goals that fail, so failure explanations are reconstructed after the
+//! fact, using the solver as an oracle: we break goals down into the
+//! clauses that could prove them, and ask the solver about their
+//! conditions one at a time. Since the solver caches its results, this is
+//! cheap compared to solving the goal in the first place.
+
+use super::{Solution, Solver};
+use crate::clauses::builder::ClauseBuilder;
+use crate::clauses::program_clauses::ToProgramClauses;
+use crate::clauses::program_clauses_for_goal;
+use crate::infer::ucanonicalize::UniverseMapExt;
+use crate::infer::{InferenceTable, ParameterEnaVariableExt};
+use crate::split::Split;
+use crate::RustIrDatabase;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+use std::fmt;
+
+/// The maximum depth of a reconstructed explanation; deeper goals are
+/// left unexplained.
+const MAX_EXPLANATION_DEPTH: usize = 32;
+
+/// A clause that could be used to prove a domain goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Candidate<I: Interner> {
+    /// One of the program clauses lowered from an impl (or from one of its
+    /// associated type values).
+    Impl(ImplId<I>, ProgramClause<I>),
+
+    /// A program clause that does not come from an impl, e.g. one derived
+    /// from a trait or type declaration, or a custom clause.
+    ProgramClause(ProgramClause<I>),
+
+    /// A clause from the environment, e.g. a where clause in scope.
+    Assumption(ProgramClause<I>),
+}
+
+impl<I: Interner> Candidate<I> {
+    pub fn clause(&self) -> &ProgramClause<I> {
+        match self {
+            Candidate::Impl(_, clause)
+            | Candidate::ProgramClause(clause)
+            | Candidate::Assumption(clause) => clause,
+        }
+    }
+}
+
+impl<I: Interner> fmt::Display for Candidate<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Candidate::Impl(impl_id, _) => write!(f, "`{}`", impl_id.display()),
+            Candidate::ProgramClause(clause) => write!(f, "program clause `{:?}`", clause),
+            Candidate::Assumption(clause) => write!(f, "assumption `{:?}`", clause),
+        }
+    }
+}
+
+/// Why `Explainer::enter` refused to explain a goal.
+pub(super) enum Stopped {
+    /// The goal is one of its own ancestors.
+    Cycle,
+
+    /// The explanation is already `MAX_EXPLANATION_DEPTH` goals deep.
+    TooDeep,
+}
+
+pub(super) struct Explainer<'s, 'db, I: Interner> {
+    solver: &'s mut Solver<I>,
+    pub(super) db: &'db dyn RustIrDatabase<I>,
+    pub(super) table: InferenceTable<I>,
+
+    /// The goals being explained, outermost first, used to detect cycles.
+    ancestors: Vec<Canonical<InEnvironment<Goal<I>>>>,
+}
+
+impl<'s, 'db, I: Interner> Explainer<'s, 'db, I> {
+    /// Instantiates `goal` in a fresh inference table, returning the
+    /// explainer along with the substitution for the canonical variables
+    /// and the instantiated goal.
+    pub(super) fn new(
+        solver: &'s mut Solver<I>,
+        db: &'db dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> (Self, Substitution<I>, InEnvironment<Goal<I>>) {
+        let (table, subst, goal) =
+            InferenceTable::from_canonical(db.interner(), goal.universes, &goal.canonical);
+        let explainer = Explainer {
+            solver,
+            db,
+            table,
+            ancestors: vec![],
+        };
+        (explainer, subst, goal)
+    }
+
+    /// Strips the quantifiers without any binders that wrap the conditions
+    /// of clauses lowered from where clauses; they are not worth showing.
+    pub(super) fn peel<'g>(&self, mut goal: &'g Goal<I>) -> &'g Goal<I> {
+        let interner = self.db.interner();
+        while let GoalData::Quantified(_, subgoal) = goal.data(interner) {
+            if !subgoal.binders.is_empty(interner) {
+                break;
+            }
+            goal = subgoal.skip_binders();
+        }
+        goal
+    }
+
+    /// Records that `goal` is being explained, unless it is one of its own
+    /// ancestors or the explanation got too deep. Every successful call
+    /// must be paired with a call to `exit`.
+    pub(super) fn enter(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &Goal<I>,
+    ) -> Result<(), Stopped> {
+        let interner = self.db.interner();
+        let canonical = self
+            .table
+            .canonicalize(interner, &InEnvironment::new(environment, goal.clone()))
+            .quantified;
+        if self.ancestors.contains(&canonical) {
+            return Err(Stopped::Cycle);
+        }
+        if self.ancestors.len() >= MAX_EXPLANATION_DEPTH {
+            return Err(Stopped::TooDeep);
+        }
+        self.ancestors.push(canonical);
+        Ok(())
+    }
+
+    pub(super) fn exit(&mut self) {
+        self.ancestors.pop();
+    }
+
+    /// Returns the clauses that could prove `goal`, trying assumptions from
+    /// the environment first. Both `environment` and `goal` must be fully
+    /// resolved.
+    pub(super) fn candidates(
+        &self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+    ) -> Result<Vec<Candidate<I>>, Floundered<I>> {
+        let interner = self.db.interner();
+        let clauses = program_clauses_for_goal(self.db, environment, goal)?;
+        let assumptions: FxHashSet<_> = self
+            .db
+            .program_clauses_for_env(environment)
+            .iter(interner)
+            .cloned()
+            .collect();
+        let impl_clauses = impl_clauses(self.db, goal);
+
+        let (assumptions, clauses): (Vec<_>, Vec<_>) = clauses
+            .into_iter()
+            .partition(|clause| assumptions.contains(clause));
+        let clauses = clauses.into_iter().map(|clause| {
+            match impl_clauses.iter().find(|(_, c)| *c == clause) {
+                Some(&(impl_id, _)) => Candidate::Impl(impl_id, clause),
+                None => Candidate::ProgramClause(clause),
+            }
+        });
+        Ok(assumptions
+            .into_iter()
+            .map(Candidate::Assumption)
+            .chain(clauses)
+            .collect())
+    }
+
+    /// Unifies `goal` with the consequence of `clause`, returning the
+    /// conditions of the clause (and any goals produced by unification),
+    /// or `None` if the clause does not apply to `goal`.
+    pub(super) fn apply_clause(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+    ) -> Option<Vec<Goal<I>>> {
+        let interner = self.db.interner();
+        let implication = match clause.data(interner) {
+            ProgramClauseData::Implies(implication) => implication.clone(),
+            ProgramClauseData::ForAll(implication) => self
+                .table
+                .instantiate_binders_existentially(interner, implication),
+        };
+        let unified = self
+            .table
+            .unify(interner, environment, goal, &implication.consequence)
+            .ok()?;
+        Some(
+            implication
+                .conditions
+                .iter(interner)
+                .cloned()
+                .chain(
+                    unified
+                        .goals
+                        .into_iter()
+                        .map(|goal| goal.goal.cast(interner)),
+                )
+                .collect(),
+        )
+    }
+
+    /// Asks the solver whether `goal` holds, returning `None` if it does
+    /// not. If it has a unique solution, the solution is applied to our
+    /// inference variables and `Some(true)` is returned; if it only holds
+    /// ambiguously, `Some(false)` is returned.
+    pub(super) fn constrain(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &Goal<I>,
+    ) -> Option<bool> {
+        let interner = self.db.interner();
+        let canonicalized = self
+            .table
+            .canonicalize(interner, &InEnvironment::new(environment, goal.clone()));
+        let u_canonicalized = self
+            .table
+            .u_canonicalize(interner, &canonicalized.quantified);
+        let solution = match self.solver.solve(self.db, &u_canonicalized.quantified)? {
+            Solution::Unique(solution) => solution,
+            Solution::Ambig(_) => return Some(false),
+        };
+        let solution = u_canonicalized
+            .universes
+            .map_from_canonical(interner, &solution);
+        let solution = self.table.instantiate_canonical(interner, &solution);
+        let empty_env = &Environment::new(interner);
+        for (var, value) in canonicalized
+            .free_vars
+            .iter()
+            .zip(solution.subst.iter(interner))
+        {
+            let var = var.to_generic_arg(interner);
+            if self.table.unify(interner, empty_env, &var, value).is_err() {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+}
+
+/// Tells whether `clause`, which proved `goal` in `environment`, is an
+/// assumption, comes from an impl or is some other program clause.
+pub(super) fn classify_clause<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    environment: &Environment<I>,
+    goal: &Goal<I>,
+    clause: &ProgramClause<I>,
+) -> Candidate<I> {
+    let interner = db.interner();
+    if db
+        .program_clauses_for_env(environment)
+        .iter(interner)
+        .any(|assumption| assumption == clause)
+    {
+        return Candidate::Assumption(clause.clone());
+    }
+    let impl_id = match goal.data(interner) {
+        GoalData::DomainGoal(goal) => impl_clauses(db, goal)
+            .into_iter()
+            .find(|(_, c)| c == clause)
+            .map(|(impl_id, _)| impl_id),
+        _ => None,
+    };
+    match impl_id {
+        Some(impl_id) => Candidate::Impl(impl_id, clause.clone()),
+        None => Candidate::ProgramClause(clause.clone()),
+    }
+}
+
+/// Returns the program clauses of the impls that could prove `goal`,
+/// along with the impl each one comes from.
+fn impl_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    goal: &DomainGoal<I>,
+) -> Vec<(ImplId<I>, ProgramClause<I>)> {
+    let interner = db.interner();
+    let (trait_ref, assoc_ty_id) = match goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => (trait_ref.clone(), None),
+        DomainGoal::Holds(WhereClause::AliasEq(AliasEq {
+            alias: AliasTy::Projection(projection),
+            ..
+        }))
+        | DomainGoal::Normalize(Normalize {
+            alias: AliasTy::Projection(projection),
+            ..
+        }) => (
+            db.trait_ref_from_projection(projection),
+            Some(projection.associated_ty_id),
+        ),
+        _ => return vec![],
+    };
+
+    let mut impl_clauses = vec![];
+    for impl_id in db.impls_for_trait(
+        trait_ref.trait_id,
+        trait_ref.substitution.parameters(interner),
+    ) {
+        let impl_datum = db.impl_datum(impl_id);
+        let mut clauses = vec![];
+        let builder = &mut ClauseBuilder::new(db, &mut clauses);
+        match assoc_ty_id {
+            None => impl_datum.to_program_clauses(builder),
+            Some(assoc_ty_id) => {
+                for &value_id in &impl_datum.associated_ty_value_ids {
+                    let value = db.associated_ty_value(value_id);
+                    if value.associated_ty_id == assoc_ty_id {
+                        value.to_program_clauses(builder);
+                    }
+                }
+            }
+        }
+        impl_clauses.extend(clauses.into_iter().map(|clause| (impl_id, clause)));
+    }
+    impl_clauses
+}
@@ -0,0 +1,366 @@
+//! Failure explanations: why a goal does not hold.
+//!
+//! For each domain goal, we look for the "nearest" candidate clause: the
+//! one that applies to the goal and has the fewest conditions that the
+//! solver cannot prove on their own. We then recurse into those
+//! conditions, so that the leaves of the explanation are the obligations
+//! that could not be proven.
+
+use super::explain::{Candidate, Explainer};
+use super::Solver;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt;
+
+/// An explanation of why a goal does not hold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailureTree<I: Interner> {
+    /// The goal that could not be proven, with the inference variables
+    /// determined while explaining it substituted in.
+    pub goal: Goal<I>,
+    pub reason: FailureReason<I>,
+}
+
+/// Why a single goal in a failure tree could not be proven.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FailureReason<I: Interner> {
+    /// No impl or other clause applies to the goal.
+    NoCandidates,
+
+    /// Some clauses apply to the goal, but none of them could be used to
+    /// prove it. `candidate` is the nearest one, and `failures` explain the
+    /// conditions of it that could not be proven. If `failures` is empty,
+    /// each condition can be proven on its own, but not all of them at
+    /// once.
+    CandidateFailed {
+        candidate: Candidate<I>,
+        failures: Vec<FailureTree<I>>,
+    },
+
+    /// A `forall`, `exists`, `if` or conjunction goal fails because these
+    /// of its subgoals fail. If empty, each subgoal can be proven on its
+    /// own, but not all of them at once.
+    Subgoals(Vec<FailureTree<I>>),
+
+    /// The two sides of an equality or subtyping goal do not unify.
+    Unification,
+
+    /// `not { G }` fails because `G` can be proven.
+    NegationHolds,
+
+    /// The goal is one of its own ancestors, the explanation got too deep,
+    /// or the goal could not be broken down any further.
+    Unexplained,
+}
+
+impl<I: Interner> FailureTree<I> {
+    /// Returns the leaf obligations of the explanation: the goals that
+    /// could not be proven, without the goals that only failed because
+    /// of them.
+    pub fn leaves(&self) -> Vec<&FailureTree<I>> {
+        let mut leaves = vec![];
+        self.push_leaves(&mut leaves);
+        leaves
+    }
+
+    fn push_leaves<'a>(&'a self, leaves: &mut Vec<&'a FailureTree<I>>) {
+        let children = match &self.reason {
+            FailureReason::CandidateFailed { failures, .. } => failures,
+            FailureReason::Subgoals(failures) => failures,
+            _ => {
+                leaves.push(self);
+                return;
+            }
+        };
+        if children.is_empty() {
+            leaves.push(self);
+        }
+        for child in children {
+            child.push_leaves(leaves);
+        }
+    }
+
+    pub fn display<'a>(&'a self, interner: &'a I) -> FailureTreeDisplay<'a, I> {
+        FailureTreeDisplay {
+            failure: self,
+            interner,
+        }
+    }
+}
+
+/// Renders a failure tree with one goal per line, indenting the failures
+/// that explain each goal below it.
+pub struct FailureTreeDisplay<'a, I: Interner> {
+    failure: &'a FailureTree<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> FailureTreeDisplay<'a, I> {
+    fn fmt_at(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        failure: &FailureTree<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        write!(f, "{}", "    ".repeat(depth))?;
+        match failure.goal.data(self.interner) {
+            GoalData::DomainGoal(DomainGoal::Holds(WhereClause::Implemented(trait_ref))) => write!(
+                f,
+                "no impl of `{:?}` matches `{:?}`",
+                trait_ref.trait_id,
+                trait_ref.self_type_parameter(self.interner)
+            )?,
+            _ => write!(f, "`{:?}` cannot be proven", failure.goal)?,
+        }
+
+        let children = match &failure.reason {
+            FailureReason::NoCandidates => match failure.goal.data(self.interner) {
+                GoalData::DomainGoal(DomainGoal::Holds(WhereClause::Implemented(_))) => {
+                    return Ok(())
+                }
+                _ => return write!(f, ": no clause applies"),
+            },
+            FailureReason::CandidateFailed {
+                candidate,
+                failures,
+            } => {
+                write!(f, "; nearest candidate {} failed", candidate)?;
+                if failures.is_empty() {
+                    write!(f, ": its conditions cannot all hold at once")?;
+                } else {
+                    write!(f, " on ")?;
+                    for (i, failure) in failures.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "`{:?}`", failure.goal)?;
+                    }
+                }
+                failures
+            }
+            FailureReason::Subgoals(failures) => {
+                if failures.is_empty() {
+                    write!(f, ": its subgoals cannot all hold at once")?;
+                }
+                failures
+            }
+            FailureReason::Unification => return write!(f, ": the types do not unify"),
+            FailureReason::NegationHolds => return write!(f, ": the negated goal holds"),
+            FailureReason::Unexplained => return Ok(()),
+        };
+        for child in children {
+            writeln!(f)?;
+            self.fmt_at(f, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for FailureTreeDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, self.failure, 0)
+    }
+}
+
+/// Explains why `goal`, which the solver found no solution for, does not
+/// hold.
+pub(super) fn explain_failure<I: Interner>(
+    solver: &mut Solver<I>,
+    db: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> FailureTree<I> {
+    let (explainer, _, InEnvironment { environment, goal }) = Explainer::new(solver, db, goal);
+    let mut builder = FailureBuilder { explainer };
+    let mut failure = builder.explain(&environment, &goal);
+    builder.resolve(&mut failure);
+    failure
+}
+
+struct FailureBuilder<'s, 'db, I: Interner> {
+    explainer: Explainer<'s, 'db, I>,
+}
+
+impl<I: Interner> FailureBuilder<'_, '_, I> {
+    fn explain(&mut self, environment: &Environment<I>, goal: &Goal<I>) -> FailureTree<I> {
+        let goal = self.explainer.peel(goal);
+        let reason = match self.explainer.enter(environment, goal) {
+            Ok(()) => {
+                let reason = self.explain_goal(environment, goal);
+                self.explainer.exit();
+                reason
+            }
+            Err(_) => FailureReason::Unexplained,
+        };
+        FailureTree {
+            goal: goal.clone(),
+            reason,
+        }
+    }
+
+    fn explain_goal(&mut self, environment: &Environment<I>, goal: &Goal<I>) -> FailureReason<I> {
+        let interner = self.explainer.db.interner();
+        match goal.data(interner) {
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let subgoal = self
+                    .explainer
+                    .table
+                    .instantiate_binders_universally(interner, subgoal);
+                FailureReason::Subgoals(vec![self.explain(environment, &subgoal)])
+            }
+            GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                let subgoal = self
+                    .explainer
+                    .table
+                    .instantiate_binders_existentially(interner, subgoal);
+                FailureReason::Subgoals(vec![self.explain(environment, &subgoal)])
+            }
+            GoalData::Implies(clauses, subgoal) => {
+                let environment = environment
+                    .clone()
+                    .add_clauses(interner, clauses.iter(interner).cloned());
+                FailureReason::Subgoals(vec![self.explain(&environment, subgoal)])
+            }
+            GoalData::All(goals) => {
+                let goals: Vec<_> = goals.iter(interner).cloned().collect();
+                let failed = self.failed_goals(environment, &goals);
+                FailureReason::Subgoals(
+                    failed
+                        .iter()
+                        .map(|goal| self.explain(environment, goal))
+                        .collect(),
+                )
+            }
+            GoalData::Not(_) => FailureReason::NegationHolds,
+            GoalData::EqGoal(_) | GoalData::SubtypeGoal(_) => FailureReason::Unification,
+            GoalData::DomainGoal(domain_goal) => self.explain_domain_goal(environment, domain_goal),
+            GoalData::CannotProve(()) => FailureReason::Unexplained,
+        }
+    }
+
+    /// Explains a failed domain goal through its nearest candidate clause.
+    fn explain_domain_goal(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+    ) -> FailureReason<I> {
+        let interner = self.explainer.db.interner();
+        let environment = &self.explainer.table.normalize_deep(interner, environment);
+        let goal = &self.explainer.table.normalize_deep(interner, goal);
+        let candidates = match self.explainer.candidates(environment, goal) {
+            Ok(candidates) => candidates,
+            Err(Floundered { .. }) => return FailureReason::Unexplained,
+        };
+
+        // Rank the candidates that apply by the number of their conditions
+        // that fail on their own. A candidate whose conditions only fail
+        // together is ranked last, since we can't point at any of them.
+        // Ties go to impls, since the other clauses (e.g. the one proving
+        // `Implemented` from `FromEnv`) are rarely what the user meant.
+        let mut nearest: Option<((usize, bool), Candidate<I>)> = None;
+        for candidate in candidates {
+            let snapshot = self.explainer.table.snapshot();
+            let failed = self.failed_conditions(environment, goal, candidate.clause());
+            self.explainer.table.rollback_to(snapshot);
+            let failed = match failed {
+                Some(failed) if self.only_contextual_failures(&candidate, &failed) => continue,
+                Some(failed) if failed.is_empty() => usize::MAX,
+                Some(failed) => failed.len(),
+                None => continue,
+            };
+            let rank = (failed, !matches!(candidate, Candidate::Impl(..)));
+            let is_nearer = match &nearest {
+                Some((best, _)) => rank < *best,
+                None => true,
+            };
+            if is_nearer {
+                nearest = Some((rank, candidate));
+            }
+        }
+
+        let candidate = match nearest {
+            Some((_, candidate)) => candidate,
+            None => return FailureReason::NoCandidates,
+        };
+        let failed = self
+            .failed_conditions(environment, goal, candidate.clause())
+            .expect("nearest candidate no longer applies");
+        let failures = failed
+            .iter()
+            .map(|condition| self.explain(environment, condition))
+            .collect();
+        FailureReason::CandidateFailed {
+            candidate,
+            failures,
+        }
+    }
+
+    /// True if `candidate` is a clause like `Implemented(T: Trait) :-
+    /// FromEnv(T: Trait)` that only failed because of conditions that
+    /// hold in particular contexts: assumptions that are not in scope, or
+    /// the `Compatible` modality used by coherence. Such clauses apply to
+    /// every goal, so they make for useless "nearest candidates".
+    fn only_contextual_failures(&self, candidate: &Candidate<I>, failed: &[Goal<I>]) -> bool {
+        let interner = self.explainer.db.interner();
+        let is_domain_goal = |goal: &Goal<I>, expected: fn(&DomainGoal<I>) -> bool| match self
+            .explainer
+            .peel(goal)
+            .data(interner)
+        {
+            GoalData::DomainGoal(domain_goal) => expected(domain_goal),
+            _ => false,
+        };
+        !matches!(candidate, Candidate::Impl(..))
+            && !failed.is_empty()
+            && (failed
+                .iter()
+                .all(|goal| is_domain_goal(goal, |goal| matches!(goal, DomainGoal::FromEnv(_))))
+                || failed.iter().any(|goal| {
+                    is_domain_goal(goal, |goal| matches!(goal, DomainGoal::Compatible(())))
+                }))
+    }
+
+    /// Applies `clause` to `goal`, returning the conditions of the clause
+    /// that fail, or `None` if the clause does not apply.
+    fn failed_conditions(
+        &mut self,
+        environment: &Environment<I>,
+        goal: &DomainGoal<I>,
+        clause: &ProgramClause<I>,
+    ) -> Option<Vec<Goal<I>>> {
+        let conditions = self.explainer.apply_clause(environment, goal, clause)?;
+        Some(self.failed_goals(environment, &conditions))
+    }
+
+    /// Returns the goals that the solver cannot prove on their own. The
+    /// unique solutions of the goals that can be proven are applied first,
+    /// so that the failed goals are as specific as possible.
+    fn failed_goals(&mut self, environment: &Environment<I>, goals: &[Goal<I>]) -> Vec<Goal<I>> {
+        let interner = self.explainer.db.interner();
+        let mut failed = vec![];
+        for goal in goals {
+            if self.explainer.constrain(environment, goal).is_none() {
+                failed.push(goal);
+            }
+        }
+        failed
+            .into_iter()
+            .map(|goal| self.explainer.table.normalize_deep(interner, goal))
+            .collect()
+    }
+
+    /// Substitutes the final values of inference variables into the goals
+    /// of the failure tree.
+    fn resolve(&mut self, failure: &mut FailureTree<I>) {
+        let interner = self.explainer.db.interner();
+        failure.goal = self.explainer.table.normalize_deep(interner, &failure.goal);
+        match &mut failure.reason {
+            FailureReason::CandidateFailed { failures, .. } | FailureReason::Subgoals(failures) => {
+                for failure in failures {
+                    self.resolve(failure);
+                }
+            }
+            _ => {}
+        }
+    }
+}
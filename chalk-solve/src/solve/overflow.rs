@@ -0,0 +1,135 @@
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+use std::fmt;
+
+/// How many goals of an overflow chain are shown at each end; the goals in
+/// between are elided.
+const SHOWN_CHAIN_ENDS: usize = 3;
+
+/// A goal that could not be solved because the solver ran into one of its
+/// limits, e.g. because a recursive impl like `impl<T: Foo> Foo for Box<T>`
+/// kept producing ever larger goals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overflow<I: Interner> {
+    /// The chain of goals that led to the overflow, outermost first. The
+    /// last goal is the one that exceeded the limit (or whose answer did).
+    pub goals: Vec<Canonical<InEnvironment<Goal<I>>>>,
+
+    /// The limit that was exceeded.
+    pub limit: OverflowLimit,
+}
+
+/// A limit that keeps the solver from running forever.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OverflowLimit {
+    /// Goals and answers may be at most this large; see `SolverChoice::SLG`.
+    MaxSize(usize),
+
+    /// Goals may be nested at most this deep; see
+    /// `SolverChoice::Recursive`.
+    OverflowDepth(usize),
+}
+
+/// The part of a goal that stays the same as a recursive impl applies
+/// itself over and over to larger and larger types.
+#[derive(PartialEq, Eq, Hash)]
+enum Predicate<I: Interner> {
+    Trait(TraitId<I>),
+    AssociatedTy(AssocTypeId<I>),
+}
+
+impl<I: Interner> Overflow<I> {
+    /// True if the same trait or associated type shows up more than once
+    /// in the chain of goals, which suggests unbounded recursion rather
+    /// than a limit that is merely too low.
+    pub fn is_recursive(&self, interner: &I) -> bool {
+        let mut seen = FxHashSet::default();
+        self.goals
+            .iter()
+            .filter_map(|goal| predicate(interner, &goal.value.goal))
+            .any(|predicate| !seen.insert(predicate))
+    }
+
+    pub fn display<'a>(&'a self, interner: &'a I) -> OverflowDisplay<'a, I> {
+        OverflowDisplay {
+            overflow: self,
+            interner,
+        }
+    }
+}
+
+fn predicate<I: Interner>(interner: &I, goal: &Goal<I>) -> Option<Predicate<I>> {
+    let domain_goal = match goal.data(interner) {
+        GoalData::DomainGoal(domain_goal) => domain_goal,
+        _ => return None,
+    };
+    match domain_goal {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
+            Some(Predicate::Trait(trait_ref.trait_id))
+        }
+        DomainGoal::Holds(WhereClause::AliasEq(AliasEq {
+            alias: AliasTy::Projection(projection),
+            ..
+        }))
+        | DomainGoal::Normalize(Normalize {
+            alias: AliasTy::Projection(projection),
+            ..
+        }) => Some(Predicate::AssociatedTy(projection.associated_ty_id)),
+        _ => None,
+    }
+}
+
+pub struct OverflowDisplay<'a, I: Interner> {
+    overflow: &'a Overflow<I>,
+    interner: &'a I,
+}
+
+impl<'a, I: Interner> fmt::Display for OverflowDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let OverflowDisplay { overflow, interner } = self;
+        let (last, chain) = match overflow.goals.split_last() {
+            Some(split) => split,
+            None => return write!(f, "overflow"),
+        };
+
+        write!(f, "overflow: `{:?}` exceeded ", last.value.goal)?;
+        match overflow.limit {
+            OverflowLimit::MaxSize(max_size) => write!(f, "the maximum size of {}", max_size)?,
+            OverflowLimit::OverflowDepth(depth) => write!(f, "the overflow depth of {}", depth)?,
+        }
+
+        if !chain.is_empty() {
+            write!(f, " via ")?;
+            for (i, goal) in chain.iter().enumerate() {
+                if chain.len() > 2 * SHOWN_CHAIN_ENDS
+                    && i >= SHOWN_CHAIN_ENDS
+                    && i < chain.len() - SHOWN_CHAIN_ENDS
+                {
+                    if i == SHOWN_CHAIN_ENDS {
+                        write!(f, " -> ...")?;
+                    }
+                    continue;
+                }
+                if i > 0 {
+                    write!(f, " -> ")?;
+                }
+                write!(f, "`{:?}`", goal.value.goal)?;
+            }
+        }
+
+        if overflow.is_recursive(interner) {
+            write!(
+                f,
+                "; the same predicate keeps recurring on larger types, \
+                 so raising the limit is unlikely to help"
+            )
+        } else {
+            let limit = match overflow.limit {
+                OverflowLimit::MaxSize(max_size) => max_size,
+                OverflowLimit::OverflowDepth(depth) => depth,
+            };
+            write!(f, "; try raising the limit above {}", limit)
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Proof trees: explanations of why a goal holds.
+//!
+//! While solving, both solvers can record which clause proved each goal
+//! and which solutions of its subgoals that relied on (see
+//! `Forest::record_derivations` and `RecursiveContext::record_proofs`).
+//! A proof tree is built by following these records down from the
+//! solution of the root goal.
+
+use super::explain::{classify_clause, Candidate};
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use std::fmt;
+
+#[cfg(feature = "slg-solver")]
+use {
+    crate::solve::slg::SlgContext,
+    chalk_engine::forest::Forest,
+    chalk_engine::{Answer, AnswerIndex, SubgoalDerivation, TableIndex},
+};
+
+#[cfg(feature = "recursive-solver")]
+use crate::recursive::{
+    lib::{ProofSubgoal, UCanonicalGoal},
+    RecursiveContext,
+};
+
+/// An explanation of why a goal holds: the goal, the step used to prove
+/// it and the proofs of the subgoals that step gave rise to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTree<I: Interner> {
+    /// The goal that was proven, with its solution substituted in. Any
+    /// variables that the solution left undetermined appear as bound
+    /// variables.
+    pub goal: Goal<I>,
+    pub step: ProofStep<I>,
+    pub subproofs: Vec<ProofTree<I>>,
+}
+
+/// How a single goal in a proof tree was proven.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofStep<I: Interner> {
+    /// By a clause from an impl, the program or the environment.
+    Clause(Candidate<I>),
+
+    /// `forall`, `exists`, `if` and conjunction goals hold because all of
+    /// their subgoals hold.
+    Subgoals,
+
+    /// By unifying or subtyping the two sides of the goal.
+    Unification,
+
+    /// Outlives goals are not proven, but become region constraints.
+    RegionConstraint,
+
+    /// `not { G }` holds because `G` has no solution.
+    Negation,
+
+    /// The goal is one of its own ancestors in the proof, which is only
+    /// allowed for coinductive goals.
+    Cycle,
+
+    /// The solver proved the goal, but did not record how.
+    Unexplained,
+}
+
+impl<I: Interner> ProofTree<I> {
+    pub fn display(&self) -> ProofTreeDisplay<'_, I> {
+        ProofTreeDisplay { proof: self }
+    }
+}
+
+/// Renders a proof tree with one goal per line, indenting the subproofs
+/// of each goal below it.
+pub struct ProofTreeDisplay<'a, I: Interner> {
+    proof: &'a ProofTree<I>,
+}
+
+impl<'a, I: Interner> ProofTreeDisplay<'a, I> {
+    fn fmt_at(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        proof: &ProofTree<I>,
+        depth: usize,
+    ) -> fmt::Result {
+        write!(f, "{}`{:?}`", "    ".repeat(depth), proof.goal)?;
+        match &proof.step {
+            ProofStep::Clause(candidate) => write!(f, " because of {}", candidate)?,
+            ProofStep::Subgoals => write!(f, " because all of its subgoals hold")?,
+            ProofStep::Unification => write!(f, " by unification")?,
+            ProofStep::RegionConstraint => write!(f, " as a region constraint")?,
+            ProofStep::Negation => write!(f, " because the negated goal has no solution")?,
+            ProofStep::Cycle => write!(f, " coinductively, since it is its own ancestor")?,
+            ProofStep::Unexplained => write!(f, " (no further explanation available)")?,
+        }
+        for subproof in &proof.subproofs {
+            writeln!(f)?;
+            self.fmt_at(f, subproof, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I: Interner> fmt::Display for ProofTreeDisplay<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, self.proof, 0)
+    }
+}
+
+/// The derivations that a solver recorded while solving.
+trait RecordedProofs<I: Interner> {
+    /// Identifies a goal together with the solution that was recorded for
+    /// it.
+    type Node: Clone + PartialEq;
+
+    /// The goal of `node`, with its solution substituted in.
+    fn goal(&self, node: &Self::Node) -> Goal<I>;
+
+    /// How `node` was derived, if that was recorded.
+    fn derivation(&self, node: &Self::Node) -> Option<RecordedDerivation<I, Self::Node>>;
+}
+
+struct RecordedDerivation<I: Interner, N> {
+    /// The clause that proved the goal, along with the environment of the
+    /// goal. `None` if the goal was simplified into its subgoals instead.
+    clause: Option<Canonical<InEnvironment<ProgramClause<I>>>>,
+    subgoals: Vec<RecordedSubgoal<I, N>>,
+}
+
+enum RecordedSubgoal<I: Interner, N> {
+    Proven(N),
+
+    /// The goal of a `not { G }` subgoal, which has no solution.
+    Refuted(Goal<I>),
+
+    /// A coinductive subgoal that was assumed to hold, since it was being
+    /// solved already.
+    Delayed(Goal<I>),
+}
+
+/// Builds the proof tree of `root` from the recorded derivations.
+fn build_proof<I: Interner, R: RecordedProofs<I>>(
+    db: &dyn RustIrDatabase<I>,
+    recorded: &R,
+    root: R::Node,
+) -> ProofTree<I> {
+    ProofBuilder {
+        db,
+        recorded,
+        ancestors: vec![],
+    }
+    .build(root)
+}
+
+struct ProofBuilder<'a, I: Interner, R: RecordedProofs<I>> {
+    db: &'a dyn RustIrDatabase<I>,
+    recorded: &'a R,
+
+    /// The nodes being built, outermost first, used to detect cycles.
+    ancestors: Vec<R::Node>,
+}
+
+impl<I: Interner, R: RecordedProofs<I>> ProofBuilder<'_, I, R> {
+    fn build(&mut self, node: R::Node) -> ProofTree<I> {
+        let interner = self.db.interner();
+        let goal = self.recorded.goal(&node);
+        if self.ancestors.contains(&node) {
+            return leaf(goal, ProofStep::Cycle);
+        }
+        let derivation = match self.recorded.derivation(&node) {
+            Some(derivation) => derivation,
+            None => return leaf(goal, ProofStep::Unexplained),
+        };
+        if let GoalData::Not(_) = goal.data(interner) {
+            // The only subgoal is the refuted goal itself.
+            return leaf(goal, ProofStep::Negation);
+        }
+
+        let step = match &derivation.clause {
+            Some(clause) => {
+                let InEnvironment {
+                    environment,
+                    goal: clause,
+                } = &clause.value;
+                ProofStep::Clause(classify_clause(self.db, environment, &goal, clause))
+            }
+            None => match goal.data(interner) {
+                GoalData::EqGoal(_) | GoalData::SubtypeGoal(_) => ProofStep::Unification,
+                GoalData::DomainGoal(DomainGoal::Holds(WhereClause::LifetimeOutlives(_)))
+                | GoalData::DomainGoal(DomainGoal::Holds(WhereClause::TypeOutlives(_))) => {
+                    ProofStep::RegionConstraint
+                }
+                _ => ProofStep::Subgoals,
+            },
+        };
+
+        self.ancestors.push(node);
+        let mut subproofs: Vec<_> = derivation
+            .subgoals
+            .into_iter()
+            .map(|subgoal| match subgoal {
+                RecordedSubgoal::Proven(node) => self.build(node),
+                RecordedSubgoal::Refuted(goal) => {
+                    leaf(GoalData::Not(goal).intern(interner), ProofStep::Negation)
+                }
+                RecordedSubgoal::Delayed(goal) => leaf(goal, ProofStep::Cycle),
+            })
+            .collect();
+        self.ancestors.pop();
+
+        // The conditions of clauses lowered from where clauses are wrapped
+        // in quantifiers without any binders; they are not worth showing.
+        if let GoalData::Quantified(_, subgoal) = goal.data(interner) {
+            if subgoal.binders.is_empty(interner) && subproofs.len() == 1 {
+                return subproofs.pop().unwrap();
+            }
+        }
+
+        ProofTree {
+            goal,
+            step,
+            subproofs,
+        }
+    }
+}
+
+fn leaf<I: Interner>(goal: Goal<I>, step: ProofStep<I>) -> ProofTree<I> {
+    ProofTree {
+        goal,
+        step,
+        subproofs: vec![],
+    }
+}
+
+/// Builds the proof tree of `goal` from the answers in `forest`, which
+/// must record derivations.
+#[cfg(feature = "slg-solver")]
+pub(super) fn slg_proof<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    forest: &Forest<I, SlgContext<I>>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> Option<ProofTree<I>> {
+    let table = forest.table_index(goal)?;
+    // The unique solution comes from the first unconditional answer.
+    let mut answer = AnswerIndex::from(0);
+    loop {
+        let Answer {
+            subst, ambiguous, ..
+        } = forest.table_answer(table, answer)?;
+        if !ambiguous && subst.value.delayed_subgoals.is_empty() {
+            break;
+        }
+        answer.increment();
+    }
+    let recorded = SlgProofs { db, forest };
+    Some(build_proof(db, &recorded, (table, answer)))
+}
+
+#[cfg(feature = "slg-solver")]
+struct SlgProofs<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+    forest: &'a Forest<I, SlgContext<I>>,
+}
+
+#[cfg(feature = "slg-solver")]
+impl<I: Interner> SlgProofs<'_, I> {
+    fn table_goal(&self, table: TableIndex) -> Goal<I> {
+        self.forest.table_goal(table).canonical.value.goal.clone()
+    }
+}
+
+#[cfg(feature = "slg-solver")]
+impl<I: Interner> RecordedProofs<I> for SlgProofs<'_, I> {
+    type Node = (TableIndex, AnswerIndex);
+
+    fn goal(&self, &(table, answer): &Self::Node) -> Goal<I> {
+        let goal = self.table_goal(table);
+        match self.forest.table_answer(table, answer) {
+            Some(answer) => answer.subst.value.subst.apply(&goal, self.db.interner()),
+            None => goal,
+        }
+    }
+
+    fn derivation(
+        &self,
+        &(table, answer): &Self::Node,
+    ) -> Option<RecordedDerivation<I, Self::Node>> {
+        let derivation = self
+            .forest
+            .table_answer(table, answer)?
+            .derivation
+            .as_ref()?;
+        Some(RecordedDerivation {
+            clause: derivation.clause.clone(),
+            subgoals: derivation
+                .subgoals
+                .iter()
+                .map(|&subgoal| match subgoal {
+                    SubgoalDerivation::Answer(table, answer) => {
+                        RecordedSubgoal::Proven((table, answer))
+                    }
+                    SubgoalDerivation::Refuted(table) => {
+                        RecordedSubgoal::Refuted(self.table_goal(table))
+                    }
+                    SubgoalDerivation::Delayed(table) => {
+                        RecordedSubgoal::Delayed(self.table_goal(table))
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Builds the proof tree of `goal` from the proofs that `context` recorded.
+#[cfg(feature = "recursive-solver")]
+pub(super) fn recursive_proof<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    context: &RecursiveContext<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> ProofTree<I> {
+    let recorded = RecursiveProofs { db, context };
+    build_proof(db, &recorded, goal.clone())
+}
+
+#[cfg(feature = "recursive-solver")]
+struct RecursiveProofs<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+    context: &'a RecursiveContext<I>,
+}
+
+#[cfg(feature = "recursive-solver")]
+impl<I: Interner> RecordedProofs<I> for RecursiveProofs<'_, I> {
+    type Node = UCanonicalGoal<I>;
+
+    fn goal(&self, node: &Self::Node) -> Goal<I> {
+        let goal = &node.canonical.value.goal;
+        match self.context.proof(node) {
+            Some(proof) => proof.solution.value.subst.apply(goal, self.db.interner()),
+            None => goal.clone(),
+        }
+    }
+
+    fn derivation(&self, node: &Self::Node) -> Option<RecordedDerivation<I, Self::Node>> {
+        let proof = self.context.proof(node)?;
+        Some(RecordedDerivation {
+            clause: proof.clause.as_ref().map(|clause| Canonical {
+                binders: node.canonical.binders.clone(),
+                value: InEnvironment::new(&node.canonical.value.environment, clause.clone()),
+            }),
+            subgoals: proof
+                .subgoals
+                .iter()
+                .map(|subgoal| match subgoal {
+                    ProofSubgoal::Proven(goal) => RecordedSubgoal::Proven(goal.clone()),
+                    ProofSubgoal::Refuted(goal) => {
+                        RecordedSubgoal::Refuted(goal.canonical.value.goal.clone())
+                    }
+                })
+                .collect(),
+        })
+    }
+}
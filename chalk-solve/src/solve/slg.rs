@@ -3,20 +3,25 @@ use crate::coinductive_goal::IsCoinductive;
 use crate::infer::ucanonicalize::UCanonicalized;
 use crate::infer::unify::UnificationResult;
 use crate::infer::InferenceTable;
+use crate::solve::budget::BudgetTracker;
+use crate::solve::stats;
 use crate::solve::truncate;
+use crate::solve::Dependency;
 use crate::RustIrDatabase;
 use chalk_derive::HasInterner;
-use chalk_engine::context;
+use chalk_engine::context::{self, Work};
 use chalk_engine::{ExClause, Literal};
 use chalk_ir::cast::Cast;
 use chalk_ir::cast::Caster;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
+use recording::RecordingDatabase;
 
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
 pub(crate) mod aggregate;
+mod recording;
 mod resolvent;
 
 #[derive(Debug)]
@@ -63,9 +68,10 @@ pub(crate) struct SlgContextOps<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     max_size: usize,
     expected_answers: Option<usize>,
+    budget: Option<&'me BudgetTracker<'me>>,
 }
 
-impl<I: Interner> SlgContextOps<'_, I> {
+impl<'me, I: Interner> SlgContextOps<'me, I> {
     pub(crate) fn new<'p>(
         program: &'p dyn RustIrDatabase<I>,
         max_size: usize,
@@ -75,6 +81,15 @@ impl<I: Interner> SlgContextOps<'_, I> {
             program,
             max_size,
             expected_answers,
+            budget: None,
+        }
+    }
+
+    /// Makes the engine stop once `budget` is exhausted.
+    pub(crate) fn with_budget(self, budget: &'me BudgetTracker<'me>) -> Self {
+        SlgContextOps {
+            budget: Some(budget),
+            ..self
         }
     }
 }
@@ -88,6 +103,8 @@ pub struct TruncatingInferenceTable<I: Interner> {
 impl<I: Interner> context::Context<I> for SlgContext<I> {
     type InferenceTable = TruncatingInferenceTable<I>;
 
+    type Dependency = Dependency<I>;
+
     // Used by: logic
     fn next_subgoal_index(ex_clause: &ExClause<I>) -> usize {
         // For now, we always pick the last subgoal in the
@@ -129,10 +146,13 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
         _infer: &mut TruncatingInferenceTable<I>,
-    ) -> Result<Vec<ProgramClause<I>>, Floundered> {
-        let clauses: Vec<_> = program_clauses_for_goal(self.program, environment, goal)?;
+        dependencies: &mut Vec<Dependency<I>>,
+    ) -> Result<Vec<ProgramClause<I>>, Floundered<I>> {
+        let recording = RecordingDatabase::new(self.program);
+        let clauses = program_clauses_for_goal(&recording, environment, goal);
+        dependencies.extend(recording.into_dependencies());
 
-        Ok(clauses)
+        clauses
     }
 
     // Used by: simplify
@@ -213,10 +233,35 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
             .quantified
     }
 
+    fn charge(&self, work: Work) {
+        match work {
+            Work::Strand => stats::record(|stats| stats.strands_processed += 1),
+            Work::Table => stats::record(|stats| stats.tables_created += 1),
+            Work::ClauseInstantiation => {}
+            Work::Answer => stats::record(|stats| stats.answers_produced += 1),
+        }
+        if let Some(budget) = self.budget {
+            match work {
+                Work::Strand => budget.charge_strand(),
+                Work::Table => budget.charge_table(),
+                Work::ClauseInstantiation => budget.charge_clause_instantiation(),
+                Work::Answer => {}
+            }
+        }
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        matches!(self.budget, Some(budget) if budget.is_exhausted())
+    }
+
     fn interner(&self) -> &I {
         self.program.interner()
     }
 
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self.program.unification_database()
+    }
+
     fn into_goal(&self, domain_goal: DomainGoal<I>) -> Goal<I> {
         domain_goal.cast(self.program.interner())
     }
@@ -295,6 +340,14 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
         self.infer.canonicalize(interner, value).quantified
     }
 
+    fn canonicalize_clause(
+        &mut self,
+        interner: &I,
+        value: &InEnvironment<ProgramClause<I>>,
+    ) -> Canonical<InEnvironment<ProgramClause<I>>> {
+        self.infer.canonicalize(interner, value).quantified
+    }
+
     fn canonicalize_constrained_subst(
         &mut self,
         interner: &I,
@@ -344,6 +397,22 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
         let result = self.infer.unify(interner, environment, a, b)?;
         Ok(into_ex_clause(interner, result, ex_clause))
     }
+
+    fn relate_tys_into_ex_clause(
+        &mut self,
+        interner: &I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &Ty<I>,
+        b: &Ty<I>,
+        ex_clause: &mut ExClause<I>,
+    ) -> Fallible<()> {
+        let result = self
+            .infer
+            .relate_tys(interner, db, environment, variance, a, b)?;
+        Ok(into_ex_clause(interner, result, ex_clause))
+    }
 }
 
 /// Helper function
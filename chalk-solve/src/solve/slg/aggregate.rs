@@ -2,13 +2,13 @@ use crate::ext::*;
 use crate::infer::InferenceTable;
 use crate::solve::slg::SlgContextOps;
 use crate::solve::slg::SubstitutionExt;
-use crate::solve::{Guidance, Solution};
+use crate::solve::{AmbiguityCandidates, Guidance, Overflow, OverflowLimit, Solution};
 use chalk_ir::cast::Cast;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 
 use chalk_engine::context::{self, AnswerResult, ContextOps};
-use chalk_engine::CompleteAnswer;
+use chalk_engine::{CompleteAnswer, FlounderedCause};
 use std::fmt::Debug;
 
 /// Methods for combining solutions to yield an aggregate solution.
@@ -19,28 +19,62 @@ pub trait AggregateOps<I: Interner> {
         answers: impl context::AnswerStream<I>,
         should_continue: impl std::ops::Fn() -> bool,
     ) -> Option<Solution<I>>;
+
+    /// Like `make_solution`, but also pushes the distinct answers behind an
+    /// ambiguous solution onto `candidates`, until it holds `max_candidates`
+    /// of them. This may draw more answers than `make_solution` would.
+    fn make_solution_with_candidates(
+        &self,
+        root_goal: &UCanonical<InEnvironment<Goal<I>>>,
+        answers: impl context::AnswerStream<I>,
+        should_continue: impl std::ops::Fn() -> bool,
+        candidates: &mut AmbiguityCandidates<I>,
+        max_candidates: usize,
+    ) -> Option<Solution<I>>;
 }
 
 /// Draws as many answers as it needs from `answers` (but
 /// no more!) in order to come up with a solution.
 impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
     fn make_solution(
+        &self,
+        root_goal: &UCanonical<InEnvironment<Goal<I>>>,
+        answers: impl context::AnswerStream<I>,
+        should_continue: impl std::ops::Fn() -> bool,
+    ) -> Option<Solution<I>> {
+        self.make_solution_with_candidates(root_goal, answers, should_continue, &mut vec![], 0)
+    }
+
+    fn make_solution_with_candidates(
         &self,
         root_goal: &UCanonical<InEnvironment<Goal<I>>>,
         mut answers: impl context::AnswerStream<I>,
         should_continue: impl std::ops::Fn() -> bool,
+        candidates: &mut AmbiguityCandidates<I>,
+        max_candidates: usize,
     ) -> Option<Solution<I>> {
         let interner = self.program.interner();
-        let CompleteAnswer { subst, ambiguous } = match answers.next_answer(|| should_continue()) {
+        let (
+            CompleteAnswer {
+                subst,
+                ambiguous,
+                mut floundered,
+            },
+            is_candidate,
+        ) = match answers.next_answer(|| should_continue()) {
             AnswerResult::NoMoreSolutions => {
                 // No answers at all
                 return None;
             }
-            AnswerResult::Answer(answer) => answer,
-            AnswerResult::Floundered => CompleteAnswer {
-                subst: self.identity_constrained_subst(root_goal),
-                ambiguous: true,
-            },
+            AnswerResult::Answer(answer) => (answer, true),
+            AnswerResult::Floundered(floundered_goal) => (
+                CompleteAnswer {
+                    subst: self.identity_constrained_subst(root_goal),
+                    ambiguous: true,
+                    floundered: Some(floundered_goal),
+                },
+                false,
+            ),
             AnswerResult::QuantumExceeded => {
                 return Some(Solution::Ambig(Guidance::Unknown));
             }
@@ -50,7 +84,7 @@ impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
         let next_answer = answers.peek_answer(|| should_continue());
         if next_answer.is_quantum_exceeded() {
             if subst.value.subst.is_identity_subst(interner) {
-                return Some(Solution::Ambig(Guidance::Unknown));
+                return Some(Solution::Ambig(unknown_guidance(floundered, self.max_size)));
             } else {
                 return Some(Solution::Ambig(Guidance::Suggested(
                     subst.map(interner, |cs| cs.subst),
@@ -74,13 +108,17 @@ impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
         // leave that for future work. This is basically
         // rust-lang/rust#21974.
         let mut subst = subst.map(interner, |cs| cs.subst);
+        if is_candidate {
+            push_candidate(candidates, max_candidates, subst.clone());
+        }
 
         // Extract answers and merge them into `subst`. Stop once we have
         // a trivial subst (or run out of answers).
         let mut num_answers = 1;
+        let mut answers_left = true;
         let guidance = loop {
             if subst.value.is_empty(interner) || is_trivial(interner, &subst) {
-                break Guidance::Unknown;
+                break unknown_guidance(floundered, self.max_size);
             }
 
             if !answers
@@ -96,15 +134,24 @@ impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
             }
 
             let new_subst = match answers.next_answer(|| should_continue()) {
-                AnswerResult::Answer(answer1) => answer1.subst,
-                AnswerResult::Floundered => {
+                AnswerResult::Answer(answer1) => {
+                    floundered = floundered.or(answer1.floundered);
+                    let candidate = answer1.subst.clone().map(interner, |cs| cs.subst);
+                    push_candidate(candidates, max_candidates, candidate);
+                    answer1.subst
+                }
+                AnswerResult::Floundered(floundered_goal) => {
                     // FIXME: this doesn't trigger for any current tests
+                    floundered = floundered.or(Some(floundered_goal));
+                    answers_left = false;
                     self.identity_constrained_subst(root_goal)
                 }
                 AnswerResult::NoMoreSolutions => {
+                    answers_left = false;
                     break Guidance::Definite(subst);
                 }
                 AnswerResult::QuantumExceeded => {
+                    answers_left = false;
                     break Guidance::Suggested(subst);
                 }
             };
@@ -118,10 +165,53 @@ impl<I: Interner> AggregateOps<I> for SlgContextOps<'_, I> {
                 "Not enough answers for solution."
             );
         }
+
+        // The guidance may be settled before all the answers are drawn,
+        // e.g. once it has become trivial; keep going for the candidates.
+        while answers_left && candidates.len() < max_candidates {
+            match answers.next_answer(&should_continue) {
+                AnswerResult::Answer(answer) => {
+                    let candidate = answer.subst.map(interner, |cs| cs.subst);
+                    push_candidate(candidates, max_candidates, candidate);
+                }
+                AnswerResult::Floundered(_)
+                | AnswerResult::NoMoreSolutions
+                | AnswerResult::QuantumExceeded => break,
+            }
+        }
         Some(Solution::Ambig(guidance))
     }
 }
 
+/// Pushes `candidate` onto `candidates` unless it is already there or
+/// `candidates` is full.
+fn push_candidate<I: Interner>(
+    candidates: &mut AmbiguityCandidates<I>,
+    max_candidates: usize,
+    candidate: Canonical<Substitution<I>>,
+) {
+    if candidates.len() < max_candidates && !candidates.contains(&candidate) {
+        candidates.push(candidate);
+    }
+}
+
+/// The guidance for a solution that tells us nothing about the inference
+/// variables, explaining why if it is because some goal floundered (or
+/// got larger than `max_size`).
+fn unknown_guidance<I: Interner>(
+    floundered: Option<FlounderedCause<I>>,
+    max_size: usize,
+) -> Guidance<I> {
+    match floundered {
+        Some(FlounderedCause::Goal(floundered_goal)) => Guidance::Floundered(floundered_goal),
+        Some(FlounderedCause::Overflow(goals)) => Guidance::Overflow(Overflow {
+            goals,
+            limit: OverflowLimit::MaxSize(max_size),
+        }),
+        None => Guidance::Unknown,
+    }
+}
+
 /// Given a current substitution used as guidance for `root_goal`, and
 /// a new possible answer to `root_goal`, returns a new set of
 /// guidance that encompasses both of them. This is often more general
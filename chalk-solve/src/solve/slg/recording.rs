@@ -0,0 +1,172 @@
+use crate::rust_ir::*;
+use crate::solve::Dependency;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+/// Wraps a database, recording the traits and impls that are looked up
+/// through it.
+pub(crate) struct RecordingDatabase<'me, I: Interner> {
+    db: &'me dyn RustIrDatabase<I>,
+    dependencies: RefCell<Vec<Dependency<I>>>,
+
+    /// The environments whose clauses were elaborated through this
+    /// database, so that the items they come from are recorded already.
+    elaborated_environments: RefCell<FxHashSet<Environment<I>>>,
+}
+
+impl<'me, I: Interner> RecordingDatabase<'me, I> {
+    pub(crate) fn new(db: &'me dyn RustIrDatabase<I>) -> Self {
+        RecordingDatabase {
+            db,
+            dependencies: RefCell::new(vec![]),
+            elaborated_environments: RefCell::new(FxHashSet::default()),
+        }
+    }
+
+    /// The traits and impls looked up so far.
+    pub(crate) fn into_dependencies(self) -> Vec<Dependency<I>> {
+        self.dependencies.into_inner()
+    }
+
+    fn record(&self, dependency: Dependency<I>) {
+        let mut dependencies = self.dependencies.borrow_mut();
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+    }
+}
+
+impl<I: Interner> fmt::Debug for RecordingDatabase<'_, I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "RecordingDatabase({:?})", self.db)
+    }
+}
+
+impl<I: Interner> RustIrDatabase<I> for RecordingDatabase<'_, I> {
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        self.db.custom_clauses()
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        let datum = self.db.associated_ty_data(ty);
+        self.record(Dependency::Trait(datum.trait_id));
+        datum
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        self.record(Dependency::Trait(trait_id));
+        self.db.trait_datum(trait_id)
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        self.db.adt_datum(adt_id)
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        self.db.fn_def_datum(fn_def_id)
+    }
+
+    fn closure_datum(&self, closure_id: ClosureId<I>) -> Arc<ClosureDatum<I>> {
+        self.db.closure_datum(closure_id)
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        self.db.generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        self.db.generator_witness_datum(generator_id)
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        self.record(Dependency::Impl(impl_id));
+        self.db.impl_datum(impl_id)
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        let value = self.db.associated_ty_value(id);
+        self.record(Dependency::Impl(value.impl_id));
+        value
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        self.db.opaque_ty_data(id)
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        self.db.hidden_opaque_type(id)
+    }
+
+    fn impls_for_trait(
+        &self,
+        trait_id: TraitId<I>,
+        parameters: &[GenericArg<I>],
+    ) -> Vec<ImplId<I>> {
+        self.record(Dependency::Trait(trait_id));
+        self.db.impls_for_trait(trait_id, parameters)
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        self.record(Dependency::Trait(trait_id));
+        self.db.local_impls_to_coherence_check(trait_id)
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, adt_id: AdtId<I>) -> bool {
+        self.record(Dependency::Trait(auto_trait_id));
+        self.db.impl_provided_for(auto_trait_id, adt_id)
+    }
+
+    fn force_impl_for(&self, well_known: WellKnownTrait, ty: &TyData<I>) -> Option<bool> {
+        self.db.force_impl_for(well_known, ty)
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        let trait_id = self.db.well_known_trait_id(well_known_trait);
+        if let Some(trait_id) = trait_id {
+            self.record(Dependency::Trait(trait_id));
+        }
+        trait_id
+    }
+
+    fn well_known_assoc_type_id(
+        &self,
+        well_known_assoc_type: WellKnownAssocType,
+    ) -> Option<AssocTypeId<I>> {
+        self.db.well_known_assoc_type_id(well_known_assoc_type)
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        let clauses = self.db.program_clauses_for_env(environment);
+        // `db` may have cached the clauses, so elaborate them again to
+        // record the items they come from, once per environment.
+        if self
+            .elaborated_environments
+            .borrow_mut()
+            .insert(environment.clone())
+        {
+            let _ = crate::program_clauses_for_env(self, environment);
+        }
+        clauses
+    }
+
+    fn interner(&self) -> &I {
+        self.db.interner()
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self.db.unification_database()
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        self.record(Dependency::Trait(trait_id));
+        self.db.is_object_safe(trait_id)
+    }
+}
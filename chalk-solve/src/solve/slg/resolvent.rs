@@ -5,6 +5,7 @@ use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::zip::{Zip, Zipper};
 use chalk_ir::*;
+use tracing::{debug, debug_span};
 
 use chalk_engine::context;
 use chalk_engine::{ExClause, Literal, TimeStamp};
@@ -68,13 +69,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
         //   - Also, we always select the first literal in `ex_clause.literals`, so `i` is 0.
         // - `clause` is C, except with binders for any existential variables.
 
-        debug_heading!(
-            "resolvent_clause(\
-             \n    goal={:?},\
-             \n    clause={:?})",
-            goal,
-            clause,
-        );
+        let _span = debug_span!("resolvent_clause", ?goal, ?clause).entered();
 
         // C' in the description above is `consequence :- conditions`.
         //
@@ -210,7 +205,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
         answer_table_goal: &Canonical<InEnvironment<Goal<I>>>,
         canonical_answer_subst: &Canonical<AnswerSubst<I>>,
     ) -> Fallible<()> {
-        debug_heading!("apply_answer_subst()");
+        let _span = debug_span!("apply_answer_subst").entered();
         debug!("ex_clause={:?}", ex_clause);
         debug!(
             "selected_goal={:?}",
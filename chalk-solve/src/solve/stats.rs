@@ -0,0 +1,208 @@
+use chalk_ir::interner::Interner;
+use chalk_ir::{DomainGoal, WhereClause};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counters describing the work done by the solver; see `Solver::stats`
+/// and `Solver::last_solve_stats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// How many tables (for the SLG solver) or new goals (for the recursive
+    /// solver) were created.
+    pub tables_created: usize,
+
+    /// How many strands (for the SLG solver) or obligations (for the
+    /// recursive solver) were pursued.
+    pub strands_processed: usize,
+
+    /// How many answers were added to tables (for the SLG solver) or
+    /// solutions found for new goals (for the recursive solver).
+    pub answers_produced: usize,
+
+    /// How many goals the recursive solver found in its cache.
+    pub cache_hits: usize,
+
+    /// How many goals the recursive solver did not find in its cache.
+    pub cache_misses: usize,
+
+    /// How many program clauses were generated for goals of each kind, e.g.
+    /// `Implemented` or `Normalize`.
+    pub clauses_by_goal_kind: BTreeMap<&'static str, usize>,
+
+    /// The time spent canonicalizing goals and answers.
+    pub canonicalization_time: Duration,
+
+    /// The time spent unifying and relating types.
+    pub unification_time: Duration,
+}
+
+impl SolverStats {
+    /// The total number of program clauses generated.
+    pub fn clauses_generated(&self) -> usize {
+        self.clauses_by_goal_kind.values().sum()
+    }
+
+    /// Adds the counters of `other` to these.
+    pub fn add(&mut self, other: &SolverStats) {
+        self.tables_created += other.tables_created;
+        self.strands_processed += other.strands_processed;
+        self.answers_produced += other.answers_produced;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        for (kind, count) in &other.clauses_by_goal_kind {
+            *self.clauses_by_goal_kind.entry(kind).or_insert(0) += count;
+        }
+        self.canonicalization_time += other.canonicalization_time;
+        self.unification_time += other.unification_time;
+    }
+}
+
+impl fmt::Display for SolverStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tables created: {}", self.tables_created)?;
+        writeln!(f, "strands processed: {}", self.strands_processed)?;
+        writeln!(f, "answers produced: {}", self.answers_produced)?;
+        writeln!(
+            f,
+            "cache hits/misses: {}/{}",
+            self.cache_hits, self.cache_misses
+        )?;
+        writeln!(f, "clauses generated: {}", self.clauses_generated())?;
+        for (kind, count) in &self.clauses_by_goal_kind {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        writeln!(f, "canonicalization time: {:?}", self.canonicalization_time)?;
+        write!(f, "unification time: {:?}", self.unification_time)
+    }
+}
+
+thread_local! {
+    /// The statistics of the solve in progress on this thread, if any.
+    static CURRENT: RefCell<Option<SolverStats>> = RefCell::new(None);
+}
+
+/// How many solves are collecting statistics, on any thread. While there
+/// are none, `record` and `Timer` don't touch `CURRENT` at all.
+static COLLECTING: AtomicUsize = AtomicUsize::new(0);
+
+fn collecting() -> bool {
+    COLLECTING.load(Ordering::Relaxed) > 0
+}
+
+/// Runs `op`, collecting the statistics of the work it does. Returns `None`
+/// instead of statistics if a surrounding call is already collecting them,
+/// in which case they are added to that call's statistics.
+pub(crate) fn collect<R>(op: impl FnOnce() -> R) -> (R, Option<SolverStats>) {
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            CURRENT.with(|current| *current.borrow_mut() = None);
+            COLLECTING.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    let outermost = CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        if current.is_some() {
+            return false;
+        }
+        *current = Some(SolverStats::default());
+        true
+    });
+    if !outermost {
+        return (op(), None);
+    }
+
+    COLLECTING.fetch_add(1, Ordering::Relaxed);
+    let reset = Reset;
+    let result = op();
+    let stats = CURRENT.with(|current| current.borrow_mut().take());
+    drop(reset);
+    (result, stats)
+}
+
+/// Updates the statistics of the solve in progress, if any.
+pub(crate) fn record(op: impl FnOnce(&mut SolverStats)) {
+    if !collecting() {
+        return;
+    }
+    CURRENT.with(|current| {
+        if let Some(stats) = current.borrow_mut().as_mut() {
+            op(stats);
+        }
+    });
+}
+
+/// Records that `count` program clauses were generated for `goal`.
+pub(crate) fn record_clauses<I: Interner>(goal: &DomainGoal<I>, count: usize) {
+    record(|stats| {
+        *stats
+            .clauses_by_goal_kind
+            .entry(goal_kind(goal))
+            .or_insert(0) += count
+    });
+}
+
+fn goal_kind<I: Interner>(goal: &DomainGoal<I>) -> &'static str {
+    match goal {
+        DomainGoal::Holds(WhereClause::Implemented(_)) => "Implemented",
+        DomainGoal::Holds(WhereClause::AliasEq(_)) => "AliasEq",
+        DomainGoal::Holds(WhereClause::LifetimeOutlives(_)) => "LifetimeOutlives",
+        DomainGoal::Holds(WhereClause::TypeOutlives(_)) => "TypeOutlives",
+        DomainGoal::WellFormed(_) => "WellFormed",
+        DomainGoal::FromEnv(_) => "FromEnv",
+        DomainGoal::Normalize(_) => "Normalize",
+        DomainGoal::IsLocal(_) => "IsLocal",
+        DomainGoal::IsUpstream(_) => "IsUpstream",
+        DomainGoal::IsFullyVisible(_) => "IsFullyVisible",
+        DomainGoal::LocalImplAllowed(_) => "LocalImplAllowed",
+        DomainGoal::Compatible(_) => "Compatible",
+        DomainGoal::DownstreamType(_) => "DownstreamType",
+        DomainGoal::Reveal(_) => "Reveal",
+        DomainGoal::ObjectSafe(_) => "ObjectSafe",
+    }
+}
+
+/// Adds the time until it is dropped to one of the durations of the
+/// statistics of the solve in progress, if any.
+pub(crate) struct Timer {
+    duration: fn(&mut SolverStats) -> &mut Duration,
+    start: Option<Instant>,
+}
+
+impl Timer {
+    pub(crate) fn canonicalization() -> Self {
+        Timer::start(|stats| &mut stats.canonicalization_time)
+    }
+
+    pub(crate) fn unification() -> Self {
+        Timer::start(|stats| &mut stats.unification_time)
+    }
+
+    fn start(duration: fn(&mut SolverStats) -> &mut Duration) -> Self {
+        // Don't pay for reading the clock unless someone is collecting.
+        let collecting = collecting() && CURRENT.with(|current| current.borrow().is_some());
+        Timer {
+            duration,
+            start: if collecting {
+                Some(Instant::now())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let elapsed = start.elapsed();
+            let duration = self.duration;
+            record(|stats| *duration(stats) += elapsed);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::RustIrDatabase;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use std::sync::Arc;
+use tracing::{debug, debug_span};
 
 /// Methods for splitting up the projections for associated types from
 /// the surrounding context.
@@ -126,10 +127,11 @@ pub trait Split<I: Interner>: RustIrDatabase<I> {
         associated_ty_value: &AssociatedTyValue<I>,
     ) -> (&'p [GenericArg<I>], ProjectionTy<I>) {
         let interner = self.interner();
-        debug_heading!(
-            "impl_parameters_and_projection_from_associated_ty_value(parameters={:?})",
-            parameters,
-        );
+        let _span = debug_span!(
+            "impl_parameters_and_projection_from_associated_ty_value",
+            ?parameters
+        )
+        .entered();
 
         let impl_datum = self.impl_datum(associated_ty_value.impl_id);
 
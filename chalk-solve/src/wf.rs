@@ -1,9 +1,11 @@
 use std::{fmt, iter};
+use tracing::debug;
 
 use crate::ext::*;
 use crate::goal_builder::GoalBuilder;
+use crate::infer::InferenceTable;
 use crate::rust_ir::*;
-use crate::solve::SolverChoice;
+use crate::solve::{Solution, SolverChoice};
 use crate::split::Split;
 use crate::RustIrDatabase;
 use chalk_ir::cast::*;
@@ -14,23 +16,130 @@ use chalk_ir::*;
 
 #[derive(Debug)]
 pub enum WfError<I: Interner> {
-    IllFormedTypeDecl(chalk_ir::AdtId<I>),
-    IllFormedTraitImpl(chalk_ir::TraitId<I>),
+    IllFormedTypeDecl {
+        adt_id: AdtId<I>,
+        failure: WfFailure<I>,
+    },
+    IllFormedTraitImpl {
+        trait_id: TraitId<I>,
+        impl_id: ImplId<I>,
+        failure: WfFailure<I>,
+    },
 }
 
-impl<I: Interner> fmt::Display for WfError<I> {
+/// Explains why a type declaration or impl is not well-formed.
+#[derive(Debug)]
+pub struct WfFailure<I: Interner> {
+    /// The requirement that could not be met.
+    pub obligation: WfObligation<I>,
+
+    /// The most specific subgoal of the obligation that could not be
+    /// proven. The generic parameters of the declaration or impl are
+    /// replaced by placeholders, and the environment contains what the
+    /// declaration or impl is allowed to assume.
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+
+    /// The solution to `goal`: `None` if it has no solution, or an
+    /// ambiguous one.
+    pub solution: Option<Solution<I>>,
+}
+
+/// A requirement that a type declaration or impl has to meet in order to
+/// be well-formed. Types and where clauses refer to the generic parameters
+/// of the declaration or impl as placeholders, like the goal in
+/// `WfFailure`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WfObligation<I: Interner> {
+    /// The type of a field must be well-formed.
+    FieldType(Ty<I>),
+
+    /// The type of a field must be `Sized` (true of all fields but the
+    /// last one of a struct).
+    SizedField(Ty<I>),
+
+    /// The types appearing in a where clause must be well-formed.
+    WhereClause(QuantifiedWhereClause<I>),
+
+    /// A where clause of the implemented trait, e.g. a supertrait bound,
+    /// must hold.
+    TraitWhereClause(QuantifiedWhereClause<I>),
+
+    /// The implemented trait reference must be well-formed.
+    TraitRef(TraitRef<I>),
+
+    /// The value of an associated type must be well-formed and meet the
+    /// bounds declared in the trait.
+    AssociatedTyValue(AssocTypeId<I>),
+
+    /// The additional requirements of a well-known trait must be met, e.g.
+    /// all fields of a `Copy` type must be `Copy`.
+    WellKnownTrait(WellKnownTrait),
+}
+
+/// An obligation, along with the goal that checks it.
+type WfGoal<I> = (WfObligation<I>, UCanonical<InEnvironment<Goal<I>>>);
+
+impl<I: Interner> fmt::Display for WfObligation<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            WfError::IllFormedTypeDecl(id) => write!(
+            WfObligation::FieldType(ty) => write!(f, "field type `{:?}` must be well-formed", ty),
+            WfObligation::SizedField(ty) => write!(f, "field type `{:?}` must be `Sized`", ty),
+            WfObligation::WhereClause(wc) => write!(
                 f,
-                "type declaration `{:?}` does not meet well-formedness requirements",
-                id
+                "the types in where clause `{:?}` must be well-formed",
+                wc.skip_binders()
+            ),
+            WfObligation::TraitWhereClause(wc) => write!(
+                f,
+                "where clause `{:?}` of the trait must hold",
+                wc.skip_binders()
             ),
-            WfError::IllFormedTraitImpl(id) => write!(
+            WfObligation::TraitRef(trait_ref) => {
+                write!(f, "`{:?}` must be well-formed", trait_ref.with_colon())
+            }
+            WfObligation::AssociatedTyValue(id) => write!(
                 f,
-                "trait impl for `{:?}` does not meet well-formedness requirements",
+                "the value of associated type `{:?}` must be well-formed and meet its bounds",
                 id
             ),
+            WfObligation::WellKnownTrait(well_known) => write!(
+                f,
+                "the requirements of the well-known trait `{:?}` must be met",
+                well_known
+            ),
+        }
+    }
+}
+
+impl<I: Interner> fmt::Display for WfFailure<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let goal = &self.goal.canonical.value.goal;
+        match self.solution {
+            Some(_) => write!(f, "{}, but `{:?}` is ambiguous", self.obligation, goal),
+            None => write!(f, "{}, but `{:?}` cannot be proven", self.obligation, goal),
+        }
+    }
+}
+
+impl<I: Interner> fmt::Display for WfError<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WfError::IllFormedTypeDecl { adt_id, failure } => write!(
+                f,
+                "type declaration `{:?}` does not meet well-formedness requirements: {}",
+                adt_id, failure
+            ),
+            WfError::IllFormedTraitImpl {
+                trait_id,
+                impl_id,
+                failure,
+            } => write!(
+                f,
+                "trait impl for `{:?}` does not meet well-formedness requirements: `{}`: {}",
+                trait_id,
+                impl_id.display(),
+                failure
+            ),
         }
     }
 }
@@ -84,6 +193,7 @@ impl<'i, I: Interner> Visitor<'i, I> for InputTypeCollector<'i, I> {
                 trait_ref.visit_with(self, outer_binder);
             }
             WhereClause::LifetimeOutlives(..) => {}
+            WhereClause::TypeOutlives(TypeOutlives { ty, .. }) => ty.visit_with(self, outer_binder),
         }
     }
 
@@ -147,6 +257,28 @@ where
 
     /// TODO: Currently only handles structs, may need more work for enums & unions
     pub fn verify_adt_decl(&self, adt_id: AdtId<I>) -> Result<(), WfError<I>> {
+        match self.first_failure(self.adt_decl_obligations(adt_id)) {
+            None => Ok(()),
+            Some(failure) => Err(WfError::IllFormedTypeDecl { adt_id, failure }),
+        }
+    }
+
+    pub fn verify_trait_impl(&self, impl_id: ImplId<I>) -> Result<(), WfError<I>> {
+        let trait_id = self.db.impl_datum(impl_id).trait_id();
+
+        match self.first_failure(self.trait_impl_obligations(impl_id)) {
+            None => Ok(()),
+            Some(failure) => Err(WfError::IllFormedTraitImpl {
+                trait_id,
+                impl_id,
+                failure,
+            }),
+        }
+    }
+
+    /// Computes the requirements that the type declaration `adt_id` has
+    /// to meet to be well-formed, along with the goals that check them.
+    fn adt_decl_obligations(&self, adt_id: AdtId<I>) -> Vec<WfGoal<I>> {
         let interner = self.db.interner();
 
         // Given a struct like
@@ -156,149 +288,232 @@ where
         //     data: Vec<T>
         // }
         // ```
-        let struct_datum = self.db.adt_datum(adt_id);
+        //
+        // we prove, for a placeholder `T` and assuming `FromEnv(T: Eq)`,
+        // that `Vec<T>` and the types in `T: Eq` are well-formed.
+        let adt_datum = self.db.adt_datum(adt_id);
+
+        // Only the last field of a struct may be unsized; the fields of
+        // enums and unions must all be sized.
+        let size_all = adt_datum.kind != AdtKind::Struct;
+        let sized_trait = self.db.well_known_trait_id(WellKnownTrait::Sized);
+
+        let mut infer = InferenceTable::new();
+        let AdtDatumBound {
+            variants,
+            where_clauses,
+        } = infer.instantiate_binders_universally(interner, &adt_datum.binders);
+        let environment = Environment::new(interner).add_clauses(
+            interner,
+            where_clauses
+                .iter()
+                .cloned()
+                .map(|wc| wc.into_from_env_goal(interner).cast(interner)),
+        );
 
-        let mut gb = GoalBuilder::new(self.db);
-        let struct_data = struct_datum
-            .binders
-            .map_ref(|b| (&b.fields, &b.where_clauses));
+        let mut obligations = vec![];
+        for variant in &variants {
+            let fields = &variant.fields;
+            for ty in fields {
+                obligations.push((
+                    WfObligation::FieldType(ty.clone()),
+                    well_formed_goal(interner, ty),
+                ));
+            }
+            if let Some(sized_trait) = sized_trait {
+                let num_sized = if size_all {
+                    fields.len()
+                } else {
+                    fields.len().saturating_sub(1)
+                };
+                for ty in &fields[..num_sized] {
+                    let sized_goal = TraitRef {
+                        trait_id: sized_trait,
+                        substitution: Substitution::from1(interner, ty.clone()),
+                    }
+                    .cast(interner);
+                    obligations.push((WfObligation::SizedField(ty.clone()), sized_goal));
+                }
+            }
+        }
+        for wc in &where_clauses {
+            obligations.push((
+                WfObligation::WhereClause(wc.clone()),
+                well_formed_goal(interner, wc),
+            ));
+        }
 
-        // We make a goal like...
-        //
-        // forall<T> { ... }
-        let wg_goal = gb.forall(&struct_data, (), |gb, _, (fields, where_clauses), ()| {
-            let interner = gb.interner();
+        obligations
+            .into_iter()
+            .map(|(obligation, goal)| {
+                let goal = canonicalize_goal(&mut infer, interner, &environment, goal);
+                (obligation, goal)
+            })
+            .collect()
+    }
 
-            // struct is well-formed in terms of Sized
-            let sized_constraint_goal = WfWellKnownGoals::struct_sized_constraint(gb.db(), fields);
-
-            // (FromEnv(T: Eq) => ...)
-            gb.implies(
-                where_clauses
-                    .iter()
-                    .cloned()
-                    .map(|wc| wc.into_from_env_goal(interner)),
-                |gb| {
-                    // WellFormed(Vec<T>), for each field type `Vec<T>` or type that appears in the where clauses
-                    let types =
-                        InputTypeCollector::types_in(gb.interner(), (&fields, &where_clauses));
-
-                    gb.all(
-                        types
-                            .into_iter()
-                            .map(|ty| ty.well_formed().cast(interner))
-                            .chain(sized_constraint_goal.into_iter()),
-                    )
-                },
-            )
-        });
+    /// Computes the requirements that the impl `impl_id` has to meet to be
+    /// well-formed, along with the goals that check them.
+    fn trait_impl_obligations(&self, impl_id: ImplId<I>) -> Vec<WfGoal<I>> {
+        let interner = self.db.interner();
+        let impl_datum = self.db.impl_datum(impl_id);
+        let trait_datum = self.db.trait_datum(impl_datum.trait_id());
 
-        let wg_goal = wg_goal.into_closed_goal(interner);
+        let mut obligations = vec![];
+        if impl_datum.is_positive() {
+            let mut infer = InferenceTable::new();
+            let ImplDatumBound {
+                trait_ref,
+                where_clauses,
+            } = infer.instantiate_binders_universally(interner, &impl_datum.binders);
+            let environment = Environment::new(interner).add_clauses(
+                interner,
+                impl_wf_environment(interner, &where_clauses, &trait_ref),
+            );
+
+            // Things to prove well-formed: input types of the where-clauses,
+            // the trait ref (which includes the where clauses of the trait,
+            // checked on their own first to point at the one that fails),
+            // and the additional requirements of well-known traits.
+            let mut goals = vec![];
+            for wc in &where_clauses {
+                goals.push((
+                    WfObligation::WhereClause(wc.clone()),
+                    well_formed_goal(interner, wc),
+                ));
+            }
+            let trait_where_clauses = trait_datum
+                .binders
+                .map_ref(|bound| bound.where_clauses.clone())
+                .substitute(interner, &trait_ref.substitution);
+            for wc in trait_where_clauses {
+                let goal = wc.clone().into_well_formed_goal(interner).cast(interner);
+                goals.push((WfObligation::TraitWhereClause(wc), goal));
+            }
+            goals.push((
+                WfObligation::TraitRef(trait_ref.clone()),
+                trait_ref.clone().well_formed().cast(interner),
+            ));
+            if let Some(well_known) = trait_datum.well_known {
+                if let Some(goal) = WfWellKnownGoals::inside_impl(self.db, &trait_ref) {
+                    goals.push((WfObligation::WellKnownTrait(well_known), goal));
+                }
+            }
 
-        let is_legal = match self.solver_choice.into_solver().solve(self.db, &wg_goal) {
-            Some(sol) => sol.is_unique(),
-            None => false,
-        };
+            for (obligation, goal) in goals {
+                let goal = canonicalize_goal(&mut infer, interner, &environment, goal);
+                obligations.push((obligation, goal));
+            }
 
-        if !is_legal {
-            Err(WfError::IllFormedTypeDecl(adt_id))
-        } else {
-            Ok(())
+            if let Some(well_known) = trait_datum.well_known {
+                if let Some(goal) = WfWellKnownGoals::outside_impl(self.db, &impl_datum) {
+                    obligations.push((
+                        WfObligation::WellKnownTrait(well_known),
+                        goal.into_peeled_goal(interner),
+                    ));
+                }
+            }
         }
-    }
-
-    pub fn verify_trait_impl(&self, impl_id: ImplId<I>) -> Result<(), WfError<I>> {
-        let interner = self.db.interner();
 
-        let impl_datum = self.db.impl_datum(impl_id);
-        let trait_id = impl_datum.trait_id();
+        for &id in &impl_datum.associated_ty_value_ids {
+            if let Some(goal) = compute_assoc_ty_goal(self.db, id) {
+                let assoc_ty_id = self.db.associated_ty_value(id).associated_ty_id;
+                obligations.push((
+                    WfObligation::AssociatedTyValue(assoc_ty_id),
+                    goal.into_peeled_goal(interner),
+                ));
+            }
+        }
 
-        let impl_goal = Goal::all(
-            interner,
-            impl_header_wf_goal(self.db, impl_id).into_iter().chain(
-                impl_datum
-                    .associated_ty_value_ids
-                    .iter()
-                    .filter_map(|&id| compute_assoc_ty_goal(self.db, id)),
-            ),
-        );
+        debug!("WF trait obligations: {:?}", obligations);
+        obligations
+    }
 
-        debug!("WF trait goal: {:?}", impl_goal);
+    /// Returns the failure of the first of `obligations` that cannot be
+    /// proven, if any.
+    fn first_failure(&self, obligations: Vec<WfGoal<I>>) -> Option<WfFailure<I>> {
+        obligations
+            .into_iter()
+            .find_map(|(obligation, goal)| self.failure(&obligation, goal))
+    }
 
-        let is_legal = match self
-            .solver_choice
-            .into_solver()
-            .solve(self.db, &impl_goal.into_closed_goal(interner))
-        {
+    /// If `goal` (which is how `obligation` is checked) cannot be proven,
+    /// returns a failure pointing to the most specific subgoal that cannot
+    /// be proven, along with its (ambiguous) solution, if any. We only look
+    /// into conjunctions (and quantifiers without binders); other goals are
+    /// reported as is.
+    fn failure(
+        &self,
+        obligation: &WfObligation<I>,
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<WfFailure<I>> {
+        let interner = self.db.interner();
+        let solution = self.solver_choice.into_solver().solve(self.db, &goal);
+        let is_proven = match &solution {
             Some(sol) => sol.is_unique(),
             None => false,
         };
-
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedTraitImpl(trait_id))
+        if is_proven {
+            return None;
         }
-    }
-}
 
-fn impl_header_wf_goal<I: Interner>(
-    db: &dyn RustIrDatabase<I>,
-    impl_id: ImplId<I>,
-) -> Option<Goal<I>> {
-    let impl_datum = db.impl_datum(impl_id);
+        let with_goal = |subgoal: Goal<I>| UCanonical {
+            canonical: Canonical {
+                binders: goal.canonical.binders.clone(),
+                value: InEnvironment::new(&goal.canonical.value.environment, subgoal),
+            },
+            universes: goal.universes,
+        };
+        let subgoal_failure = match goal.canonical.value.goal.data(interner) {
+            GoalData::All(subgoals) => subgoals
+                .iter(interner)
+                .find_map(|subgoal| self.failure(obligation, with_goal(subgoal.clone()))),
+            // `forall<> { G }` is just `G`
+            GoalData::Quantified(_, subgoal) if subgoal.binders.is_empty(interner) => subgoal
+                .skip_binders()
+                .shifted_out(interner)
+                .ok()
+                .and_then(|subgoal| self.failure(obligation, with_goal(subgoal))),
+            _ => None,
+        };
 
-    if !impl_datum.is_positive() {
-        return None;
+        subgoal_failure.or_else(|| {
+            Some(WfFailure {
+                obligation: obligation.clone(),
+                goal,
+                solution,
+            })
+        })
     }
+}
 
-    let impl_fields = impl_datum
-        .binders
-        .map_ref(|v| (&v.trait_ref, &v.where_clauses));
-
-    let mut gb = GoalBuilder::new(db);
-    // forall<P0...Pn> {...}
-    let well_formed_goal = gb.forall(&impl_fields, (), |gb, _, (trait_ref, where_clauses), ()| {
-        let interner = gb.interner();
-
-        let trait_constraint_goal = WfWellKnownGoals::inside_impl(gb.db(), &trait_ref);
-
-        // if (WC && input types are well formed) { ... }
-        gb.implies(
-            impl_wf_environment(interner, &where_clauses, &trait_ref),
-            |gb| {
-                // We retrieve all the input types of the where clauses appearing on the trait impl,
-                // e.g. in:
-                // ```
-                // impl<T, K> Foo for (T, K) where T: Iterator<Item = (HashSet<K>, Vec<Box<T>>)> { ... }
-                // ```
-                // we would retrieve `HashSet<K>`, `Box<T>`, `Vec<Box<T>>`, `(HashSet<K>, Vec<Box<T>>)`.
-                // We will have to prove that these types are well-formed (e.g. an additional `K: Hash`
-                // bound would be needed here).
-                let types = InputTypeCollector::types_in(gb.interner(), &where_clauses);
-
-                // Things to prove well-formed: input types of the where-clauses, projection types
-                // appearing in the header, associated type values, and of course the trait ref.
-                debug!("verify_trait_impl: input_types={:?}", types);
-                let goals = types
-                    .into_iter()
-                    .map(|ty| ty.well_formed().cast(interner))
-                    .chain(Some((*trait_ref).clone().well_formed().cast(interner)))
-                    .chain(trait_constraint_goal.into_iter());
-
-                gb.all::<_, Goal<I>>(goals)
-            },
-        )
-    });
-
-    Some(
-        gb.all(
-            iter::once(well_formed_goal)
-                .chain(WfWellKnownGoals::outside_impl(db, &impl_datum).into_iter()),
-        ),
+/// Creates a goal requiring all the input types in `value` to be well-formed.
+/// Like bound type parameters, the placeholders that stand in for the
+/// generic parameters of a declaration are assumed to be well-formed.
+fn well_formed_goal<I: Interner>(interner: &I, value: impl Visit<I>) -> Goal<I> {
+    Goal::all(
+        interner,
+        InputTypeCollector::types_in(interner, value)
+            .into_iter()
+            .filter(|ty| !matches!(ty.data(interner), TyData::Placeholder(_)))
+            .map(|ty| ty.well_formed().cast(interner)),
     )
 }
 
+/// Puts `goal` in `environment` and canonicalizes it, so that it can be
+/// solved. Both may refer to placeholders created by `infer`.
+fn canonicalize_goal<I: Interner>(
+    infer: &mut InferenceTable<I>,
+    interner: &I,
+    environment: &Environment<I>,
+    goal: Goal<I>,
+) -> UCanonical<InEnvironment<Goal<I>>> {
+    let canonical = infer
+        .canonicalize(interner, &InEnvironment::new(environment, goal))
+        .quantified;
+    infer.u_canonicalize(interner, &canonical).quantified
+}
+
 /// Creates the conditions that an impl (and its contents of an impl)
 /// can assume to be true when proving that it is well-formed.
 fn impl_wf_environment<'i, I: Interner>(
@@ -477,7 +692,8 @@ impl WfWellKnownGoals {
             | WellKnownTrait::FnOnce
             | WellKnownTrait::FnMut
             | WellKnownTrait::Fn
-            | WellKnownTrait::Unsize => None,
+            | WellKnownTrait::Unsize
+            | WellKnownTrait::Generator => None,
         }
     }
 
@@ -498,42 +714,16 @@ impl WfWellKnownGoals {
             | WellKnownTrait::FnOnce
             | WellKnownTrait::FnMut
             | WellKnownTrait::Fn
-            | WellKnownTrait::Unsize => Some(GoalData::CannotProve(()).intern(interner)),
+            | WellKnownTrait::Unsize
+            | WellKnownTrait::Generator => Some(GoalData::CannotProve(()).intern(interner)),
         }
     }
 
-    /// Computes a goal to prove Sized constraints on a struct definition.
-    /// Struct is considered well-formed (in terms of Sized) when it either
-    /// has no fields or all of it's fields except the last are proven to be Sized.
-    pub fn struct_sized_constraint<I: Interner>(
-        db: &dyn RustIrDatabase<I>,
-        fields: &[Ty<I>],
-    ) -> Option<Goal<I>> {
-        if fields.len() <= 1 {
-            return None;
-        }
-
-        let interner = db.interner();
-
-        let sized_trait = db.well_known_trait_id(WellKnownTrait::Sized)?;
-
-        Some(Goal::all(
-            interner,
-            fields[..fields.len() - 1].iter().map(|ty| {
-                TraitRef {
-                    trait_id: sized_trait,
-                    substitution: Substitution::from1(interner, ty.clone()),
-                }
-                .cast(interner)
-            }),
-        ))
-    }
-
     /// Computes a goal to prove constraints on a Copy implementation.
     /// Copy impl is considered well-formed for
     ///    a) certain builtin types (scalar values, shared ref, etc..)
-    ///    b) structs which
-    ///        1) have all Copy fields
+    ///    b) ADTs which
+    ///        1) have all Copy fields (in every variant)
     ///        2) don't have a Drop impl
     fn copy_impl_constraint<I: Interner>(
         db: &dyn RustIrDatabase<I>,
@@ -570,7 +760,7 @@ impl WfWellKnownGoals {
 
         let goals = adt_datum
             .binders
-            .map_ref(|b| &b.fields)
+            .map_ref(|b| b.all_fields().cloned().collect::<Vec<_>>())
             .substitute(interner, substitution)
             .into_iter()
             .map(|f| {
@@ -4,13 +4,19 @@ extern crate serde_derive;
 use std::fs::File;
 use std::io::Read;
 use std::process::exit;
+use std::time::Instant;
 
 use chalk_integration::db::ChalkDatabase;
+use chalk_integration::error::ChalkError;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::*;
 use chalk_integration::query::LoweringDatabase;
+use chalk_integration::Identifier;
+use chalk_ir::{Goal, GoalData, InEnvironment, Substitution, UCanonical};
 use chalk_solve::ext::*;
-use chalk_solve::{RustIrDatabase, SolverChoice};
+use chalk_solve::logging::with_tracing_logs;
+use chalk_solve::rust_ir::AdtKind;
+use chalk_solve::{Guidance, RustIrDatabase, Solution, SolverChoice};
 use docopt::Docopt;
 use rustyline::error::ReadlineError;
 
@@ -27,8 +33,20 @@ Options:
   --help              Show this screen.
   --program=PATH      Specifies the path to the `.chalk` file containing traits/impls.
   --goal=GOAL         Specifies a goal to evaluate (may be given more than once).
+  --goals-file=PATH   Specifies a file of goals to evaluate, one per line. Empty
+                      lines and lines starting with `//` are skipped.
+  --format=F          Specifies how to print solutions, `text` or `json`; with
+                      `json`, one JSON object is printed per goal [default: text].
+  --solver=S          Specifies the solver to use, `slg` or `recursive` [default: slg].
   --overflow-depth=N  Specifies the overflow depth [default: 10].
+  --no-cache          Disables the recursive solver's cache of solved goals.
+  --expected-answers=N
+                      Makes the SLG solver assert that ambiguous goals have
+                      exactly N answers.
   --multiple          Output multiple answers instead of ambiguous solution.
+  --compare-solvers   Solve each goal with both solvers and report where they disagree.
+  --threads=N         Specifies how many threads check the program for coherence
+                      and well-formedness [default: 1].
 ";
 
 /// This struct represents the various command line options available.
@@ -36,24 +54,155 @@ Options:
 struct Args {
     flag_program: Option<String>,
     flag_goal: Vec<String>,
+    flag_goals_file: Option<String>,
+    flag_format: OutputFormat,
+    flag_solver: SolverName,
     flag_overflow_depth: usize,
+    flag_no_cache: bool,
+    flag_expected_answers: Option<usize>,
     flag_multiple: bool,
+    flag_compare_solvers: bool,
+    flag_threads: usize,
+}
+
+/// The solvers that can be chosen with `--solver` or the `solver` command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+enum SolverName {
+    Slg,
+    Recursive,
+}
+
+/// The formats that can be chosen with `--format`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// The outcome of a goal, as printed with `--format=json`.
+#[derive(Debug, Serialize)]
+struct GoalOutput<'a> {
+    goal: &'a str,
+    /// One of `unique`, `ambig-definite`, `ambig-suggested`, `unknown` or
+    /// `none`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution: Option<&'static str>,
+    /// The values of the goal's existential variables, if known.
+    substitution: Vec<Binding>,
+    lifetime_constraints: Vec<String>,
+    /// Why there is no inference guidance, if the solver knows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// The error the goal was rejected with, in which case there is no
+    /// solution.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// How long solving the goal took, in milliseconds.
+    time_ms: f64,
+}
+
+/// A binding `?0 := T` of a substitution.
+#[derive(Debug, Serialize)]
+struct Binding {
+    var: String,
+    value: String,
+}
+
+impl<'a> GoalOutput<'a> {
+    fn new(goal: &'a str) -> Self {
+        GoalOutput {
+            goal,
+            solution: None,
+            substitution: vec![],
+            lifetime_constraints: vec![],
+            reason: None,
+            error: None,
+            time_ms: 0.0,
+        }
+    }
+
+    /// Records `solution`; this needs the program to be set, for the
+    /// names in the substitution.
+    fn set_solution(&mut self, solution: &Option<Solution<ChalkIr>>) {
+        let bindings = |subst: &Substitution<ChalkIr>| -> Vec<Binding> {
+            subst
+                .iter(&ChalkIr)
+                .enumerate()
+                .map(|(index, value)| Binding {
+                    var: format!("?{}", index),
+                    value: format!("{:?}", value),
+                })
+                .collect()
+        };
+        self.solution = Some(match solution {
+            Some(Solution::Unique(constrained)) => {
+                self.substitution = bindings(&constrained.value.subst);
+                self.lifetime_constraints = constrained
+                    .value
+                    .constraints
+                    .iter()
+                    .map(|constraint| {
+                        if constraint.environment.clauses.is_empty(&ChalkIr) {
+                            format!("{:?}", constraint.goal)
+                        } else {
+                            format!("{:?}", constraint)
+                        }
+                    })
+                    .collect();
+                "unique"
+            }
+            Some(Solution::Ambig(Guidance::Definite(subst))) => {
+                self.substitution = bindings(&subst.value);
+                "ambig-definite"
+            }
+            Some(Solution::Ambig(Guidance::Suggested(subst))) => {
+                self.substitution = bindings(&subst.value);
+                "ambig-suggested"
+            }
+            Some(Solution::Ambig(Guidance::Unknown)) => "unknown",
+            Some(Solution::Ambig(Guidance::Floundered(floundered_goal))) => {
+                self.reason = Some(floundered_goal.to_string());
+                "unknown"
+            }
+            Some(Solution::Ambig(Guidance::Overflow(overflow))) => {
+                self.reason = Some(overflow.display(&ChalkIr).to_string());
+                "unknown"
+            }
+            None => "none",
+        });
+    }
+
+    fn print(&self) {
+        println!("{}", serde_json::to_string(self).unwrap());
+    }
 }
 
 /// A loaded and parsed program.
 struct LoadedProgram {
+    path: String,
     text: String,
     db: ChalkDatabase,
 }
 
 impl LoadedProgram {
-    /// Creates a new Program struct, given a `.chalk` file as a String and
-    /// a [`SolverChoice`].
+    /// Creates a new Program struct, given the path and contents of a
+    /// `.chalk` file, a [`SolverChoice`] and the number of threads to check
+    /// the program with.
     ///
     /// [`SolverChoice`]: struct.solve.SolverChoice.html
-    fn new(text: String, solver_choice: SolverChoice) -> Result<LoadedProgram> {
-        let db = ChalkDatabase::with(&text, solver_choice);
-        Ok(LoadedProgram { text, db })
+    fn new(
+        path: &str,
+        text: String,
+        solver_choice: SolverChoice,
+        check_threads: usize,
+    ) -> Result<LoadedProgram> {
+        let mut db = ChalkDatabase::with_path(path, &text, solver_choice);
+        db.set_check_threads(check_threads);
+        Ok(LoadedProgram {
+            path: path.to_string(),
+            text,
+            db,
+        })
     }
 
     /// Parse a goal and attempt to solve it, using the specified solver.
@@ -63,9 +212,7 @@ impl LoadedProgram {
         text: &str,
         multiple_answers: bool,
     ) -> Result<()> {
-        let program = self.db.checked_program()?;
-        let goal = chalk_parse::parse_goal(text)?.lower(&*program)?;
-        let peeled_goal = goal.into_peeled_goal(self.db.interner());
+        let peeled_goal = self.peeled_goal(text)?;
         if multiple_answers {
             if self.db.solve_multiple(&peeled_goal, |v, has_next| {
                 println!("{}\n", v.as_ref().map(|v| v.display(&ChalkIr)));
@@ -101,11 +248,171 @@ impl LoadedProgram {
         }
         Ok(())
     }
+
+    /// Parse a goal and solve it, printing the outcome as a JSON object.
+    /// Returns whether the goal could be solved, that is, whether it was
+    /// valid.
+    fn goal_json(&self, text: &str) -> bool {
+        let mut output = GoalOutput::new(text.trim());
+        let goal = self.db.checked_program().and_then(|program| {
+            Ok(chalk_parse::parse_goal(text)
+                .map_err(ChalkError::from)?
+                .lower(&*program)?)
+        });
+        match goal {
+            Ok(goal) => {
+                let peeled_goal = goal.into_peeled_goal(self.db.interner());
+                let start = Instant::now();
+                let solution = self.db.solve(&peeled_goal);
+                output.time_ms = start.elapsed().as_secs_f64() * 1000.0;
+                output.set_solution(&solution);
+            }
+            Err(error) => output.error = Some(error.to_string()),
+        }
+        output.print();
+        output.error.is_none()
+    }
+
+    /// Parse a goal, and lower it with its outer quantifiers and
+    /// implications peeled off.
+    fn peeled_goal(&self, text: &str) -> Result<UCanonical<InEnvironment<Goal<ChalkIr>>>> {
+        let program = self.db.checked_program()?;
+        let goal = chalk_parse::parse_goal(text)?.lower(&*program)?;
+        Ok(goal.into_peeled_goal(self.db.interner()))
+    }
+
+    /// Parse a goal and print the program clauses that could prove it.
+    fn clauses(&self, text: &str) -> Result<()> {
+        let peeled_goal = self.peeled_goal(text)?;
+        let InEnvironment { environment, goal } = &peeled_goal.canonical.value;
+        let domain_goal = match goal.data(&ChalkIr) {
+            GoalData::DomainGoal(domain_goal) => domain_goal,
+            _ => {
+                return Err(format!(
+                    "`{}` is not a single goal like `T: Trait`, so it has no clauses",
+                    text.trim()
+                )
+                .into())
+            }
+        };
+        match chalk_solve::program_clauses_for_goal(&self.db, environment, domain_goal) {
+            Ok(clauses) if clauses.is_empty() => println!("No clauses.\n"),
+            Ok(clauses) => {
+                for clause in clauses {
+                    println!("{:?}", clause);
+                }
+                println!();
+            }
+            Err(floundered) => println!(
+                "Floundered: the clauses cannot be enumerated without knowing `{:?}`.\n",
+                floundered.ty
+            ),
+        }
+        Ok(())
+    }
+
+    /// Print the impls of the trait with the given name, along with their
+    /// specialization priorities.
+    fn impls(&self, trait_name: &str) -> Result<()> {
+        let program = self.db.checked_program()?;
+        let trait_id = *program
+            .trait_ids
+            .get(&Identifier::from(trait_name))
+            .ok_or_else(|| format!("no trait named `{}`", trait_name))?;
+        let priorities = &self.db.coherence()?[&trait_id];
+        let mut impls = program
+            .impl_data
+            .iter()
+            .filter(|(_, impl_datum)| impl_datum.trait_id() == trait_id)
+            .peekable();
+        if impls.peek().is_none() {
+            println!("No impls.");
+        }
+        for (&impl_id, _) in impls {
+            println!(
+                "{:?} (priority {})",
+                impl_id,
+                priorities.get(impl_id).unwrap_or_default()
+            );
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Print the traits, ADTs, functions and opaque types of the program,
+    /// along with their ids.
+    fn items(&self) -> Result<()> {
+        let program = self.db.checked_program()?;
+        for (name, id) in &program.trait_ids {
+            println!("{:?} trait {}", id.0, name);
+        }
+        for (name, id) in &program.adt_ids {
+            let kind = match program.adt_data[id].kind {
+                AdtKind::Struct => "struct",
+                AdtKind::Enum => "enum",
+                AdtKind::Union => "union",
+            };
+            println!("{:?} {} {}", id.0, kind, name);
+        }
+        for (name, id) in &program.fn_def_ids {
+            println!("{:?} fn {}", id.0, name);
+        }
+        for (name, id) in &program.opaque_ty_ids {
+            println!("{:?} opaque type {}", id.0, name);
+        }
+        println!();
+        Ok(())
+    }
+
+    /// Parse a goal and solve it, explaining why it holds, or why it
+    /// doesn't.
+    fn explain(&self, text: &str) -> Result<()> {
+        let peeled_goal = self.peeled_goal(text)?;
+        match self.db.solve_with_proof(&peeled_goal) {
+            Some((solution, proof)) => {
+                println!("{}", solution.display(&ChalkIr));
+                match proof {
+                    Some(proof) => println!("{}\n", proof.display()),
+                    None => println!("Only unique solutions can be explained.\n"),
+                }
+            }
+            None => {
+                println!("No possible solution.");
+                if let Some(failure) = self.db.explain_failure(&peeled_goal) {
+                    println!("{}", failure.display(&ChalkIr));
+                }
+                println!();
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a goal and solve it with each of the given solvers. Prints the
+    /// solution if they agree, or each solver's solution if they don't, and
+    /// returns whether they agreed.
+    fn compare_solvers(&self, text: &str, solver_choices: &[SolverChoice]) -> Result<bool> {
+        let peeled_goal = self.peeled_goal(text)?;
+        let comparison = self.db.compare_solvers(&peeled_goal, solver_choices);
+        let display = |solution: &Option<Solution<ChalkIr>>| match solution {
+            Some(v) => format!("{}", v.display(&ChalkIr)),
+            None => "No possible solution.".to_string(),
+        };
+        if comparison.agree {
+            println!("{}\n", display(&comparison.solutions[0].1));
+        } else {
+            println!("The solvers disagree on `{}`:", text.trim());
+            for (solver_choice, solution) in &comparison.solutions {
+                println!("  {:?}: {}", solver_choice, display(solution));
+            }
+            println!();
+        }
+        Ok(comparison.agree)
+    }
 }
 
 fn run() -> Result<()> {
     // Parse the command line arguments.
-    let args: &Args = &Docopt::new(USAGE)
+    let mut args: Args = Docopt::new(USAGE)
         .and_then(|d| d.deserialize())
         .unwrap_or_else(|e| e.exit());
 
@@ -114,11 +421,41 @@ fn run() -> Result<()> {
         eprintln!("error: overflow depth must be at least 1");
         exit(1);
     }
+    if args.flag_threads == 0 {
+        eprintln!("error: thread count must be at least 1");
+        exit(1);
+    }
+    if args.flag_format == OutputFormat::Json && (args.flag_multiple || args.flag_compare_solvers) {
+        eprintln!(
+            "error: `--format=json` cannot be combined with `--multiple` or `--compare-solvers`"
+        );
+        exit(1);
+    }
+    if let Err(e) = args.check_solver(args.flag_solver) {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+
+    // Read the goals file, if given.
+    if let Some(path) = &args.flag_goals_file {
+        match std::fs::read_to_string(path) {
+            Ok(text) => args.flag_goal.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with("//"))
+                    .map(str::to_string),
+            ),
+            Err(err) => {
+                eprintln!("error reading goals file: {}", err);
+                exit(1);
+            }
+        }
+    }
 
     // Load the .chalk file, if given.
     let mut prog = None;
     if let Some(program) = &args.flag_program {
-        match load_program(args, program) {
+        match load_program(&args, program) {
             Ok(p) => prog = Some(p),
             Err(err) => {
                 eprintln!("error loading program: {}", err);
@@ -127,10 +464,10 @@ fn run() -> Result<()> {
         }
     }
 
-    if args.flag_goal.is_empty() {
+    if args.flag_goal.is_empty() && args.flag_goals_file.is_none() {
         // The user specified no goal. Enter interactive mode.
         readline_loop(&mut rustyline::Editor::new(), "?- ", |rl, line| {
-            if let Err(e) = process(args, line, rl, &mut prog) {
+            if let Err(e) = with_tracing_logs(|| process(&mut args, line, rl, &mut prog)) {
                 eprintln!("error: {}", e);
             }
         })
@@ -144,9 +481,27 @@ fn run() -> Result<()> {
 
         // Evaluate the goal(s). If any goal returns an error, print the error
         // and exit.
+        let mut disagreements = 0;
+        let mut invalid_goals = 0;
         prog.db.with_program(|_| -> Result<()> {
             for g in &args.flag_goal {
-                if let Err(e) = prog.goal(None, g, args.flag_multiple) {
+                let result = with_tracing_logs(|| {
+                    if args.flag_format == OutputFormat::Json {
+                        // Keep going, so that every goal has an object.
+                        if !prog.goal_json(g) {
+                            invalid_goals += 1;
+                        }
+                        Ok(())
+                    } else if args.flag_compare_solvers {
+                        if !prog.compare_solvers(g, &args.compared_solver_choices())? {
+                            disagreements += 1;
+                        }
+                        Ok(())
+                    } else {
+                        prog.goal(None, g, args.flag_multiple)
+                    }
+                });
+                if let Err(e) = result {
                     eprintln!("error: {}", e);
                     exit(1);
                 }
@@ -154,6 +509,23 @@ fn run() -> Result<()> {
             Ok(())
         })?;
 
+        if invalid_goals > 0 {
+            return Err(format!(
+                "error: {} of {} goals are invalid",
+                invalid_goals,
+                args.flag_goal.len()
+            )
+            .into());
+        }
+        if disagreements > 0 {
+            return Err(format!(
+                "error: the solvers disagree on {} of {} goals",
+                disagreements,
+                args.flag_goal.len()
+            )
+            .into());
+        }
+
         Ok(())
     }
 }
@@ -190,10 +562,9 @@ where
 
 /// Process a single command. `args` is a struct containing the command-line
 /// arguments, and `prog` is a parsed `.chalk` file.
-// TODO: Could we pass in an Options struct or something? The Args struct
-// still has Strings where it should have Enums... (e.g. solver_choice)
+// TODO: Could we pass in an Options struct or something?
 fn process(
-    args: &Args,
+    args: &mut Args,
     command: &str,
     rl: &mut rustyline::Editor<()>,
     prog: &mut Option<LoadedProgram>,
@@ -206,7 +577,12 @@ fn process(
         help()
     } else if command == "program" {
         // Load a .chalk file via stdin, until EOF is found.
-        let chalk_prog = LoadedProgram::new(read_program(rl)?, args.solver_choice())?;
+        let chalk_prog = LoadedProgram::new(
+            "<stdin>",
+            read_program(rl)?,
+            args.solver_choice(),
+            args.flag_threads,
+        )?;
         // Let's do a sanity check before going forward.
         let _ = chalk_prog.db.checked_program()?;
         *prog = Some(chalk_prog);
@@ -222,8 +598,30 @@ fn process(
             Some(level) => std::env::set_var("CHALK_DEBUG", level),
             None => println!("debug <level> set debug level to <level>"),
         }
+    } else if command.starts_with("solver ") {
+        let solver = match command.split_whitespace().nth(1) {
+            Some("slg") => SolverName::Slg,
+            Some("recursive") => SolverName::Recursive,
+            _ => {
+                println!("solver <name> use the `slg` or `recursive` solver");
+                return Ok(());
+            }
+        };
+        args.check_solver(solver)?;
+        args.flag_solver = solver;
+        // Rebuild the database, so that nothing cached by the previous
+        // solver carries over.
+        if let Some(old_prog) = prog.take() {
+            *prog = Some(LoadedProgram::new(
+                &old_prog.path,
+                old_prog.text,
+                args.solver_choice(),
+                args.flag_threads,
+            )?);
+        }
     } else {
-        // The command is either "print", "lowered", or a goal.
+        // The command is either "print", "lowered", "items", one of the
+        // commands that take a goal or trait, or a goal.
 
         // Check that a program has been loaded.
         let prog = prog.as_ref().ok_or(format!(
@@ -239,10 +637,26 @@ fn process(
                 // TODO: Write a line of documentation here.
                 "lowered" => println!("{:#?}", prog.db.environment()),
 
-                // Assume this is a goal.
-                // TODO: Print out "type 'help' to see available commands" if it
-                // fails to parse?
-                _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                // List the items of the program.
+                "items" => prog.items()?,
+
+                _ => match command.split_once(' ') {
+                    // Commands that inspect how a goal or trait is handled.
+                    Some(("clauses", goal)) => prog.clauses(goal)?,
+                    Some(("impls", trait_name)) => prog.impls(trait_name.trim())?,
+                    Some(("explain", goal)) => prog.explain(goal)?,
+
+                    // Assume this is a goal.
+                    // TODO: Print out "type 'help' to see available commands" if it
+                    // fails to parse?
+                    _ if args.flag_format == OutputFormat::Json => {
+                        prog.goal_json(command);
+                    }
+                    _ if args.flag_compare_solvers => {
+                        prog.compare_solvers(command, &args.compared_solver_choices())?;
+                    }
+                    _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                },
             }
             Ok(())
         })?
@@ -252,25 +666,34 @@ fn process(
 }
 
 /// Load the file into a string, and parse it.
-// TODO: Could we pass in an Options struct or something? The Args struct
-// still has Strings where it should have Enums... (e.g. solver_choice)
+// TODO: Could we pass in an Options struct or something?
 fn load_program(args: &Args, filename: &str) -> Result<LoadedProgram> {
     let mut text = String::new();
     File::open(filename)?.read_to_string(&mut text)?;
-    Ok(LoadedProgram::new(text, args.solver_choice())?)
+    Ok(LoadedProgram::new(
+        filename,
+        text,
+        args.solver_choice(),
+        args.flag_threads,
+    )?)
 }
 
 /// Print out help for commands in interpreter mode.
 // TODO: Implement "help <command>" for more info.
 fn help() {
     println!("Commands:");
-    println!("  help          print this output");
-    println!("  program       provide a program via stdin");
-    println!("  load <file>   load program from <file>");
-    println!("  print         print the current program");
-    println!("  lowered       print the lowered program");
-    println!("  <goal>        attempt to solve <goal>");
-    println!("  debug <level> set debug level to <level>");
+    println!("  help            print this output");
+    println!("  program         provide a program via stdin");
+    println!("  load <file>     load program from <file>");
+    println!("  print           print the current program");
+    println!("  lowered         print the lowered program");
+    println!("  items           list the traits, types and fns of the program");
+    println!("  impls <trait>   list the impls of <trait> and their priorities");
+    println!("  <goal>          attempt to solve <goal>");
+    println!("  explain <goal>  solve <goal> and explain why it holds or fails");
+    println!("  clauses <goal>  print the program clauses that could prove <goal>");
+    println!("  debug <level>   set debug level to <level>");
+    println!("  solver <name>   use the `slg` or `recursive` solver");
 }
 
 /// Read a program from the command-line. Stop reading when EOF is read. If
@@ -287,9 +710,42 @@ fn read_program(rl: &mut rustyline::Editor<()>) -> Result<String> {
 
 impl Args {
     fn solver_choice(&self) -> SolverChoice {
-        SolverChoice::SLG {
-            max_size: self.flag_overflow_depth,
-            expected_answers: None,
+        self.solver_choice_for(self.flag_solver)
+    }
+
+    /// The solvers that `--compare-solvers` compares.
+    fn compared_solver_choices(&self) -> [SolverChoice; 2] {
+        [
+            self.solver_choice_for(SolverName::Slg),
+            self.solver_choice_for(SolverName::Recursive),
+        ]
+    }
+
+    /// Checks that the other options can be used with `solver`.
+    fn check_solver(&self, solver: SolverName) -> Result<()> {
+        if solver == SolverName::Recursive {
+            if self.flag_multiple {
+                return Err("the recursive solver cannot be combined with `--multiple`".into());
+            }
+            if self.flag_expected_answers.is_some() {
+                return Err(
+                    "the recursive solver cannot be combined with `--expected-answers`".into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn solver_choice_for(&self, solver: SolverName) -> SolverChoice {
+        match solver {
+            SolverName::Slg => SolverChoice::SLG {
+                max_size: self.flag_overflow_depth,
+                expected_answers: self.flag_expected_answers,
+            },
+            SolverName::Recursive => SolverChoice::Recursive {
+                overflow_depth: self.flag_overflow_depth,
+                caching_enabled: !self.flag_no_cache,
+            },
         }
     }
 }
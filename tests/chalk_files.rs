@@ -0,0 +1,98 @@
+//! Runs the `.chalk` test files in `tests/chalk`; see
+//! `chalk_integration::test_file` for their format.
+//!
+//! ```text
+//! cargo test --test chalk_files -- [--bless] [FILTER...]
+//! ```
+//!
+//! Only the files whose path contains one of the filters are run. With
+//! `--bless`, the expectations that fail are replaced with the actual
+//! output, instead of failing the test.
+
+use chalk_integration::test_file::{find_test_files, TestFile};
+use chalk_solve::logging::with_tracing_logs;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let mut bless = false;
+    let mut filters = vec![];
+    for arg in std::env::args().skip(1) {
+        match &arg[..] {
+            "--bless" => bless = true,
+            // flags meant for the libtest harness, e.g. `--quiet`
+            arg if arg.starts_with('-') => {}
+            _ => filters.push(arg),
+        }
+    }
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("chalk");
+    let files = find_test_files(&dir).unwrap_or_else(|error| {
+        eprintln!("cannot read {}: {}", dir.display(), error);
+        process::exit(1);
+    });
+
+    let mut failed = vec![];
+    let mut passed = 0;
+    for path in files {
+        let name = path.strip_prefix(&dir).unwrap().display().to_string();
+        if !filters.is_empty() && !filters.iter().any(|filter| name.contains(&filter[..])) {
+            continue;
+        }
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let file = match TestFile::parse(&text) {
+            Ok(file) => file,
+            Err(error) => {
+                println!("test {} ... FAILED\n    {}", name, error);
+                failed.push(name);
+                continue;
+            }
+        };
+        let run = with_tracing_logs(|| file.run(&name));
+        if run.passed() {
+            println!("test {} ... ok", name);
+            passed += 1;
+            continue;
+        }
+
+        if bless {
+            if run.can_bless() {
+                std::fs::write(&path, run.bless(&file)).unwrap();
+                println!("test {} ... blessed", name);
+                passed += 1;
+                continue;
+            }
+            println!("test {} ... FAILED (cannot be blessed)", name);
+        } else {
+            println!("test {} ... FAILED", name);
+        }
+        for failure in &run.failures {
+            println!("    {}", failure.to_string().replace('\n', "\n    "));
+        }
+        failed.push(name);
+    }
+
+    println!();
+    if !failed.is_empty() {
+        println!("failures:");
+        for name in &failed {
+            println!("    {}", name);
+        }
+        println!();
+    }
+    println!(
+        "test result: {}. {} passed; {} failed",
+        if failed.is_empty() { "ok" } else { "FAILED" },
+        passed,
+        failed.len()
+    );
+    if !failed.is_empty() {
+        if !bless {
+            println!("\nto update the expectations, run with `--bless`");
+        }
+        process::exit(1);
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the `chalk` binary with `args`, feeding it `input` on stdin.
+fn chalk(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_chalk"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn recursive_solver_rejects_multiple() {
+    let output = chalk(&["--solver=recursive", "--multiple", "--goal=Foo: Bar"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "error: the recursive solver cannot be combined with `--multiple`\n"
+    );
+}
+
+#[test]
+fn recursive_solver_rejects_expected_answers() {
+    let output = chalk(
+        &[
+            "--solver=recursive",
+            "--expected-answers=2",
+            "--goal=Foo: Bar",
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "error: the recursive solver cannot be combined with `--expected-answers`\n"
+    );
+}
+
+#[test]
+fn repl_rejects_recursive_solver_with_multiple() {
+    let output = chalk(&["--multiple"], "solver recursive\n");
+    assert_eq!(
+        stderr(&output),
+        "error: the recursive solver cannot be combined with `--multiple`\n"
+    );
+}
@@ -133,6 +133,21 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
         unimplemented!()
     }
 
+    fn closure_datum(&self, closure_id: ClosureId<ChalkIr>) -> Arc<ClosureDatum<ChalkIr>> {
+        unimplemented!()
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        unimplemented!()
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        unimplemented!()
+    }
+
     // All `Bar` impls
     fn impls_for_trait(
         &self,
@@ -163,6 +178,13 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
         unimplemented!()
     }
 
+    fn well_known_assoc_type_id(
+        &self,
+        well_known_assoc_type: WellKnownAssocType,
+    ) -> Option<AssocTypeId<ChalkIr>> {
+        unimplemented!()
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &Environment<ChalkIr>,
@@ -182,11 +204,25 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
         &ChalkIr
     }
 
+    fn unification_database(&self) -> &dyn UnificationDatabase<ChalkIr> {
+        self
+    }
+
     fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
         unimplemented!()
     }
 }
 
+impl UnificationDatabase<ChalkIr> for MockDatabase {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
+        unimplemented!()
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
+        unimplemented!()
+    }
+}
+
 fn prepare_goal() -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
     use chalk_integration::interner;
     use chalk_ir::*;
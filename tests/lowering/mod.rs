@@ -1,6 +1,8 @@
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::query::LoweringDatabase;
+use chalk_integration::Identifier;
 use chalk_solve::SolverChoice;
+use std::sync::Arc;
 
 #[test]
 fn lower_success() {
@@ -696,3 +698,190 @@ fn phantom_data() {
         }
     }
 }
+
+#[test]
+fn enums_and_unions() {
+    lowering_success! {
+        program {
+            enum Void {}
+
+            enum Option<T> {
+                None,
+                Some { value: T }
+            }
+
+            #[upstream]
+            enum Either<L, R> {
+                Left { left: L },
+                Right { right: R }
+            }
+
+            union Bits<T> {
+                value: T,
+                raw: u32
+            }
+        }
+    }
+}
+
+#[test]
+fn closures() {
+    lowering_success! {
+        program {
+            closure foo(self) {}
+            closure bar(&self, a: u32) -> u32 { u32 }
+            closure baz<T, 'a>(&mut self, t: T) -> &'a T { T, &'a T }
+        }
+    }
+
+    lowering_error! {
+        program {
+            closure foo<T>(&self) {}
+            struct S { f: foo }
+        }
+        error_msg {
+            "`foo` takes 1 type parameters, not 0"
+        }
+    }
+}
+
+#[test]
+fn generators() {
+    lowering_success! {
+        program {
+            generator foo[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+            generator bar<T, 'a>[resume = T, yield = &'a T] -> T {
+                upvars [T, &'a T]
+                witnesses exists<'b> [&'b T]
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            generator foo<T>[resume = T, yield = ()] {
+                upvars []
+                witnesses []
+            }
+            struct S { f: foo }
+        }
+        error_msg {
+            "`foo` takes 1 type parameters, not 0"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(generator)]
+            trait Generator<R> {
+                type Yield;
+            }
+        }
+        error_msg {
+            "lang item trait `Generator` must define the associated type `Return`"
+        }
+    }
+}
+
+#[test]
+fn variance() {
+    lowering_success! {
+        program {
+            #[variance(Covariant, Invariant, Contravariant)]
+            struct Foo<'a, T, U> { }
+            #[variance(Covariant)]
+            #[upstream]
+            enum Bar<T> { }
+            #[variance(Contravariant)]
+            fn baz<T>(t: T);
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[variance(Covariant)]
+            struct Foo<T, U> { }
+        }
+        error_msg {
+            "`Foo` has 2 generic parameters, but 1 variances were given"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[variance(Covariant, Covariant)]
+            fn foo<T>(t: T);
+        }
+        error_msg {
+            "`foo` has 1 generic parameters, but 2 variances were given"
+        }
+    }
+}
+
+#[test]
+fn item_spans() {
+    let text = "struct Foo { }\ntrait Bar { type Item; }\nimpl Bar for Foo { type Item = Foo; }\n";
+    let db = ChalkDatabase::with(text, SolverChoice::default());
+    db.with_program(|program| {
+        let span_text = |id| {
+            let span = program.item_span(id).unwrap();
+            &text[span.lo..span.hi]
+        };
+        let trait_id = program.trait_ids[&Identifier::from("Bar")];
+        let impl_id = *program.impl_data.keys().next().unwrap();
+        let assoc_ty_id = *program.associated_ty_data.keys().next().unwrap();
+        let assoc_ty_value_id = *program.associated_ty_values.keys().next().unwrap();
+        assert_eq!(span_text(trait_id.0), "trait Bar { type Item; }");
+        assert_eq!(
+            span_text(impl_id.0),
+            "impl Bar for Foo { type Item = Foo; }"
+        );
+        assert_eq!(span_text(assoc_ty_id.0), "type Item;");
+        assert_eq!(span_text(assoc_ty_value_id.0), "type Item = Foo;");
+    });
+}
+
+#[test]
+fn error_locations() {
+    let db = ChalkDatabase::with_path(
+        "foo.chalk",
+        "struct Foo { }\ntrait Bar { }\nimpl Bar for Baz { }\n",
+        SolverChoice::default(),
+    );
+    assert_eq!(
+        db.program_ir().unwrap_err().to_string(),
+        "invalid parameter name `Baz`\n \
+         --> foo.chalk:3:14\n  \
+         |\n\
+         3 | impl Bar for Baz { }\n  \
+         |              ^^^"
+    );
+
+    let db = ChalkDatabase::with_path(
+        "foo.chalk",
+        "#[lang(copy)]\ntrait Copy { }\nstruct Foo { f: Bar }\nstruct Bar { }\nimpl Copy for Foo { }\n",
+        SolverChoice::default(),
+    );
+    let error = db.checked_program().unwrap_err().to_string();
+    assert!(
+        error.ends_with(
+            " --> foo.chalk:5:1\n  \
+             |\n\
+             5 | impl Copy for Foo { }\n  \
+             | ^^^^^^^^^^^^^^^^^^^^^"
+        ),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn error_locations_default_path() {
+    let mut db = ChalkDatabase::default();
+    db.set_program_text(Arc::new("impl Bar for Baz { }".to_string()));
+    let error = db.program_ir().unwrap_err().to_string();
+    assert!(error.contains(" --> <input>:1:6"), "{}", error);
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+/// Solves `goal` in `program` with the given solver, which must find an
+/// ambiguous solution, and renders the candidates behind the ambiguity.
+fn candidates(program: &str, goal: &str, solver_choice: SolverChoice) -> Vec<String> {
+    with_goal(program, goal, solver_choice, |db, goal| {
+        let (solution, candidates) = db.solve_with_candidates(goal).unwrap();
+        assert!(!solution.is_unique());
+        candidates
+            .iter()
+            .map(|candidate| candidate.display(&ChalkIr).to_string())
+            .collect()
+    })
+}
+
+#[test]
+fn ambiguity_candidate_impls() {
+    let program = "
+        trait Foo { }
+        impl Foo for u32 { }
+        impl Foo for i64 { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            candidates(program, "exists<T> { T: Foo }", solver_choice),
+            vec!["[?0 := Uint(U32)]", "[?0 := Int(I64)]"]
+        );
+    }
+}
+
+#[test]
+fn ambiguity_candidates_past_trivial_guidance() {
+    let program = "
+        trait Foo { }
+        trait Bar { }
+        struct Vec<T> { }
+        impl Foo for u32 { }
+        impl Foo for i64 { }
+        impl Foo for u8 { }
+        impl Bar for Vec<u32> { }
+        impl Bar for Vec<i64> { }
+        impl Bar for Vec<u8> { }
+    ";
+    for solver_choice in solver_choices() {
+        let mut foo = candidates(program, "exists<T> { T: Foo }", solver_choice);
+        foo.sort();
+        assert_eq!(
+            foo,
+            vec!["[?0 := Int(I64)]", "[?0 := Uint(U32)]", "[?0 := Uint(U8)]"]
+        );
+
+        let mut bar = candidates(program, "exists<T> { T: Bar }", solver_choice);
+        bar.sort();
+        assert_eq!(
+            bar,
+            vec![
+                "[?0 := Vec<Int(I64)>]",
+                "[?0 := Vec<Uint(U32)>]",
+                "[?0 := Vec<Uint(U8)>]"
+            ]
+        );
+    }
+}
+
+#[test]
+fn ambiguity_candidates_skip_floundered_answers() {
+    let program = "
+        struct Foo { }
+
+        #[non_enumerable]
+        trait NonEnumerable { }
+        impl NonEnumerable for Foo { }
+    ";
+    for solver_choice in solver_choices() {
+        assert!(candidates(program, "exists<T> { T: NonEnumerable }", solver_choice).is_empty());
+    }
+}
+
+#[test]
+fn ambiguity_candidate_obligations() {
+    let program = "
+        trait Foo { }
+        trait Bar { }
+        struct Vec<T> { }
+        impl Bar for u32 { }
+        impl Bar for i64 { }
+        impl<T> Foo for Vec<T> where T: Bar { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            candidates(program, "exists<T> { Vec<T>: Foo }", solver_choice),
+            vec!["[?0 := Uint(U32)]", "[?0 := Int(I64)]"]
+        );
+        assert_eq!(
+            candidates(program, "exists<T> { T: Foo }", solver_choice),
+            vec!["[?0 := Vec<Uint(U32)>]", "[?0 := Vec<Int(I64)>]"]
+        );
+    }
+}
+
+#[test]
+fn ambiguity_candidates_unique() {
+    let program = "
+        trait Foo { }
+        impl Foo for u32 { }
+    ";
+    with_goal(
+        program,
+        "exists<T> { T: Foo }",
+        SolverChoice::default(),
+        |db, goal| {
+            let (solution, candidates) = db.solve_with_candidates(goal).unwrap();
+            assert!(solution.is_unique());
+            assert!(candidates.is_empty());
+        },
+    );
+}
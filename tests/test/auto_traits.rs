@@ -53,6 +53,60 @@ fn auto_semantics() {
     }
 }
 
+#[test]
+fn auto_trait_enums_and_unions() {
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            struct Rc { }
+            impl !Send for Rc { }
+
+            struct Ok { }
+
+            enum Either<L, R> {
+                Left { left: L },
+                Right { right: R }
+            }
+
+            union U<T> {
+                value: T
+            }
+        }
+
+        goal {
+            Either<Ok, Ok>: Send
+        } yields {
+            "Unique"
+        }
+
+        // Every field of every variant must implement the auto trait
+        goal {
+            Either<Ok, Rc>: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Either<Rc, Ok>: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            U<Ok>: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            U<Rc>: Send
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
 #[test]
 fn auto_trait_without_impls() {
     test! {
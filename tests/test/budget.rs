@@ -0,0 +1,120 @@
+use super::*;
+use chalk_solve::{BudgetExceeded, Guidance, SolveBudget};
+use std::time::{Duration, Instant};
+
+const PROGRAM: &str = "
+    trait A { }
+    trait B { }
+    trait C { }
+    struct Z { }
+    struct S<X> { }
+
+    impl A for Z { }
+    impl<X> A for S<X> where X: B { }
+    impl<X> B for S<X> where X: C { }
+    impl B for Z { }
+    impl C for Z { }
+";
+
+const GOAL: &str = "S<S<Z>>: A";
+
+#[test]
+fn budget_large_enough_solves_goal() {
+    for solver_choice in solver_choices() {
+        with_goal(PROGRAM, GOAL, solver_choice, |db, goal| {
+            let budget = SolveBudget {
+                max_strands: Some(1000),
+                max_tables: Some(1000),
+                max_clause_instantiations: Some(1000),
+                deadline: None,
+            };
+            let solution = db.solve_with_budget(goal, budget).unwrap();
+            assert!(solution.unwrap().is_unique());
+        });
+    }
+}
+
+#[test]
+fn budget_limits_strands_tables_and_clauses() {
+    let budgets = [
+        (
+            SolveBudget {
+                max_strands: Some(1),
+                ..SolveBudget::default()
+            },
+            BudgetExceeded::Strands,
+        ),
+        (
+            SolveBudget {
+                max_tables: Some(1),
+                ..SolveBudget::default()
+            },
+            BudgetExceeded::Tables,
+        ),
+        (
+            SolveBudget {
+                max_clause_instantiations: Some(1),
+                ..SolveBudget::default()
+            },
+            BudgetExceeded::ClauseInstantiations,
+        ),
+    ];
+    for solver_choice in solver_choices() {
+        for &(budget, exceeded) in &budgets {
+            with_goal(PROGRAM, GOAL, solver_choice, |db, goal| {
+                assert_eq!(db.solve_with_budget(goal, budget), Err(exceeded));
+            });
+        }
+    }
+}
+
+#[test]
+fn budget_deadline_stops_solving() {
+    for solver_choice in solver_choices() {
+        with_goal(PROGRAM, GOAL, solver_choice, |db, goal| {
+            let budget = SolveBudget {
+                deadline: Some(Instant::now()),
+                ..SolveBudget::default()
+            };
+            assert_eq!(
+                db.solve_with_budget(goal, budget),
+                Err(BudgetExceeded::Deadline)
+            );
+
+            let budget = SolveBudget::with_timeout(Duration::from_secs(60));
+            assert!(db
+                .solve_with_budget(goal, budget)
+                .unwrap()
+                .unwrap()
+                .is_unique());
+        });
+    }
+}
+
+#[test]
+fn budget_exceeded_solve_can_be_resumed() {
+    for solver_choice in solver_choices() {
+        with_goal(PROGRAM, GOAL, solver_choice, |db, goal| {
+            let budget = SolveBudget {
+                max_strands: Some(2),
+                ..SolveBudget::default()
+            };
+            assert_eq!(
+                db.solve_with_budget(goal, budget),
+                Err(BudgetExceeded::Strands)
+            );
+            assert!(db.solve(goal).unwrap().is_unique());
+        });
+    }
+}
+
+#[test]
+fn solve_limited_cancels_recursive_solver() {
+    with_goal(PROGRAM, GOAL, SolverChoice::recursive(), |db, goal| {
+        assert_eq!(
+            db.solve_limited(goal, || false),
+            Some(Solution::Ambig(Guidance::Unknown))
+        );
+        assert!(db.solve_limited(goal, || true).unwrap().is_unique());
+    });
+}
@@ -74,6 +74,45 @@ fn tuples_are_sized() {
     }
 }
 
+#[test]
+fn enums_and_unions_are_sized() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            enum Void {}
+
+            enum Option<T> where T: Sized {
+                None,
+                Some { value: T }
+            }
+
+            union U<T> where T: Sized {
+                value: T
+            }
+        }
+
+        goal {
+            Void: Sized
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<T> { Option<T>: Sized }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<T> { U<T>: Sized }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
 #[test]
 fn functions_are_sized() {
     test! {
@@ -7,25 +7,19 @@ fn dyn_to_dyn_unsizing() {
             #[lang(unsize)]
             trait Unsize<T> {}
 
-            #[object_safe]
             trait Principal {}
-            #[object_safe]
             trait OtherPrincipal {}
-            #[object_safe]
             trait GenericPrincipal<T> {
                 type Item;
             }
 
             #[auto]
-            #[object_safe]
             trait Auto1 {}
 
             #[auto]
-            #[object_safe]
             trait Auto2 {}
 
             #[auto]
-            #[object_safe]
             trait Auto3 {}
         }
 
@@ -153,17 +147,14 @@ fn ty_to_dyn_unsizing() {
             #[lang(sized)]
             trait Sized {}
 
-            #[object_safe]
             trait Principal {}
-            #[object_safe]
             trait GenericPrincipal<T> {
                 type Item;
             }
 
-            trait UnsafePrincipal {}
+            trait UnsafePrincipal where Self: Sized {}
 
             #[auto]
-            #[object_safe]
             trait Auto {}
 
             struct Foo {}
@@ -329,9 +320,7 @@ fn tuple_unsizing() {
 
             struct Foo {}
 
-            #[object_safe]
             trait Principal {}
-            #[object_safe]
             trait OtherPrincipal {}
 
             impl Principal for Foo {}
@@ -481,9 +470,7 @@ fn struct_unsizing() {
                 t2: SConst<N, T>
             }
 
-            #[object_safe]
             trait Principal {}
-            #[object_safe]
             trait OtherPrincipal {}
 
             impl Principal for Foo {}
@@ -0,0 +1,253 @@
+use super::*;
+
+#[test]
+fn closure_is_well_formed() {
+    test! {
+        program {
+            closure foo(self) {}
+            closure bar<T>(&self, a: T) -> T { T }
+        }
+
+        goal {
+            WellFormed(foo)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                WellFormed(bar<T>)
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn closure_is_sized() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            closure foo(self) {}
+        }
+
+        goal {
+            foo: Sized
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn closure_implements_fn_traits() {
+    test! {
+        program {
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+
+            #[lang(fn_mut)]
+            trait FnMut<Args> where Self: FnOnce<Args> { }
+
+            #[lang(fn)]
+            trait Fn<Args> where Self: FnMut<Args> { }
+
+            closure foo(&self) {}
+            closure bar(&self, a: u8, b: f32) -> u32 {}
+            closure baz(&mut self, a: u8) {}
+            closure buz(self) {}
+            closure foobar<T>(&self, t: T) -> T {}
+        }
+
+        // A closure of kind `Fn` implements all of the `Fn` traits
+        goal {
+            foo: Fn<()>
+        } yields {
+            "Unique"
+        }
+        goal {
+            foo: FnMut<()>
+        } yields {
+            "Unique"
+        }
+        goal {
+            foo: FnOnce<()>
+        } yields {
+            "Unique"
+        }
+        goal {
+            bar: Fn<(u8, f32)>
+        } yields {
+            "Unique"
+        }
+        goal {
+            bar: Fn<(u8,)>
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            Normalize(<bar as FnOnce<(u8, f32)>>::Output -> u32)
+        } yields {
+            "Unique"
+        }
+
+        // A closure of kind `FnMut` only implements `FnMut` and `FnOnce`
+        goal {
+            baz: Fn<(u8,)>
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            baz: FnMut<(u8,)>
+        } yields {
+            "Unique"
+        }
+        goal {
+            baz: FnOnce<(u8,)>
+        } yields {
+            "Unique"
+        }
+
+        // A closure of kind `FnOnce` only implements `FnOnce`
+        goal {
+            buz: Fn<()>
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            buz: FnMut<()>
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            buz: FnOnce<()>
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<foobar<T> as FnOnce<(T,)>>::Output -> T)
+            }
+        } yields {
+            "Unique"
+        }
+        goal {
+            exists<T> {
+                Normalize(<foobar<u32> as FnOnce<(u32,)>>::Output -> T)
+            }
+        } yields {
+            "Unique; substitution [?0 := Uint(U32)], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn closure_copy_and_clone_depend_on_upvars() {
+    test! {
+        program {
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(clone)]
+            trait Clone { }
+
+            struct IsCopy { }
+            impl Copy for IsCopy { }
+            impl Clone for IsCopy { }
+
+            struct NotCopy { }
+
+            closure foo(&self) {}
+            closure bar(&self) { IsCopy, IsCopy }
+            closure baz(&self) { IsCopy, NotCopy }
+            closure foobar<T>(&self) { T }
+        }
+
+        goal {
+            foo: Copy
+        } yields {
+            "Unique"
+        }
+        goal {
+            bar: Copy
+        } yields {
+            "Unique"
+        }
+        goal {
+            baz: Copy
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            baz: Clone
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            foobar<IsCopy>: Clone
+        } yields {
+            "Unique"
+        }
+        goal {
+            forall<T> {
+                foobar<T>: Copy
+            }
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            forall<T> {
+                if (T: Copy) {
+                    foobar<T>: Copy
+                }
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn closure_auto_traits_depend_on_upvars() {
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            struct Rc { }
+            impl !Send for Rc { }
+
+            struct Foo { }
+
+            closure foo(&self) {}
+            closure bar(&self) { Foo, Rc }
+            closure baz<T>(self) { T }
+        }
+
+        goal {
+            foo: Send
+        } yields {
+            "Unique"
+        }
+        goal {
+            bar: Send
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            baz<Foo>: Send
+        } yields {
+            "Unique"
+        }
+        goal {
+            baz<Rc>: Send
+        } yields {
+            "No possible solution"
+        }
+    }
+}
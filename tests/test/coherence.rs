@@ -1,4 +1,6 @@
+use chalk_integration::db::ChalkDatabase;
 use chalk_integration::query::LoweringDatabase;
+use chalk_solve::SolverChoice;
 
 #[test]
 fn two_impls_for_same_type() {
@@ -10,7 +12,7 @@ fn two_impls_for_same_type() {
             impl Foo for Bar { }
         }
         error_msg {
-            "overlapping impls of trait `Foo`"
+            "overlapping impls of trait `Foo`: `impl Foo for Bar` and `impl Foo for Bar` both apply to `Bar: Foo`"
         }
     }
 
@@ -22,7 +24,24 @@ fn two_impls_for_same_type() {
             impl Foo for Bar<3> { }
         }
         error_msg {
-            "overlapping impls of trait `Foo`"
+            "overlapping impls of trait `Foo`: `impl Foo for Bar<3>` and `impl Foo for Bar<3>` both apply to `Bar<3>: Foo`"
+        }
+    }
+}
+
+#[test]
+fn overlapping_impls_witness() {
+    lowering_error! {
+        program {
+            trait Foo<T> { }
+            struct Vec<T> { }
+            struct Bar { }
+            impl<T> Foo<T> for Vec<Bar> { }
+            impl<U> Foo<Bar> for Vec<U> { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`: `impl<type> Foo<^0.0> for Vec<Bar>` and \
+             `impl<type> Foo<Bar> for Vec<^0.0>` both apply to `Vec<Bar>: Foo<Bar>`"
         }
     }
 }
@@ -319,7 +338,7 @@ fn orphan_check() {
 
             impl Foo for Bar { }
         } error_msg {
-            "impl for trait `Foo` violates the orphan rules"
+            "impl for trait `Foo` violates the orphan rules: `impl Foo for Bar`: none of its input types is local"
         }
     }
 
@@ -340,7 +359,8 @@ fn orphan_check() {
 
             impl<T> Foo<Bar> for T { }
         } error_msg {
-            "impl for trait `Foo` violates the orphan rules"
+            "impl for trait `Foo` violates the orphan rules: `impl<type> Foo<Bar> for ^0.0`: \
+             input type 0 mentions an uncovered type parameter, but comes before the first local input type 1"
         }
     }
 
@@ -480,3 +500,50 @@ fn orphan_check() {
         }
     }
 }
+
+#[test]
+fn parallel_coherence_reports_the_first_error() {
+    let program = "
+        trait Foo { }
+        trait Bar { }
+        trait Baz { }
+        struct S { }
+        struct T { }
+        impl Foo for S { }
+        impl Bar for S { }
+        impl Bar for S { }
+        impl Baz for T { }
+        impl Baz for T { }
+    ";
+    let check = |threads| {
+        let mut db = ChalkDatabase::with(program, SolverChoice::default());
+        db.set_check_threads(threads);
+        db.coherence().unwrap_err().to_string()
+    };
+
+    let expected = check(1);
+    assert!(expected.contains("trait `Bar`"), "{}", expected);
+    for threads in 2..=4 {
+        assert_eq!(check(threads), expected);
+    }
+}
+
+#[test]
+fn parallel_coherence_computes_the_same_priorities() {
+    let program = "
+        #[upstream] trait Foo { }
+        trait Bar { }
+        struct Vec<T> { }
+        struct S { }
+        impl<T> Foo for Vec<T> { }
+        impl Foo for Vec<S> { }
+        impl Bar for S { }
+    ";
+    let check = |threads| {
+        let mut db = ChalkDatabase::with(program, SolverChoice::default());
+        db.set_check_threads(threads);
+        db.coherence().unwrap()
+    };
+
+    assert_eq!(check(4), check(1));
+}
@@ -0,0 +1,71 @@
+use super::*;
+use chalk_solve::SolverComparison;
+
+const PROGRAM: &str = "
+    trait C1orC2 { }
+
+    #[coinductive]
+    trait C1 { }
+
+    #[coinductive]
+    trait C2 { }
+
+    #[coinductive]
+    trait C3 { }
+
+    forall<T> {
+        T: C1 if T: C2, T: C3
+    }
+
+    forall<T> {
+        T: C2 if T: C1
+    }
+
+    forall<T> {
+        T: C1orC2 if T: C1
+    }
+
+    forall<T> {
+        T: C1orC2 if T: C2
+    }
+
+    trait Foo { }
+    struct S { }
+    struct Vec<T> { }
+    impl Foo for S { }
+    impl<T> Foo for Vec<T> where T: Foo { }
+";
+
+fn compare(goal: &str) -> SolverComparison<ChalkIr> {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    let peeled_goal = goal.into_peeled_goal(db.interner());
+    db.with_program(|_| {
+        db.compare_solvers(
+            &peeled_goal,
+            &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        )
+    })
+}
+
+#[test]
+fn solvers_agree() {
+    for goal in &["Vec<S>: Foo", "exists<T> { T: Foo }", "Vec<u32>: Foo"] {
+        let comparison = compare(goal);
+        assert!(comparison.agree, "{:?}", comparison);
+        assert_eq!(comparison.solutions.len(), 2);
+    }
+}
+
+#[test]
+fn solvers_disagree() {
+    // FIXME(chalk#399) recursive solver doesn't handle coinduction correctly
+    let comparison = compare("forall<X> { X: C1orC2 }");
+    assert!(!comparison.agree);
+    let (slg, slg_solution) = &comparison.solutions[0];
+    let (recursive, recursive_solution) = &comparison.solutions[1];
+    assert_eq!(*slg, SolverChoice::slg_default());
+    assert_eq!(*slg_solution, None);
+    assert_eq!(*recursive, SolverChoice::recursive());
+    assert!(recursive_solution.as_ref().unwrap().is_unique());
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+/// Solves `goal` in `program` with the given solver, which must find no
+/// solution, and renders the explanation of why it fails.
+fn failure(program: &str, goal: &str, solver_choice: SolverChoice) -> String {
+    with_goal(program, goal, solver_choice, |db, goal| {
+        let failure = db.explain_failure(goal).unwrap();
+        failure.display(&ChalkIr).to_string()
+    })
+}
+
+#[test]
+fn failure_nearest_candidate() {
+    let program = "
+        trait Copy { }
+        trait Bar { }
+        struct Baz<T> { }
+        struct Foo { }
+        struct Qux { }
+        impl Copy for Foo { }
+        impl<T> Bar for Baz<T> where T: Copy { }
+        impl Bar for Qux { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            failure(program, "Baz<u32>: Bar", solver_choice),
+            "no impl of `Bar` matches `Baz<Uint(U32)>`; nearest candidate \
+             `impl<type> Bar for Baz<^0.0>` failed on `Implemented(Uint(U32): Copy)`\n    \
+             no impl of `Copy` matches `Uint(U32)`"
+        );
+        assert_eq!(
+            failure(program, "Baz<Baz<u32>>: Bar", solver_choice),
+            "no impl of `Bar` matches `Baz<Baz<Uint(U32)>>`; nearest candidate \
+             `impl<type> Bar for Baz<^0.0>` failed on `Implemented(Baz<Uint(U32)>: Copy)`\n    \
+             no impl of `Copy` matches `Baz<Uint(U32)>`"
+        );
+        assert_eq!(
+            failure(program, "Foo: Bar", solver_choice),
+            "no impl of `Bar` matches `Foo`"
+        );
+    }
+}
+
+#[test]
+fn failure_subgoals_and_leaves() {
+    let program = "
+        trait Copy { }
+        trait Clone { }
+        struct Foo { }
+        struct Vec<T> { }
+        impl Copy for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone, T: Copy { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            failure(
+                program,
+                "exists<T> { T = Foo, Vec<T>: Clone }",
+                solver_choice
+            ),
+            "`all((Foo = Foo), Implemented(Vec<Foo>: Clone))` cannot be proven\n    \
+             no impl of `Clone` matches `Vec<Foo>`; nearest candidate \
+             `impl<type> Clone for Vec<^0.0>` failed on `Implemented(Foo: Clone)`\n        \
+             no impl of `Clone` matches `Foo`"
+        );
+        assert_eq!(
+            failure(program, "forall<T> { Vec<T>: Clone }", solver_choice),
+            "no impl of `Clone` matches `Vec<!1_0>`; nearest candidate \
+             `impl<type> Clone for Vec<^0.0>` failed on \
+             `Implemented(!1_0: Clone)`, `Implemented(!1_0: Copy)`\n    \
+             no impl of `Clone` matches `!1_0`\n    \
+             no impl of `Copy` matches `!1_0`"
+        );
+    }
+
+    let leaves: Vec<_> = with_goal(
+        program,
+        "Vec<Vec<Foo>>: Clone",
+        SolverChoice::default(),
+        |db, goal| {
+            let failure = db.explain_failure(goal).unwrap();
+            failure
+                .leaves()
+                .iter()
+                .map(|leaf| format!("{:?}", leaf.goal))
+                .collect()
+        },
+    );
+    assert_eq!(
+        leaves,
+        vec!["Implemented(Foo: Clone)", "Implemented(Vec<Foo>: Copy)"]
+    );
+}
+
+#[test]
+fn failure_not_explained_for_solvable_goals() {
+    let program = "
+        trait Clone { }
+        struct Foo { }
+        impl Clone for Foo { }
+    ";
+    with_goal(
+        program,
+        "Foo: Clone",
+        SolverChoice::default(),
+        |db, goal| {
+            assert!(db.explain_failure(goal).is_none());
+        },
+    );
+}
@@ -0,0 +1,87 @@
+use super::*;
+use chalk_solve::Guidance;
+
+#[test]
+fn floundering_reports_non_enumerable_goal() {
+    test! {
+        program {
+            struct Foo { }
+
+            #[non_enumerable]
+            trait NonEnumerable { }
+            impl NonEnumerable for Foo { }
+        }
+
+        goal {
+            exists<T> { T: NonEnumerable }
+        } yields {
+            "Ambiguous; no inference guidance; \
+             `Implemented(^0.0: NonEnumerable)` floundered on unresolved `^0.0`"
+        }
+    }
+}
+
+#[test]
+fn floundering_reports_subgoal() {
+    test! {
+        program {
+            struct Foo { }
+
+            #[non_enumerable]
+            trait NonEnumerable { }
+            impl NonEnumerable for Foo { }
+
+            trait Bar { }
+            impl<T> Bar for T where T: NonEnumerable { }
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields {
+            "Ambiguous; no inference guidance; \
+             `Implemented(^0.0: NonEnumerable)` floundered on unresolved `^0.0`"
+        }
+    }
+}
+
+#[test]
+fn floundering_reports_negative_goal() {
+    test! {
+        program {
+            trait A { }
+
+            struct Vec<T> { }
+            impl<T> A for Vec<T> { }
+        }
+
+        goal {
+            exists<T> { not { T: A } }
+        } yields {
+            "Ambiguous; no inference guidance; \
+             `not { Implemented(^0.0: A) }` floundered on unresolved `^0.0`"
+        }
+    }
+}
+
+#[test]
+fn floundering_guidance_is_structured() {
+    let program = "
+        struct Foo { }
+
+        #[non_enumerable]
+        trait NonEnumerable { }
+        impl NonEnumerable for Foo { }
+    ";
+    for solver_choice in solver_choices() {
+        let goal = "exists<T> { T: NonEnumerable }";
+        with_goal(program, goal, solver_choice, |db, goal| {
+            match db.solve(goal) {
+                Some(Solution::Ambig(Guidance::Floundered(floundered))) => {
+                    assert_eq!(floundered.goal, goal.canonical);
+                    assert_eq!(floundered.variable, Some(0));
+                }
+                solution => panic!("expected floundering, got {:?}", solution),
+            }
+        });
+    }
+}
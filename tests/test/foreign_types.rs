@@ -0,0 +1,84 @@
+use super::*;
+
+#[test]
+fn foreign_ty_trait_impl() {
+    test! {
+        program {
+            extern type A;
+            trait Foo {}
+            impl Foo for A {}
+        }
+
+        goal { A: Foo } yields { "Unique" }
+    }
+}
+
+#[test]
+fn foreign_ty_lowering() {
+    lowering_success! {
+        program {
+            extern type A;
+            struct Foo { field: A }
+        }
+    }
+}
+
+#[test]
+fn foreign_ty_is_well_formed() {
+    test! {
+        program {
+            extern type A;
+        }
+
+        goal { WellFormed(A) } yields { "Unique" }
+    }
+}
+
+#[test]
+fn foreign_ty_is_not_sized() {
+    test! {
+        program {
+            #[lang(sized)] trait Sized {}
+            extern type A;
+        }
+
+        goal { not { A: Sized } } yields { "Unique" }
+    }
+}
+
+#[test]
+fn foreign_ty_is_not_sized_in_adt() {
+    test! {
+        program {
+            #[lang(sized)] trait Sized {}
+            extern type A;
+            struct S { a: A }
+        }
+
+        goal { not { S: Sized } } yields { "Unique" }
+    }
+}
+
+#[test]
+fn foreign_ty_auto_traits() {
+    test! {
+        program {
+            #[auto] trait Send {}
+            #[auto] trait Sync {}
+            extern type A;
+            extern type B;
+            impl Send for A {}
+            impl !Sync for B {}
+            struct S { a: A }
+        }
+
+        // Foreign types only implement auto traits through explicit impls
+        goal { A: Send } yields { "Unique" }
+        goal { not { A: Sync } } yields { "Unique" }
+        goal { not { B: Send } } yields { "Unique" }
+        goal { not { B: Sync } } yields { "Unique" }
+
+        goal { S: Send } yields { "Unique" }
+        goal { not { S: Sync } } yields { "Unique" }
+    }
+}
@@ -0,0 +1,237 @@
+use super::*;
+
+#[test]
+fn generator_is_well_formed() {
+    test! {
+        program {
+            generator foo[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+            generator bar<T>[resume = T, yield = T] -> T {
+                upvars [T]
+                witnesses []
+            }
+        }
+
+        goal {
+            WellFormed(foo)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                WellFormed(bar<T>)
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn generator_is_sized() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            generator foo[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            foo: Sized
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn generator_test() {
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            #[lang(generator)]
+            trait Generator<R> {
+                type Yield;
+                type Return;
+            }
+
+            struct StructOne {}
+            struct NotSend {}
+            struct SendSameLifetime<'a, 'b, T> { field: T }
+            impl<'a, T> Send for SendSameLifetime<'a, 'a, T> {}
+
+            struct SendAnyLifetime<'a, 'b, T> { field: T }
+
+            impl !Send for NotSend {}
+
+            generator empty_gen[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+
+            generator upvar_lifetime_restrict<T>[resume = (), yield = ()] {
+                upvars [T, StructOne]
+                witnesses exists<'a, 'b> [SendSameLifetime<'a, 'b, T>]
+            }
+
+            generator send_any_lifetime<T>[resume = (), yield = ()] {
+                upvars []
+                witnesses exists<'a, 'b> [SendAnyLifetime<'a, 'b, T>, StructOne]
+            }
+
+            generator not_send_resume_yield[resume = NotSend, yield = NotSend] {
+                upvars []
+                witnesses []
+            }
+
+            generator gen_with_types<T>[resume = T, yield = StructOne] -> NotSend {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            WellFormed(empty_gen)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            empty_gen: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            empty_gen: Generator<()>
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                gen_with_types<T>: Generator<T>
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<gen_with_types<T> as Generator<T>>::Yield -> StructOne)
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<gen_with_types<T> as Generator<T>>::Return -> NotSend)
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<gen_with_types<T> as Generator<T>>::Return -> StructOne)
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // The witness only implements `Send` if its existential
+        // lifetimes are equal
+        goal {
+            forall<T> {
+                if (T: Send) {
+                    upvar_lifetime_restrict<T>: Send
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([for<> FromEnv(!1_0: Send)]), goal: '!2_0: '!2_1 }, \
+            InEnvironment { environment: Env([for<> FromEnv(!1_0: Send)]), goal: '!2_1: '!2_0 }\
+            ]"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Send) {
+                    send_any_lifetime<T>: Send
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                send_any_lifetime<T>: Send
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // Resume and yield types do not affect auto traits
+        goal {
+            not_send_resume_yield: Send
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn generator_upvars_affect_auto_traits() {
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            struct Foo { }
+            struct NotSend { }
+            impl !Send for NotSend { }
+
+            generator send_gen[resume = (), yield = ()] {
+                upvars [Foo]
+                witnesses []
+            }
+
+            generator not_send_gen[resume = (), yield = ()] {
+                upvars [Foo, NotSend]
+                witnesses []
+            }
+
+            generator not_send_witness_gen[resume = (), yield = ()] {
+                upvars [Foo]
+                witnesses [NotSend]
+            }
+        }
+
+        goal {
+            send_gen: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            not_send_gen: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            not_send_witness_gen: Send
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -4,7 +4,10 @@ use chalk_integration::db::ChalkDatabase;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::LowerGoal;
 use chalk_integration::query::LoweringDatabase;
+use chalk_integration::test_file::{check_solutions, solve_test_goal, Mismatch, TestGoal};
+use chalk_ir::{Goal, InEnvironment, UCanonical};
 use chalk_solve::ext::*;
+use chalk_solve::logging::with_tracing_logs;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::{Solution, SolverChoice};
 
@@ -15,35 +18,6 @@ mod bench;
 mod coherence;
 mod wf_lowering;
 
-fn assert_result(mut result: Option<Solution<ChalkIr>>, expected: &str) {
-    // sort constraints, since the different solvers may output them in different order
-    match &mut result {
-        Some(Solution::Unique(solution)) => {
-            solution
-                .value
-                .constraints
-                .sort_by_key(|c| format!("{:?}", c));
-        }
-        _ => {}
-    }
-    let result = match result {
-        Some(v) => format!("{}", v.display(&ChalkIr)),
-        None => format!("No possible solution"),
-    };
-
-    assert_same(&result, expected);
-}
-
-// different goals
-enum TestGoal {
-    // solver should produce same aggregated single solution
-    Aggregated(&'static str),
-    // solver should produce exactly multiple solutions
-    All(Vec<&'static str>),
-    // solver should produce first same multiple solutions
-    First(Vec<&'static str>),
-}
-
 macro_rules! test {
     (program $program:tt $($goals:tt)*) => {
         test!(@program[$program]
@@ -206,7 +180,7 @@ macro_rules! test {
     };
 }
 
-fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
+fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal<&str>)>) {
     println!("program {}", program_text);
     assert!(program_text.starts_with("{"));
     assert!(program_text.ends_with("}"));
@@ -231,80 +205,73 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>) {
             db.set_solver_choice(solver_choice);
         }
 
-        chalk_integration::tls::set_current_program(&program, || {
-            println!("----------------------------------------------------------------------");
-            println!("goal {}", goal_text);
-            assert!(goal_text.starts_with("{"));
-            assert!(goal_text.ends_with("}"));
-            let goal = chalk_parse::parse_goal(&goal_text[1..goal_text.len() - 1])
-                .unwrap()
-                .lower(&*program)
-                .unwrap();
+        with_tracing_logs(|| {
+            chalk_integration::tls::set_current_program(&program, || {
+                println!("----------------------------------------------------------------------");
+                println!("goal {}", goal_text);
+                assert!(goal_text.starts_with("{"));
+                assert!(goal_text.ends_with("}"));
+                let goal = chalk_parse::parse_goal(&goal_text[1..goal_text.len() - 1])
+                    .unwrap()
+                    .lower(&*program)
+                    .unwrap();
 
-            println!("using solver: {:?}", solver_choice);
-            let peeled_goal = goal.into_peeled_goal(db.interner());
-            match expected {
-                TestGoal::Aggregated(expected) => {
-                    let result = db.solve(&peeled_goal);
-                    assert_result(result, expected);
+                println!("using solver: {:?}", solver_choice);
+                let peeled_goal = goal.into_peeled_goal(db.interner());
+                let solutions = solve_test_goal(&db, &peeled_goal, &expected);
+                match check_solutions(&solutions, &expected) {
+                    Ok(()) => {}
+                    Err(Mismatch::Solution {
+                        expected, actual, ..
+                    }) => assert_same(&actual, &expected),
+                    Err(mismatch) => panic!("{}", mismatch),
                 }
-                TestGoal::All(expected) => {
-                    let mut expected = expected.into_iter();
-                    assert!(
-                        db.solve_multiple(&peeled_goal, |result, next_result| {
-                            match expected.next() {
-                                Some(expected) => {
-                                    assert_same(
-                                        &format!(
-                                            "{}",
-                                            result.as_ref().map(|v| v.display(&ChalkIr))
-                                        ),
-                                        expected,
-                                    );
-                                }
-                                None => {
-                                    assert!(!next_result, "Unexpected next solution");
-                                }
-                            }
-                            true
-                        }),
-                        "Not all solutions processed"
-                    );
-                    if expected.next().is_some() {
-                        panic!("Not all solutions processed");
-                    }
-                }
-                TestGoal::First(expected) => {
-                    let mut expected = expected.into_iter();
-                    db.solve_multiple(&peeled_goal, |result, next_result| match expected.next() {
-                        Some(solution) => {
-                            assert_same(
-                                &format!("{}", result.as_ref().map(|v| v.display(&ChalkIr))),
-                                solution,
-                            );
-                            if !next_result {
-                                assert!(expected.next().is_none(), "Not enough solutions found");
-                            }
-                            true
-                        }
-                        None => false,
-                    });
-                    assert!(expected.next().is_none(), "Not enough solutions found");
-                }
-            }
+            })
         });
     }
 }
 
+/// The solvers that tests of solver output besides solutions are run
+/// with; these are the two that `test!` runs each goal with.
+fn solver_choices() -> Vec<SolverChoice> {
+    vec![SolverChoice::slg_default(), SolverChoice::recursive()]
+}
+
+/// Checks `program` with the default solver, like `test!` does, then calls
+/// `op` with the database set up to use `solver_choice` and with `goal`
+/// lowered in it. The program is in TLS while `op` runs, so that anything
+/// it renders refers to items by name.
+fn with_goal<R>(
+    program: &str,
+    goal: &str,
+    solver_choice: SolverChoice,
+    op: impl FnOnce(&mut ChalkDatabase, &UCanonical<InEnvironment<Goal<ChalkIr>>>) -> R,
+) -> R {
+    let mut db = ChalkDatabase::with(program, SolverChoice::default());
+    let program = db.checked_program().unwrap();
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    db.set_solver_choice(solver_choice);
+    let peeled_goal = goal.into_peeled_goal(db.interner());
+    chalk_integration::tls::set_current_program(&program, || op(&mut db, &peeled_goal))
+}
+
+mod ambiguity_candidates;
 mod arrays;
 mod auto_traits;
+mod budget;
 mod builtin_impls;
+mod closures;
 mod coherence_goals;
 mod coinduction;
+mod compare_solvers;
 mod constants;
 mod cycle;
 mod existential_types;
+mod failure_explanations;
+mod floundering;
 mod fn_def;
+mod foreign_types;
+mod generators;
 mod implied_bounds;
 mod impls;
 mod misc;
@@ -313,11 +280,19 @@ mod never;
 mod numerics;
 mod object_safe;
 mod opaque_types;
+mod overflow;
 mod projection;
+mod proof_trees;
 mod refs;
 mod scalars;
 mod slices;
+mod solver_reuse;
+mod stats;
 mod string;
+mod subtype;
+mod test_files;
+mod tracing_events;
 mod tuples;
+mod type_outlives;
 mod unify;
 mod wf_goals;
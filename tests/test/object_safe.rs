@@ -1,15 +1,69 @@
 use super::*;
+use chalk_integration::Identifier;
+use chalk_solve::object_safety::{object_safety_violations, ObjectSafetyViolation};
 
 #[test]
-fn object_safe_flag() {
+fn object_safe_computed() {
     test! {
         program {
-            #[object_safe]
+            #[lang(sized)]
+            trait Sized {}
+
             trait Foo {}
-            trait Bar {}
+            trait Generic<T> where Self: Foo {}
+            trait SizedSelf where Self: Sized {}
+            trait SupertraitSelf where Self: Generic<Self> {}
+            trait SupertraitProjectionSelf where Self: Iterator<Item = Self> {}
+            trait Iterator { type Item; }
+            trait Gat { type Item<T>; }
+            trait Sub where Self: SizedSelf {}
+            trait Cycle1 where Self: Cycle2 {}
+            trait Cycle2 where Self: Cycle1 {}
         }
 
         goal { ObjectSafe(Foo) } yields { "Unique" }
-        goal { not { ObjectSafe(Bar) } } yields { "Unique" }
+        goal { ObjectSafe(Generic) } yields { "Unique" }
+        goal { ObjectSafe(Iterator) } yields { "Unique" }
+        goal { ObjectSafe(SupertraitProjectionSelf) } yields { "Unique" }
+        goal { ObjectSafe(Cycle1) } yields { "Unique" }
+        goal { not { ObjectSafe(SizedSelf) } } yields { "Unique" }
+        goal { not { ObjectSafe(SupertraitSelf) } } yields { "Unique" }
+        goal { not { ObjectSafe(Gat) } } yields { "Unique" }
+        goal { not { ObjectSafe(Sub) } } yields { "Unique" }
     }
 }
+
+#[test]
+fn object_safety_violation_list() {
+    let db = ChalkDatabase::with(
+        "
+        #[lang(sized)]
+        trait Sized {}
+        trait Foo {}
+        trait Bar<T> {}
+        trait Baz where Self: Sized, Self: Bar<Self> { type Item<T>; }
+        trait Sub where Self: Baz {}
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.checked_program().unwrap();
+    let foo = program.trait_ids[&Identifier::from("Foo")];
+    let baz = program.trait_ids[&Identifier::from("Baz")];
+    let sub = program.trait_ids[&Identifier::from("Sub")];
+    let item = program
+        .associated_ty_data
+        .values()
+        .find(|a| a.trait_id == baz)
+        .unwrap()
+        .id;
+
+    assert_eq!(object_safety_violations(&db, foo), vec![]);
+    assert_eq!(
+        object_safety_violations(&db, sub),
+        vec![
+            ObjectSafetyViolation::SizedSelf(baz),
+            ObjectSafetyViolation::SupertraitSelf(baz),
+            ObjectSafetyViolation::GenericAssociatedType(baz, item),
+        ]
+    );
+}
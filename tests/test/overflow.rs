@@ -0,0 +1,119 @@
+use super::*;
+use chalk_solve::{Guidance, OverflowLimit};
+
+/// Solves `goal` in `program` with the given solver.
+fn solve(program: &str, goal: &str, solver_choice: SolverChoice) -> Solution<ChalkIr> {
+    with_goal(program, goal, solver_choice, |db, goal| {
+        db.solve(goal).unwrap()
+    })
+}
+
+/// Like `solve`, but renders the solution.
+fn solve_to_string(program: &str, goal: &str, solver_choice: SolverChoice) -> String {
+    with_goal(program, goal, solver_choice, |db, goal| {
+        db.solve(goal).unwrap().display(&ChalkIr).to_string()
+    })
+}
+
+const GROWING_PROGRAM: &str = "
+    trait Q { }
+    struct Z { }
+    struct G<X> { }
+    struct S<X> { }
+
+    impl Q for Z { }
+    impl<X> Q for G<X> where X: Q { }
+    impl<X> Q for S<X> where X: Q, S<G<X>>: Q { }
+";
+
+const DEEP_PROGRAM: &str = "
+    trait A { }
+    trait B { }
+    trait C { }
+    struct Z { }
+    struct S<X> { }
+
+    impl A for Z { }
+    impl<X> A for S<X> where X: B { }
+    impl<X> B for S<X> where X: C { }
+    impl B for Z { }
+    impl C for Z { }
+";
+
+#[test]
+fn overflow_reports_growing_goal() {
+    for solver_choice in [SolverChoice::slg(10, None), SolverChoice::recursive()] {
+        let solution = solve_to_string(GROWING_PROGRAM, "S<Z>: Q", solver_choice);
+        assert!(
+            solution.starts_with("Ambiguous; no inference guidance; overflow: "),
+            "{}",
+            solution
+        );
+        assert!(
+            solution.contains("via `Implemented(S<Z>: Q)` -> "),
+            "{}",
+            solution
+        );
+        assert!(
+            solution.ends_with(
+                "; the same predicate keeps recurring on larger types, \
+                 so raising the limit is unlikely to help"
+            ),
+            "{}",
+            solution
+        );
+    }
+}
+
+#[test]
+fn overflow_reports_exceeded_limit() {
+    let solution = solve(GROWING_PROGRAM, "S<Z>: Q", SolverChoice::slg(10, None));
+    match solution {
+        Solution::Ambig(Guidance::Overflow(overflow)) => {
+            assert_eq!(overflow.limit, OverflowLimit::MaxSize(10));
+            assert!(overflow.is_recursive(&ChalkIr));
+        }
+        solution => panic!("expected overflow, got {:?}", solution),
+    }
+
+    let solution = solve(GROWING_PROGRAM, "S<Z>: Q", SolverChoice::recursive());
+    match solution {
+        Solution::Ambig(Guidance::Overflow(overflow)) => {
+            assert_eq!(overflow.limit, OverflowLimit::MaxSize(30));
+        }
+        solution => panic!("expected overflow, got {:?}", solution),
+    }
+}
+
+#[test]
+fn overflow_depth_suggests_raising_limit() {
+    let shallow = SolverChoice::Recursive {
+        overflow_depth: 3,
+        caching_enabled: true,
+    };
+    let solution = solve_to_string(DEEP_PROGRAM, "S<S<Z>>: A", shallow);
+    assert!(
+        solution.starts_with("Ambiguous; no inference guidance; overflow: "),
+        "{}",
+        solution
+    );
+    assert!(
+        solution.contains("exceeded the overflow depth of 3 via `Implemented(S<S<Z>>: A)` -> `Implemented(S<Z>: B)` -> `Implemented(Z: C)`"),
+        "{}",
+        solution
+    );
+    assert!(
+        solution.ends_with("; try raising the limit above 3"),
+        "{}",
+        solution
+    );
+
+    let deeper = SolverChoice::Recursive {
+        overflow_depth: 4,
+        caching_enabled: true,
+    };
+    assert_eq!(
+        solve_to_string(DEEP_PROGRAM, "S<S<Z>>: A", deeper),
+        "Unique; substitution [], lifetime constraints []"
+    );
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+/// Solves `goal` in `program` with the given solver and renders the proof
+/// tree explaining why it holds.
+fn proof_tree(program: &str, goal: &str, solver_choice: SolverChoice) -> String {
+    with_goal(program, goal, solver_choice, |db, goal| {
+        let (solution, proof) = db.solve_with_proof(goal).unwrap();
+        assert!(solution.is_unique());
+        proof.unwrap().display().to_string()
+    })
+}
+
+#[test]
+fn proof_tree_impls() {
+    let program = "
+        trait Clone { }
+        struct Foo { }
+        struct Vec<T> { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            proof_tree(program, "Vec<Vec<Foo>>: Clone", solver_choice),
+            "`Implemented(Vec<Vec<Foo>>: Clone)` because of `impl<type> Clone for Vec<^0.0>`\n    \
+             `Implemented(Vec<Foo>: Clone)` because of `impl<type> Clone for Vec<^0.0>`\n        \
+             `Implemented(Foo: Clone)` because of `impl Clone for Foo`"
+        );
+    }
+}
+
+#[test]
+fn proof_tree_existentials_and_assumptions() {
+    let program = "
+        trait Clone { }
+        trait Eq { }
+        struct Foo { }
+        struct Vec<T> { }
+        impl Clone for Foo { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+    ";
+    for solver_choice in solver_choices() {
+        assert_eq!(
+            proof_tree(program, "exists<T> { T = Foo, Vec<T>: Clone }", solver_choice),
+            "`all((Foo = Foo), Implemented(Vec<Foo>: Clone))` because all of its subgoals hold\n    \
+             `Implemented(Vec<Foo>: Clone)` because of `impl<type> Clone for Vec<^0.0>`\n        \
+             `Implemented(Foo: Clone)` because of `impl Clone for Foo`"
+        );
+        assert_eq!(
+            proof_tree(program, "forall<T> { if (T: Eq) { T: Eq } }", solver_choice),
+            "`Implemented(!1_0: Eq)` because of assumption \
+             `for<type> Implemented(^0.0: Eq) :- FromEnv(^0.0: Eq)`\n    \
+             `FromEnv(!1_0: Eq)` because of assumption `for<> FromEnv(!1_0: Eq)`"
+        );
+    }
+}
+
+#[test]
+fn proof_tree_ambiguous() {
+    let program = "
+        trait Clone { }
+        struct Foo { }
+        struct Bar { }
+        impl Clone for Foo { }
+        impl Clone for Bar { }
+    ";
+    with_goal(
+        program,
+        "exists<T> { T: Clone }",
+        SolverChoice::default(),
+        |db, goal| {
+            let (solution, proof) = db.solve_with_proof(goal).unwrap();
+            assert!(!solution.is_unique());
+            assert!(proof.is_none());
+        },
+    );
+}
+
+#[test]
+fn proof_tree_negation() {
+    let program = "
+        trait Clone { }
+        struct Foo { }
+        struct Bar { }
+        impl Clone for Foo { }
+    ";
+    let goal = "Foo: Clone, not { Bar: Clone }";
+    let root = "`all(Implemented(Foo: Clone), not { Implemented(Bar: Clone) })` \
+                because all of its subgoals hold";
+    let impl_proof = "`Implemented(Foo: Clone)` because of `impl Clone for Foo`";
+    let negation_proof =
+        "`not { Implemented(Bar: Clone) }` because the negated goal has no solution";
+
+    // Subgoals are listed in the order they were proven: SLG leaves
+    // negative subgoals for last, the recursive solver does not.
+    assert_eq!(
+        proof_tree(program, goal, SolverChoice::slg_default()),
+        format!("{}\n    {}\n    {}", root, impl_proof, negation_proof)
+    );
+    assert_eq!(
+        proof_tree(program, goal, SolverChoice::recursive()),
+        format!("{}\n    {}\n    {}", root, negation_proof, impl_proof)
+    );
+}
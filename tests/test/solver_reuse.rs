@@ -0,0 +1,75 @@
+use super::*;
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    trait A { }
+    trait B { }
+    struct Z { }
+    struct S<X> { }
+
+    impl A for Z { }
+    impl<X> A for S<X> where X: B { }
+    impl B for Z { }
+    impl B for S<Z> { }
+";
+
+/// Replaces the program text of `db`, checks the new program with the
+/// default solver, like `test!` does, and returns `goal` lowered against it.
+fn edit(
+    db: &mut ChalkDatabase,
+    solver_choice: SolverChoice,
+    program_text: &str,
+    goal: &str,
+) -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
+    db.set_program_text(Arc::new(program_text.to_string()));
+    db.set_solver_choice(SolverChoice::default());
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    db.set_solver_choice(solver_choice);
+    goal.into_peeled_goal(db.interner())
+}
+
+#[test]
+fn slg_tables_survive_unrelated_edits() {
+    let mut db = ChalkDatabase::default();
+    db.set_collect_stats(true);
+    let goal = edit(&mut db, SolverChoice::slg_default(), PROGRAM, "S<Z>: A");
+    assert!(db.solve(&goal).unwrap().is_unique());
+    assert!(db.last_solve_stats().tables_created >= 2);
+
+    // The clauses for `S<Z>: A` and `Z: B` don't come from the second
+    // impl of `B`, so their tables are still valid.
+    let program = PROGRAM.replace("impl B for S<Z> { }", "impl B for S<Z> where Z: A { }");
+    let goal = edit(&mut db, SolverChoice::slg_default(), &program, "S<Z>: A");
+    assert!(db.solve(&goal).unwrap().is_unique());
+    assert_eq!(db.last_solve_stats().tables_created, 0);
+}
+
+#[test]
+fn slg_tables_are_invalidated_by_related_edits() {
+    let mut db = ChalkDatabase::default();
+    db.set_collect_stats(true);
+    let goal = edit(&mut db, SolverChoice::slg_default(), PROGRAM, "S<Z>: A");
+    assert!(db.solve(&goal).unwrap().is_unique());
+
+    // `S<Z>: A` used the answers of `Z: B`, so changing the impl behind
+    // them invalidates both tables.
+    let program = PROGRAM.replace("impl B for Z { }", "impl B for Z where Z: B { }");
+    let goal = edit(&mut db, SolverChoice::slg_default(), &program, "S<Z>: A");
+    assert_eq!(db.solve(&goal), None);
+    assert!(db.last_solve_stats().tables_created >= 2);
+}
+
+#[test]
+fn solvers_are_recreated_when_types_change() {
+    for solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        let mut db = ChalkDatabase::default();
+        db.set_collect_stats(true);
+        let goal = edit(&mut db, *solver_choice, PROGRAM, "S<Z>: A");
+        assert!(db.solve(&goal).unwrap().is_unique());
+
+        let program = PROGRAM.replace("struct Z { }", "struct Z { s: S<Z> }");
+        let goal = edit(&mut db, *solver_choice, &program, "S<Z>: A");
+        assert!(db.solve(&goal).unwrap().is_unique());
+        assert_eq!(db.solver_stats(), db.last_solve_stats());
+    }
+}
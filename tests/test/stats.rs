@@ -0,0 +1,84 @@
+use super::*;
+use chalk_solve::SolverStats;
+
+const PROGRAM: &str = "
+    trait A { }
+    trait B { }
+    struct Z { }
+    struct S<X> { }
+
+    impl A for Z { }
+    impl<X> A for S<X> where X: B { }
+    impl B for Z { }
+";
+
+#[test]
+fn stats_count_slg_work() {
+    with_goal(
+        PROGRAM,
+        "S<Z>: A",
+        SolverChoice::slg_default(),
+        |db, goal| {
+            db.set_collect_stats(true);
+            assert!(db.solve(goal).unwrap().is_unique());
+
+            let stats = db.last_solve_stats();
+            assert!(stats.tables_created >= 2, "{}", stats);
+            assert!(stats.strands_processed >= 2, "{}", stats);
+            assert!(stats.answers_produced >= 2, "{}", stats);
+            assert!(stats.clauses_by_goal_kind["Implemented"] >= 2, "{}", stats);
+            assert_eq!(stats.cache_hits + stats.cache_misses, 0);
+            assert_eq!(db.solver_stats(), stats);
+        },
+    );
+}
+
+#[test]
+fn stats_count_recursive_cache_hits() {
+    with_goal(PROGRAM, "S<Z>: A", SolverChoice::recursive(), |db, goal| {
+        db.set_collect_stats(true);
+        assert!(db.solve(goal).unwrap().is_unique());
+        let first = db.last_solve_stats();
+        assert!(first.tables_created >= 2, "{}", first);
+        assert!(first.strands_processed >= 1, "{}", first);
+        assert!(first.answers_produced >= 2, "{}", first);
+        assert!(first.cache_misses >= 2, "{}", first);
+        assert!(first.clauses_by_goal_kind["Implemented"] >= 2, "{}", first);
+
+        // The second time around, the goal comes straight from the cache.
+        assert!(db.solve(goal).unwrap().is_unique());
+        let second = db.last_solve_stats();
+        assert_eq!(second.cache_hits, 1);
+        assert_eq!(second.tables_created, 0);
+
+        let mut total = first;
+        total.add(&second);
+        assert_eq!(db.solver_stats(), total);
+    });
+}
+
+#[test]
+fn stats_include_nested_solves() {
+    for solver_choice in solver_choices() {
+        with_goal(PROGRAM, "S<S<Z>>: A", solver_choice, |db, goal| {
+            db.set_collect_stats(true);
+            assert!(db.explain_failure(goal).is_some());
+
+            // The solves done to build the explanation are part of the
+            // statistics of `explain_failure` itself.
+            assert_eq!(db.solver_stats(), db.last_solve_stats());
+            assert!(db.last_solve_stats().clauses_generated() >= 2);
+        });
+    }
+}
+
+#[test]
+fn stats_are_off_by_default() {
+    for solver_choice in solver_choices() {
+        with_goal(PROGRAM, "S<Z>: A", solver_choice, |db, goal| {
+            assert!(db.solve(goal).unwrap().is_unique());
+            assert_eq!(db.solver_stats(), SolverStats::default());
+            assert_eq!(db.last_solve_stats(), SolverStats::default());
+        });
+    }
+}
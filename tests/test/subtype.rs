@@ -0,0 +1,165 @@
+use super::*;
+
+#[test]
+fn subtype_simple() {
+    test! {
+        program {
+            struct Foo { }
+        }
+
+        goal {
+            Subtype(Foo, Foo)
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            exists<T> {
+                Subtype(Foo, T)
+            }
+        } yields {
+            "Unique; substitution [?0 := Foo], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn subtype_refs() {
+    test! {
+        program {
+            struct Foo { }
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(&'a Foo, &'b Foo)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(&'a mut &'a Foo, &'b mut &'b Foo)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                exists<T> {
+                    Subtype(T, &'a Foo)
+                }
+            }
+        } yields {
+            "Unique; for<?U1> { substitution [?0 := Not<'^0.0, Foo>], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }"
+        }
+    }
+}
+
+#[test]
+fn subtype_higher_ranked_fn() {
+    test! {
+        program {
+            struct Foo { }
+        }
+
+        goal {
+            forall<'x> {
+                Subtype(for<'a> fn(&'a Foo), fn(&'x Foo))
+            }
+        } yields {
+            "Unique; for<?U2> { substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }] }"
+        }
+
+        goal {
+            forall<'x> {
+                Subtype(fn(&'x Foo), for<'a> fn(&'a Foo))
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }]"
+        }
+
+        goal {
+            forall<'x> {
+                Subtype(fn(&'x Foo) -> &'x Foo, fn(&'x Foo) -> &'x Foo)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn subtype_adt_variance() {
+    test! {
+        program {
+            #[variance(Covariant)]
+            struct Co<'a> { }
+            #[variance(Contravariant)]
+            struct Contra<'a> { }
+            struct Inv<'a> { }
+            #[variance(Covariant, Invariant)]
+            struct Mixed<T, U> { }
+            struct Foo { }
+            struct Bar { }
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Co<'a>, Co<'b>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Contra<'a>, Contra<'b>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Inv<'a>, Inv<'b>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Mixed<&'a Foo, &'a Foo>, Mixed<&'b Foo, &'b Foo>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"
+        }
+
+        goal {
+            Subtype(Mixed<Foo, Foo>, Mixed<Foo, Bar>)
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn subtype_fn_def_variance() {
+    test! {
+        program {
+            #[variance(Covariant)]
+            fn foo<'a>(a: &'a ());
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(foo<'a>, foo<'b>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests of the `.chalk` test file format, see
+//! `chalk_integration::test_file`; the files themselves are in
+//! `tests/chalk`.
+
+use chalk_integration::test_file::{ParseError, TestFile};
+
+const PROGRAM: &str = "
+struct Foo { }
+struct Bar { }
+trait Clone { }
+impl Clone for Foo { }
+";
+
+fn run(annotations: &str) -> (TestFile, Vec<String>) {
+    let file = TestFile::parse(&format!("{}{}", PROGRAM, annotations)).unwrap();
+    let run = file.run("<test>");
+    let failures = run.failures.iter().map(|f| f.to_string()).collect();
+    (file, failures)
+}
+
+#[test]
+fn passing_goals() {
+    let (_, failures) = run(r#"
+//~ goal { Foo: Clone } yields { "Unique" }
+//~ goal { Bar: Clone } yields[slg] { "No possible solution" }
+//~ goal {
+//~     exists<T> { T: Clone }
+//~ } yields_all {
+//~     "substitution [?0 := Foo]"
+//~ }
+"#);
+    assert_eq!(failures, Vec::<String>::new());
+}
+
+#[test]
+fn failing_goal_is_checked_with_each_solver() {
+    let (_, failures) = run(r#"
+//~ goal { Bar: Clone } yields { "Unique" }
+"#);
+    assert_eq!(failures.len(), 2);
+    assert!(failures[0].starts_with("line 7 (SLG"), "{}", failures[0]);
+    assert!(
+        failures[1].starts_with("line 7 (Recursive"),
+        "{}",
+        failures[1]
+    );
+}
+
+#[test]
+fn bless_replaces_failing_expectations() {
+    let annotations = r#"
+//~ goal { Foo: Clone } yields { "Unique" }
+//~ goal { Bar: Clone } yields { "Unique" }
+//~ goal { exists<T> { T: Clone } } yields_all { }
+"#;
+    let (file, _) = run(annotations);
+    let run = file.run("<test>");
+    assert!(run.can_bless());
+    let blessed = run.bless(&file);
+    assert_eq!(
+        &blessed[PROGRAM.len()..],
+        r#"
+//~ goal { Foo: Clone } yields { "Unique" }
+//~ goal { Bar: Clone } yields { "No possible solution" }
+//~ goal { exists<T> { T: Clone } } yields_all { "substitution [?0 := Foo], lifetime constraints []" }
+"#
+    );
+    assert!(TestFile::parse(&blessed).unwrap().run("<test>").passed());
+}
+
+#[test]
+fn bless_needs_all_solutions() {
+    let (file, failures) = run(r#"
+struct Vec<T> { }
+impl<T> Clone for Vec<T> where T: Clone { }
+//~ goal { exists<T> { T: Clone } } yields_all[slg] { }
+"#);
+    assert_eq!(failures.len(), 1);
+    assert!(failures[0].starts_with("line 9 (SLG"), "{}", failures[0]);
+    assert!(
+        failures[0].ends_with("Unexpected next solution"),
+        "{}",
+        failures[0]
+    );
+    assert!(!file.run("<test>").can_bless());
+}
+
+#[test]
+fn error_msg() {
+    let (_, failures) = run(r#"
+impl Clone for Foo { }
+//~ error_msg { "overlapping impls" }
+"#);
+    assert_eq!(failures, Vec::<String>::new());
+
+    let (_, failures) = run(r#"
+//~ error_msg { "overlapping impls" }
+"#);
+    assert_eq!(failures, vec!["line 1: the program has no errors"]);
+}
+
+#[test]
+fn invalid_annotations() {
+    let parse = |annotations: &str| TestFile::parse(annotations).unwrap_err();
+    assert_eq!(
+        parse("\n//~ goal { Foo: Clone }\n"),
+        ParseError {
+            line: 2,
+            message: "expected `yields`, `yields_all` or `yields_first`".to_string()
+        }
+    );
+    assert_eq!(
+        parse("//~ goal { Foo: Clone } yields_all[recursive] { }").message,
+        "cannot test the recursive solver with yields_first or yields_all"
+    );
+    assert_eq!(
+        parse("//~ goal { Foo: Clone } yields[chalk] { \"Unique\" }").message,
+        "unknown solver `chalk`"
+    );
+    assert_eq!(
+        parse("//~ goal { Foo: Clone } yields { \"Unique }").message,
+        "invalid string"
+    );
+}
+
+#[test]
+fn invalid_goals_cannot_be_blessed() {
+    let (file, failures) = run(r#"
+//~ goal { Baz: Clone } yields { "Unique" }
+"#);
+    assert_eq!(failures.len(), 1);
+    assert!(
+        failures[0].starts_with("line 7: invalid goal"),
+        "{}",
+        failures[0]
+    );
+    assert!(!file.run("<test>").can_bless());
+}
@@ -0,0 +1,93 @@
+use super::*;
+use std::sync::{Arc, Mutex};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+const PROGRAM: &str = "
+    trait A { }
+    trait B { }
+    struct Z { }
+    struct S<X> { }
+    struct Loop { }
+
+    impl A for Z { }
+    impl<X> A for S<X> where X: B { }
+    impl B for Z { }
+    impl A for Loop where Loop: A { }
+";
+
+/// A subscriber that records the names of the events it sees.
+#[derive(Clone, Default)]
+struct EventNames {
+    names: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Subscriber for EventNames {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        self.names.lock().unwrap().push(event.metadata().name());
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Solves `goal` with `solver_choice`, returning the names of the events
+/// emitted during the solve.
+fn solve_events(solver_choice: SolverChoice, goal: &str) -> Vec<&'static str> {
+    let mut db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+    let goal = db.parse_and_lower_goal(goal).unwrap();
+    db.set_solver_choice(solver_choice);
+    let peeled_goal = goal.into_peeled_goal(db.interner());
+
+    let subscriber = EventNames::default();
+    tracing::subscriber::with_default(subscriber.clone(), || db.solve(&peeled_goal));
+    let names = subscriber.names.lock().unwrap().clone();
+    names
+}
+
+fn assert_events(names: &[&str], expected: &[&str]) {
+    for name in expected {
+        assert!(names.contains(name), "no `{}` event in {:?}", name, names);
+    }
+}
+
+#[test]
+fn slg_solver_emits_solve_events() {
+    let names = solve_events(SolverChoice::slg_default(), "S<Z>: A");
+    assert_events(&names, &["goal_entered", "clause_tried", "answer_found"]);
+}
+
+#[test]
+fn recursive_solver_emits_solve_events() {
+    let names = solve_events(SolverChoice::recursive(), "S<Z>: A");
+    assert_events(
+        &names,
+        &[
+            "goal_entered",
+            "clause_tried",
+            "answer_found",
+            "table_completed",
+        ],
+    );
+}
+
+#[test]
+fn solvers_emit_cycle_events() {
+    for solver_choice in &[SolverChoice::slg_default(), SolverChoice::recursive()] {
+        let names = solve_events(*solver_choice, "Loop: A");
+        assert_events(&names, &["cycle_detected", "table_completed"]);
+    }
+}
@@ -0,0 +1,136 @@
+use super::*;
+
+#[test]
+fn type_outlives_goal() {
+    test! {
+        program {
+            struct Foo<T> { }
+            struct Bar { }
+        }
+
+        goal {
+            forall<'a, T> {
+                T: 'a
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }\
+            ]"
+        }
+
+        goal {
+            forall<'a, T> {
+                Foo<T>: 'a
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: Foo<!1_1>: '!1_0 }\
+            ]"
+        }
+
+        goal {
+            exists<'a> {
+                Foo<Bar>: 'a
+            }
+        } yields {
+            "Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: Foo<Bar>: '^0.0 }\
+            ] }"
+        }
+    }
+}
+
+#[test]
+fn type_outlives_where_clause() {
+    test! {
+        program {
+            trait Trait { }
+            struct Foo<'a, T> where T: 'a { }
+            struct Bar<T> { }
+            impl<'a, T> Trait for Bar<&'a T> where T: 'a { }
+        }
+
+        goal {
+            forall<'a, T> {
+                WellFormed(Foo<'a, T>)
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }\
+            ]"
+        }
+
+        goal {
+            forall<'a, T> {
+                Bar<&'a T>: Trait
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }\
+            ]"
+        }
+
+        goal {
+            forall<'a, T> {
+                if (T: 'a) {
+                    T: 'a
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([for<> !1_1: '!1_0]), goal: !1_1: '!1_0 }\
+            ]"
+        }
+    }
+}
+
+#[test]
+fn type_outlives_implied_bounds() {
+    test! {
+        program {
+            trait Clone { }
+            struct Foo<T> where T: Clone { }
+        }
+
+        // An outlives assumption says nothing about whether the type is
+        // well-formed, so unlike `FromEnv(Foo<T>)` it has no implied bounds
+        goal {
+            forall<'a, T> {
+                if (Foo<T>: 'a) {
+                    T: Clone
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a, T> {
+                if (FromEnv(Foo<T>)) {
+                    T: Clone
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a, T> {
+                T: Clone
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn type_outlives_lowering() {
+    lowering_success! {
+        program {
+            trait Foo<'a> where Self: 'a { }
+            struct Bar<'a, T> where T: 'a, &'a T: 'a { }
+            impl<'a, T> Foo<'a> for Bar<'a, T> where Bar<'a, T>: 'a { }
+        }
+    }
+}
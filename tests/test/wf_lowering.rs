@@ -1,4 +1,6 @@
+use chalk_integration::db::ChalkDatabase;
 use chalk_integration::query::LoweringDatabase;
+use chalk_solve::SolverChoice;
 
 #[test]
 fn well_formed_trait_decl() {
@@ -54,7 +56,9 @@ fn cyclic_traits() {
 
             impl<T> A for T where T: B {}
         } error_msg {
-            "trait impl for `B` does not meet well-formedness requirements"
+            "trait impl for `B` does not meet well-formedness requirements: `impl<type> B for ^0.0`: \
+             where clause `Implemented(!1_0: A)` of the trait must hold, \
+             but `WellFormed(!1_0: A)` cannot be proven"
         }
     }
 
@@ -144,7 +148,8 @@ fn ill_formed_ty_decl() {
                 value: Set<K>
             }
         } error_msg {
-            "type declaration `MyType` does not meet well-formedness requirements"
+            "type declaration `MyType` does not meet well-formedness requirements: \
+             field type `Set<!1_0>` must be well-formed, but `WellFormed(Set<!1_0>)` cannot be proven"
         }
     }
 }
@@ -710,6 +715,54 @@ fn struct_sized_constraints() {
     }
 }
 
+#[test]
+fn enum_and_union_sized_constraints() {
+    // Unlike structs, every field of an enum or union must be Sized,
+    // including the last one.
+    lowering_error! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            enum E<T> {
+                A { t: T }
+            }
+        } error_msg {
+            "type declaration `E` does not meet well-formedness requirements"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            union U<T> {
+                t: T
+            }
+        } error_msg {
+            "type declaration `U` does not meet well-formedness requirements"
+        }
+    }
+
+    lowering_success! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            enum E<T> where T: Sized {
+                A { t: T },
+                B { t1: T, t2: T },
+                C
+            }
+
+            union U<T> where T: Sized {
+                t: T
+            }
+        }
+    }
+}
+
 #[test]
 fn copy_constraints() {
     lowering_error! {
@@ -724,7 +777,9 @@ fn copy_constraints() {
 
             impl<T> Copy for S<T> { }
         } error_msg {
-           "trait impl for `Copy` does not meet well-formedness requirements"
+           "trait impl for `Copy` does not meet well-formedness requirements: `impl<type> Copy for S<^0.0>`: \
+            the requirements of the well-known trait `Copy` must be met, \
+            but `Implemented(!1_0: Copy)` cannot be proven"
         }
     }
 
@@ -744,6 +799,42 @@ fn copy_constraints() {
         }
     }
 
+    lowering_success! {
+        program {
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(drop)]
+            trait Drop { }
+
+            enum E<T> where T: Copy {
+                A { t: T },
+                B
+            }
+
+            impl<T> Copy for E<T> { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(drop)]
+            trait Drop { }
+
+            enum E<T> {
+                A,
+                B { t: T }
+            }
+
+            impl<T> Copy for E<T> { }
+        } error_msg {
+           "trait impl for `Copy` does not meet well-formedness requirements"
+        }
+    }
+
     lowering_error! {
         program {
             #[lang(copy)]
@@ -833,3 +924,82 @@ fn no_unsize_impls() {
         }
     }
 }
+
+#[test]
+fn parallel_wf_checks_report_the_first_error() {
+    let program = "
+        trait Copy { }
+        struct Foo<T> where T: Copy { }
+        struct A { }
+        struct B { f: Foo<B> }
+        struct C { f: Foo<C> }
+        impl Copy for A { }
+        trait Bar where Self: Copy { }
+        impl Bar for B { }
+        impl Bar for C { }
+    ";
+    let check = |threads| {
+        let mut db = ChalkDatabase::with(program, SolverChoice::default());
+        db.set_check_threads(threads);
+        db.checked_program().unwrap_err().to_string()
+    };
+
+    let expected = check(1);
+    assert!(expected.contains("type declaration `B`"), "{}", expected);
+    for threads in 2..=4 {
+        assert_eq!(check(threads), expected);
+    }
+}
+
+#[test]
+fn ill_formed_diagnostics() {
+    lowering_error! {
+        program {
+            trait Hash { }
+            trait Foo { }
+            struct Set<K> where K: Hash { }
+
+            struct MyType<K> where Set<K>: Foo { }
+        } error_msg {
+            "type declaration `MyType` does not meet well-formedness requirements: \
+             the types in where clause `Implemented(Set<!1_0>: Foo)` must be well-formed, \
+             but `WellFormed(Set<!1_0>)` cannot be proven"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            struct S<T> {
+                t1: T,
+                t2: T
+            }
+        } error_msg {
+            "type declaration `S` does not meet well-formedness requirements: \
+             field type `!1_0` must be `Sized`, but `Implemented(!1_0: Sized)` cannot be proven"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct OnlyFoo<T> where T: Foo { }
+
+            struct MyType { }
+
+            trait Bar {
+                type Value;
+            }
+
+            impl Bar for MyType {
+                type Value = OnlyFoo<MyType>;
+            }
+        } error_msg {
+            "trait impl for `Bar` does not meet well-formedness requirements: `impl Bar for MyType`: \
+             the value of associated type `(Bar::Value)` must be well-formed and meet its bounds, \
+             but `WellFormed(OnlyFoo<MyType>)` cannot be proven"
+        }
+    }
+}
@@ -41,7 +41,7 @@ pub fn assert_same(result: &str, expected: &str) {
     let expected1: String = expected.chars().filter(|w| !w.is_whitespace()).collect();
     let result1: String = result.chars().filter(|w| !w.is_whitespace()).collect();
     assert!(!expected1.is_empty(), "Expectation cannot be empty!");
-    if !result1.starts_with(&expected1) {
+    if !chalk_integration::test_file::output_matches(result, expected) {
         let prefix = &result1[..std::cmp::min(result1.len(), expected1.len())];
         // These will never be equal, which will cause a nice error message
         // to be displayed